@@ -4,7 +4,8 @@ use std::time::Instant;
 use transcribe_rs::{
     engines::{
         parakeet::{
-            ParakeetEngine, ParakeetInferenceParams, ParakeetModelParams, TimestampGranularity,
+            ParakeetEngine, ParakeetInferenceParams, ParakeetModelParams, SegmentationParams,
+            TimestampGranularity,
         },
         whisper::{WhisperEngine, WhisperInferenceParams},
     },
@@ -18,6 +19,7 @@ fn get_audio_duration(path: &PathBuf) -> Result<f64, Box<dyn std::error::Error>>
     Ok(duration)
 }
 
+#[allow(dead_code)] // Only the variant selected in main() is constructed
 enum Engine {
     Whisper,
     Parakeet,
@@ -102,7 +104,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             // Configure Parakeet parameters with timestamp granularity
             let params = ParakeetInferenceParams {
                 timestamp_granularity: TimestampGranularity::Segment, // Options: Token, Word, Segment
-                ..Default::default()
+                segmentation: SegmentationParams::subtitles(), // Short segments for subtitles
+                restore_punctuation: true, // Split unpunctuated output at pauses
             };
 
             let result = engine.transcribe_file(&wav_path, Some(params))?;
@@ -2,13 +2,102 @@
 //!
 //! This module provides functions for reading and processing audio files
 //! to prepare them for transcription engines.
+//!
+//! Compressed formats (MP3, M4A/AAC, FLAC, Ogg/Vorbis) and arbitrary WAV
+//! variants are decoded in-process, so no external `ffmpeg` binary is needed.
 
+use std::fs::File;
 use std::path::Path;
+use std::str::FromStr;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use rubato::{
+    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+
+/// Sample rate expected by all transcription engines.
+pub const TARGET_SAMPLE_RATE: u32 = 16000;
+
+/// Number of input frames fed to the resampler per processing call.
+const RESAMPLE_CHUNK_SIZE: usize = 4096;
+
+/// How multi-channel audio is reduced to the single channel engines expect.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MixStrategy {
+    /// Average all channels together (default)
+    #[default]
+    Average,
+    /// Keep only the channel with the given zero-based index
+    Channel(usize),
+    /// Keep the channel with the highest RMS energy, useful for recordings
+    /// where one microphone picked up the speaker and the other mostly noise
+    Loudest,
+}
+
+impl FromStr for MixStrategy {
+    type Err = String;
+
+    /// Parse `average`, `loudest`, `left`, `right` or a zero-based channel index.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "average" | "avg" | "mean" => Ok(Self::Average),
+            "loudest" => Ok(Self::Loudest),
+            "left" => Ok(Self::Channel(0)),
+            "right" => Ok(Self::Channel(1)),
+            other => other.parse().map(Self::Channel).map_err(|_| {
+                format!(
+                    "Unknown mix strategy: {} (expected average, loudest or a channel number)",
+                    other
+                )
+            }),
+        }
+    }
+}
+
+/// Audio decoded from a file, before any resampling or down-mixing.
+#[derive(Debug, Clone)]
+pub struct DecodedAudio {
+    /// Interleaved samples normalized to the range [-1.0, 1.0]
+    pub samples: Vec<f32>,
+    /// Sample rate of the source in Hz
+    pub sample_rate: u32,
+    /// Number of interleaved channels
+    pub channels: usize,
+}
+
+impl DecodedAudio {
+    /// Duration of the decoded audio in seconds.
+    pub fn duration(&self) -> f64 {
+        if self.sample_rate == 0 || self.channels == 0 {
+            return 0.0;
+        }
+        (self.samples.len() / self.channels) as f64 / self.sample_rate as f64
+    }
+
+    /// Down-mix and resample to the 16 kHz mono format expected by engines.
+    pub fn resample_to_mono(
+        &self,
+        mix: MixStrategy,
+    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        let mono = downmix(&self.samples, self.channels, mix)?;
+        resample(&mono, self.sample_rate, TARGET_SAMPLE_RATE)
+    }
+}
 
 /// Read WAV file samples and convert them to the required format.
 ///
 /// This function reads a WAV file and converts it to the format expected by
-/// transcription engines: 16kHz sample rate, 16-bit samples, mono channel.
+/// transcription engines: 16kHz sample rate, mono channel, f32 samples.
+/// Any sample rate, channel count and PCM variant (8/16/24/32-bit integer or
+/// 32-bit float) is accepted; channels are averaged and the audio is
+/// resampled as needed.
 ///
 /// # Arguments
 ///
@@ -22,8 +111,8 @@ use std::path::Path;
 ///
 /// This function will return an error if:
 /// - The file cannot be opened or read
-/// - The WAV format is incorrect (not 16kHz, 16-bit, mono)
-/// - The samples cannot be converted to the expected format
+/// - The file is not a valid WAV file
+/// - The samples cannot be resampled
 ///
 /// # Examples
 ///
@@ -35,65 +124,354 @@ use std::path::Path;
 /// println!("Loaded {} samples", samples.len());
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-///
-/// # Audio Requirements
-///
-/// The input WAV file must have:
-/// - Sample rate: 16,000 Hz
-/// - Bit depth: 16 bits per sample
-/// - Channels: 1 (mono)
-/// - Format: PCM integer samples
 pub fn read_wav_samples(wav_path: &Path) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    read_wav_file(wav_path)?.resample_to_mono(MixStrategy::default())
+}
+
+/// Read a WAV file of any PCM variant into interleaved f32 samples.
+fn read_wav_file(wav_path: &Path) -> Result<DecodedAudio, Box<dyn std::error::Error>> {
     let mut reader = hound::WavReader::open(wav_path)?;
     let spec = reader.spec();
 
-    let expected_spec = hound::WavSpec {
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels as usize,
+    })
+}
+
+/// Get the duration of a WAV file in seconds.
+pub fn get_audio_duration(wav_path: &Path) -> Result<f64, Box<dyn std::error::Error>> {
+    let reader = hound::WavReader::open(wav_path)?;
+    let spec = reader.spec();
+    let duration = reader.duration() as f64 / spec.sample_rate as f64;
+    Ok(duration)
+}
+
+/// Decode an audio file of any supported container/codec into raw samples.
+///
+/// The format is detected from the file contents, using the file extension
+/// as a hint. Supported inputs include WAV (any PCM/float variant), MP3,
+/// M4A/AAC, FLAC and Ogg/Vorbis, plus Ogg/Opus with the `opus` feature.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened, the format is not
+/// recognized, or the codec has no decoder (e.g. Opus without the `opus`
+/// feature).
+pub fn decode_audio_file(path: &Path) -> Result<DecodedAudio, Box<dyn std::error::Error>> {
+    let file = File::open(path)?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        mss,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or("No decodable audio track found")?;
+
+    if track.codec_params.codec == CODEC_TYPE_OPUS {
+        // Symphonia demuxes Opus but has no decoder for it
+        let track_id = track.id;
+        let channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(0);
+        let pre_skip = track.codec_params.delay.unwrap_or(0) as usize;
+        return decode_opus(format.as_mut(), track_id, channels, pre_skip);
+    }
+
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(0);
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut samples = Vec::new();
+    let mut sample_buf: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(e)) => {
+                // Corrupt frames are skipped rather than aborting the whole file
+                log::warn!("Skipping undecodable packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        channels = spec.channels.count();
+
+        let buf = match sample_buf.as_mut() {
+            Some(buf) if buf.capacity() >= decoded.capacity() * channels => buf,
+            _ => sample_buf.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buf.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buf.samples());
+    }
+
+    if sample_rate == 0 || channels == 0 {
+        return Err("Could not determine sample rate or channel layout".into());
+    }
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate,
+        channels,
+    })
+}
+
+/// Decode the Opus packets of `track_id` with libopus.
+#[cfg(feature = "opus")]
+fn decode_opus(
+    format: &mut dyn FormatReader,
+    track_id: u32,
+    channels: usize,
+    pre_skip: usize,
+) -> Result<DecodedAudio, Box<dyn std::error::Error>> {
+    use audiopus::coder::Decoder;
+    use audiopus::packet::Packet;
+    use audiopus::{Channels, MutSignals, SampleRate};
+
+    let layout = match channels {
+        1 => Channels::Mono,
+        2 => Channels::Stereo,
+        n => return Err(format!("Opus audio with {} channels is not supported", n).into()),
+    };
+    let mut decoder = Decoder::new(SampleRate::Hz48000, layout)?;
+    // Room for the longest Opus frame (120 ms at 48 kHz)
+    let mut frame = vec![0.0f32; 5760 * channels];
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = Packet::try_from(packet.buf()).and_then(|input| {
+            decoder.decode_float(Some(input), MutSignals::try_from(&mut frame[..])?, false)
+        });
+        match decoded {
+            Ok(per_channel) => samples.extend_from_slice(&frame[..per_channel * channels]),
+            // Corrupt frames are skipped rather than aborting the whole file
+            Err(e) => log::warn!("Skipping undecodable packet: {}", e),
+        }
+    }
+
+    // The encoder's look-ahead at the start is not part of the audio
+    samples.drain(..(pre_skip * channels).min(samples.len()));
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate: 48_000,
+        channels,
+    })
+}
+
+#[cfg(not(feature = "opus"))]
+fn decode_opus(
+    _format: &mut dyn FormatReader,
+    _track_id: u32,
+    _channels: usize,
+    _pre_skip: usize,
+) -> Result<DecodedAudio, Box<dyn std::error::Error>> {
+    Err("Opus audio needs the `opus` feature (libopus); convert it to FLAC, MP3 or Ogg/Vorbis or rebuild with `--features opus`".into())
+}
+
+/// Load any supported audio file as 16 kHz mono samples.
+///
+/// This is the format expected by [`crate::TranscriptionEngine::transcribe_samples`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use transcribe_rs::audio::load_audio_file;
+/// use std::path::Path;
+///
+/// let samples = load_audio_file(Path::new("podcast.mp3"))?;
+/// println!("Loaded {:.1}s of audio", samples.len() as f32 / 16000.0);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn load_audio_file(path: &Path) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    load_audio_file_with_mix(path, MixStrategy::default())
+}
+
+/// Load any supported audio file as 16 kHz mono samples using the given
+/// channel mix strategy.
+pub fn load_audio_file_with_mix(
+    path: &Path,
+    mix: MixStrategy,
+) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    decode_audio_file(path)?.resample_to_mono(mix)
+}
+
+/// Write 16 kHz mono samples to a 16-bit PCM WAV file.
+pub fn write_wav_samples(path: &Path, samples: &[f32]) -> Result<(), Box<dyn std::error::Error>> {
+    let spec = hound::WavSpec {
         channels: 1,
-        sample_rate: 16000,
+        sample_rate: TARGET_SAMPLE_RATE,
         bits_per_sample: 16,
         sample_format: hound::SampleFormat::Int,
     };
 
-    if spec.channels != expected_spec.channels {
-        return Err(format!(
-            "Expected {} channels, found {}",
-            expected_spec.channels, spec.channels
-        )
-        .into());
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for &sample in samples {
+        let clamped = sample.clamp(-1.0, 1.0);
+        writer.write_sample((clamped * i16::MAX as f32) as i16)?;
     }
+    writer.finalize()?;
+    Ok(())
+}
 
-    if spec.sample_rate != expected_spec.sample_rate {
-        return Err(format!(
-            "Expected {} Hz sample rate, found {} Hz",
-            expected_spec.sample_rate, spec.sample_rate
-        )
-        .into());
+/// Convert any supported audio file to a 16 kHz, 16-bit, mono WAV file.
+pub fn convert_to_wav(input: &Path, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let samples = load_audio_file(input)?;
+    write_wav_samples(output, &samples)
+}
+
+/// Reduce interleaved multi-channel samples to a single channel.
+///
+/// # Errors
+///
+/// Returns an error if `MixStrategy::Channel` names a channel that does not exist.
+pub fn downmix(
+    samples: &[f32],
+    channels: usize,
+    mix: MixStrategy,
+) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    if channels <= 1 {
+        return Ok(samples.to_vec());
     }
 
-    if spec.bits_per_sample != expected_spec.bits_per_sample {
-        return Err(format!(
-            "Expected {} bits per sample, found {}",
-            expected_spec.bits_per_sample, spec.bits_per_sample
+    let pick = |channel: usize| -> Vec<f32> {
+        samples
+            .chunks_exact(channels)
+            .map(|frame| frame[channel])
+            .collect()
+    };
+
+    match mix {
+        MixStrategy::Average => Ok(samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()),
+        MixStrategy::Channel(channel) if channel < channels => Ok(pick(channel)),
+        MixStrategy::Channel(channel) => Err(format!(
+            "Requested channel {}, but audio only has {} channels",
+            channel, channels
         )
-        .into());
+        .into()),
+        MixStrategy::Loudest => {
+            let mut energy = vec![0.0f64; channels];
+            for frame in samples.chunks_exact(channels) {
+                for (acc, &s) in energy.iter_mut().zip(frame) {
+                    *acc += (s as f64) * (s as f64);
+                }
+            }
+            let loudest = energy
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(idx, _)| idx)
+                .unwrap_or(0);
+            Ok(pick(loudest))
+        }
     }
+}
 
-    if spec.sample_format != expected_spec.sample_format {
-        return Err(format!("Expected Int sample format, found {:?}", spec.sample_format).into());
+/// Resample mono audio with a band-limited sinc interpolator.
+///
+/// The resampler's filter delay is compensated, so the output is aligned with
+/// the input and has exactly `len * to_rate / from_rate` samples (rounded).
+pub fn resample(
+    samples: &[f32],
+    from_rate: u32,
+    to_rate: u32,
+) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    if from_rate == to_rate || samples.is_empty() {
+        return Ok(samples.to_vec());
     }
 
-    let samples: Result<Vec<f32>, _> = reader
-        .samples::<i16>()
-        .map(|sample| sample.map(|s| s as f32 / i16::MAX as f32))
-        .collect();
+    let ratio = to_rate as f64 / from_rate as f64;
+    let params = SincInterpolationParameters {
+        sinc_len: 256,
+        f_cutoff: 0.95,
+        interpolation: SincInterpolationType::Linear,
+        oversampling_factor: 256,
+        window: WindowFunction::BlackmanHarris2,
+    };
+    let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, RESAMPLE_CHUNK_SIZE, 1)?;
 
-    Ok(samples?)
-}
+    let expected_len = (samples.len() as f64 * ratio).round() as usize;
+    let delay = resampler.output_delay();
+    let mut output = Vec::with_capacity(expected_len + delay);
 
-/// Get the duration of a WAV file in seconds.
-pub fn get_audio_duration(wav_path: &Path) -> Result<f64, Box<dyn std::error::Error>> {
-    let reader = hound::WavReader::open(wav_path)?;
-    let spec = reader.spec();
-    let duration = reader.duration() as f64 / spec.sample_rate as f64;
-    Ok(duration)
+    let mut pos = 0;
+    while samples.len() - pos >= resampler.input_frames_next() {
+        let end = pos + resampler.input_frames_next();
+        let chunk = resampler.process(&[&samples[pos..end]], None)?;
+        output.extend_from_slice(&chunk[0]);
+        pos = end;
+    }
+
+    if pos < samples.len() {
+        let chunk = resampler.process_partial(Some(&[&samples[pos..]]), None)?;
+        output.extend_from_slice(&chunk[0]);
+    }
+
+    // Flush the samples still held back by the filter delay
+    while output.len() < expected_len + delay {
+        let chunk = resampler.process_partial(None::<&[&[f32]]>, None)?;
+        if chunk[0].is_empty() {
+            break;
+        }
+        output.extend_from_slice(&chunk[0]);
+    }
+
+    output.drain(..delay.min(output.len()));
+    output.truncate(expected_len);
+    Ok(output)
 }
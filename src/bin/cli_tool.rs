@@ -1,5 +1,4 @@
 use anyhow::{Context, Result};
-use hound;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -9,30 +8,51 @@ use std::process::{Child, Command, Stdio};
 use std::time::Instant;
 use tempfile::TempDir;
 use tokio::task;
-use transcribe_rs::TranscriptionSegment;
+use transcribe_rs::audio::MixStrategy;
+use transcribe_rs::normalize::normalize_segments;
+use transcribe_rs::punctuation::{load_restorer, restore_segments};
+use transcribe_rs::merge::{merge_chunks, ChunkTranscript, MergeParams};
+use transcribe_rs::chinese::ChineseVariant;
+use transcribe_rs::cli::{flag_value, normalize, punctuation, whisper_params, worker_args};
+use transcribe_rs::hallucination::{FilterParams, HallucinationFilter};
+use transcribe_rs::subtitle::{resegment, ResegmentParams, SubtitleFormat};
+use transcribe_rs::transcript::{Transcript, TranscriptMetadata, TRANSCRIPT_EXTENSION};
+use transcribe_rs::vad::SpeechRegion;
+use transcribe_rs::worker::dominant_language;
+use transcribe_rs::{audio, vad, TranscriptionSegment};
 
-const CHUNK_DURATION: u32 = 300; // 5 minutes
-const OVERLAP_DURATION: u32 = 10; // 10 seconds
 const NUM_WORKERS: usize = 6; // Optimized for 24GB RAM & ~900MB Model
 
 #[derive(Serialize)]
 struct WorkerRequest {
     chunk_path: String,
+    index: usize,
+    regions: Vec<SpeechRegion>,
 }
 
 #[derive(Deserialize)]
 struct WorkerResponse {
     success: bool,
     segments: Option<Vec<TranscriptionSegment>>,
+    #[serde(default)]
+    language: Option<String>,
     error: Option<String>,
 }
 
+/// Segments of one chunk and the language they were decoded in
+struct ChunkResult {
+    segments: Vec<TranscriptionSegment>,
+    language: Option<String>,
+}
+
 struct WorkerProcess {
     child: Child,
 }
 
 impl WorkerProcess {
-    fn new(model_path: &Path) -> Result<Self> {
+    /// Start a worker for `model_path`; `decoding_args` are passed on as
+    /// its decoding options
+    fn new(model_path: &Path, decoding_args: &[String]) -> Result<Self> {
         let exe_path = std::env::current_exe()
             .context("Failed to get current exe path")?
             .parent()
@@ -62,6 +82,7 @@ impl WorkerProcess {
 
         let child = Command::new(worker_cmd)
             .arg(model_path)
+            .args(decoding_args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit()) // Let worker logs go to stderr
@@ -71,9 +92,21 @@ impl WorkerProcess {
         Ok(Self { child })
     }
 
-    fn transcribe(&mut self, chunk_path: &Path) -> Result<Vec<TranscriptionSegment>> {
+    /// Transcribe `chunk`; `regions` are the speech regions of the whole
+    /// recording
+    fn transcribe(&mut self, chunk: &AudioChunk, regions: &[SpeechRegion]) -> Result<ChunkResult> {
         let request = WorkerRequest {
-            chunk_path: chunk_path.to_string_lossy().to_string(),
+            chunk_path: chunk.path.to_string_lossy().to_string(),
+            index: chunk.index,
+            // The worker only sees the chunk, so its regions are relative
+            regions: regions
+                .iter()
+                .filter(|r| r.end > chunk.start_time && r.start < chunk.end_time)
+                .map(|r| SpeechRegion {
+                    start: r.start - chunk.start_time,
+                    end: r.end - chunk.start_time,
+                })
+                .collect(),
         };
         let json_req = serde_json::to_string(&request)?;
 
@@ -90,121 +123,71 @@ impl WorkerProcess {
             .context("Failed to parse worker response")?;
 
         if response.success {
-            Ok(response.segments.unwrap_or_default())
+            Ok(ChunkResult {
+                segments: response.segments.unwrap_or_default(),
+                language: response.language,
+            })
         } else {
             anyhow::bail!("Worker error: {}", response.error.unwrap_or_default())
         }
     }
 }
 
-/// Convert input audio to 16kHz, 16-bit, Mono WAV using FFmpeg
-async fn convert_to_wav(input_path: &Path, output_path: &Path) -> Result<()> {
+/// Convert input audio to 16kHz, 16-bit, Mono WAV (decoded in-process),
+/// reducing multi-channel audio with `mix`
+fn convert_to_wav(input_path: &Path, output_path: &Path, mix: MixStrategy) -> Result<()> {
     println!("Converting {:?} to {:?}", input_path, output_path);
-    
-    let status = tokio::process::Command::new("ffmpeg")
-        .arg("-i")
-        .arg(input_path)
-        .arg("-ar")
-        .arg("16000")
-        .arg("-ac")
-        .arg("1")
-        .arg("-c:a")
-        .arg("pcm_s16le")
-        .arg(output_path)
-        .arg("-y") // Overwrite output
-        .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .await
-        .context("Failed to execute ffmpeg")?;
-
-    if !status.success() {
-        anyhow::bail!("FFmpeg conversion failed");
-    }
 
-    Ok(())
+    audio::load_audio_file_with_mix(input_path, mix)
+        .and_then(|samples| audio::write_wav_samples(output_path, &samples))
+        .map_err(|e| anyhow::anyhow!("Audio conversion failed: {}", e))
 }
 
 #[derive(Clone)]
 struct AudioChunk {
-    path: PathBuf,
-    start_time: f64,
     index: usize,
+    path: PathBuf,
+    start_time: f32,
+    end_time: f32,
 }
 
-fn split_audio(wav_path: &Path, output_dir: &Path) -> Result<Vec<AudioChunk>> {
+/// Split audio into chunk files that start and end in silence; also returns
+/// the detected speech regions. Uses the Silero model when given, else the
+/// energy-based detector
+fn split_audio(
+    wav_path: &Path,
+    output_dir: &Path,
+    vad_model: Option<&Path>,
+    chunking: &vad::ChunkPlanParams,
+) -> Result<(Vec<AudioChunk>, Vec<SpeechRegion>)> {
     println!("Splitting audio {:?}...", wav_path);
-    
-    let mut reader = hound::WavReader::open(wav_path).context("Failed to open WAV file")?;
-    let spec = reader.spec();
-    let sample_rate = spec.sample_rate;
-    
-    let samples: Vec<i16> = reader.samples::<i16>().collect::<Result<_, _>>()?;
-    let total_samples = samples.len();
-    let samples_per_chunk = (sample_rate * CHUNK_DURATION) as usize;
-    let samples_overlap = (sample_rate * OVERLAP_DURATION) as usize;
-    let step_size = samples_per_chunk - samples_overlap;
-    
-    let mut chunks = Vec::new();
 
-    let mut start_sample = 0;
-    let mut index = 0;
+    let samples = audio::read_wav_samples(wav_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read WAV file: {}", e))?;
+    let duration = samples.len() as f32 / audio::TARGET_SAMPLE_RATE as f32;
 
-    while start_sample < total_samples {
-        let end_sample = std::cmp::min(start_sample + samples_per_chunk, total_samples);
-        let chunk_samples = &samples[start_sample..end_sample];
-        
-        if chunk_samples.len() < sample_rate as usize && index > 0 {
-            break;
-        }
+    let regions = vad::detect_speech_regions(&samples, vad_model)?;
+    let planned = vad::plan_chunks(&regions, duration, chunking);
+    println!("Found {} speech regions, {} chunks", regions.len(), planned.len());
+
+    let mut chunks = Vec::new();
+    for chunk in planned {
+        let first = (chunk.start * audio::TARGET_SAMPLE_RATE as f32) as usize;
+        let last = ((chunk.end * audio::TARGET_SAMPLE_RATE as f32) as usize).min(samples.len());
+
+        let chunk_path = output_dir.join(format!("chunk_{}.wav", chunk.index));
+        audio::write_wav_samples(&chunk_path, &samples[first.min(last)..last])
+            .map_err(|e| anyhow::anyhow!("Failed to write chunk: {}", e))?;
 
-        let chunk_path = output_dir.join(format!("chunk_{}.wav", index));
-        let start_time = start_sample as f64 / sample_rate as f64;
-        
-        let mut writer = hound::WavWriter::create(&chunk_path, spec)?;
-        for sample in chunk_samples {
-            writer.write_sample(*sample)?;
-        }
-        writer.finalize()?;
-        
         chunks.push(AudioChunk {
+            index: chunk.index,
             path: chunk_path,
-            start_time,
-            index,
+            start_time: chunk.start,
+            end_time: chunk.end,
         });
-
-        start_sample += step_size;
-        index += 1;
     }
 
-    Ok(chunks)
-}
-
-fn format_timestamp(seconds: f32) -> String {
-    let hours = (seconds / 3600.0) as u32;
-    let minutes = ((seconds % 3600.0) / 60.0) as u32;
-    let secs = (seconds % 60.0) as u32;
-    let millis = ((seconds.fract()) * 1000.0) as u32;
-    format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs, millis)
-}
-
-fn cleanup_text(text: &str) -> String {
-    let mut text = text.to_string();
-    
-    // Common hallucination / typo fixes
-    let replacements = vec![
-        ("Start using a trial version of", ""),
-        ("Unicorn", ""), // Often hallucinated in silence
-        ("Amara.org", ""),
-        ("Subtitle by", ""),
-    ];
-
-    for (target, replacement) in replacements {
-        text = text.replace(target, replacement);
-    }
-    
-    text.trim().to_string()
+    Ok((chunks, regions))
 }
 
 #[tokio::main]
@@ -217,6 +200,42 @@ async fn main() -> Result<()> {
     // Default model handling with fallback search
     let default_model_name = "models/breeze-asr-25-q4_k.bin";
     let model_arg = args.get(2).map(|s| s.as_str());
+    // `None` selects the JSON transcript instead of a subtitle format
+    let format = match flag_value(&args, "--format") {
+        Some(f) if f.eq_ignore_ascii_case(TRANSCRIPT_EXTENSION) => None,
+        Some(f) => Some(f.parse::<SubtitleFormat>().map_err(anyhow::Error::msg)?),
+        None => Some(SubtitleFormat::default()),
+    };
+    let hallucination_filter = match flag_value(&args, "--rules") {
+        Some(rules) => HallucinationFilter::with_rule_file(rules.as_ref(), FilterParams::default())?,
+        None => HallucinationFilter::default(),
+    };
+    let chinese_variant = flag_value(&args, "--chinese")
+        .map(|v| v.parse::<ChineseVariant>().map_err(anyhow::Error::msg))
+        .transpose()?;
+    let mix = match flag_value(&args, "--mix") {
+        Some(mix) => mix.parse::<MixStrategy>().map_err(anyhow::Error::msg)?,
+        None => MixStrategy::default(),
+    };
+    // Checked here so a typo fails before any worker is started
+    whisper_params(&args)?;
+    let decoding_args = worker_args(&args);
+    let normalize = normalize(&args);
+    let mut restorer = punctuation(&args)
+        .map(|params| load_restorer(&params))
+        .transpose()
+        .context("Failed to load punctuation model")?;
+    let vad_model = flag_value(&args, "--vad-model").map(PathBuf::from);
+    let mut chunking = vad::ChunkPlanParams::default();
+    if let Some(secs) = flag_value(&args, "--min-chunk") {
+        chunking.min_chunk_duration = secs.parse().context("Invalid --min-chunk seconds")?;
+    }
+    if let Some(secs) = flag_value(&args, "--max-chunk") {
+        chunking.max_chunk_duration = secs.parse().context("Invalid --max-chunk seconds")?;
+    }
+    if chunking.min_chunk_duration > chunking.max_chunk_duration {
+        anyhow::bail!("--min-chunk must not exceed --max-chunk");
+    }
     
     // We check a list of potential bases for the model
     let mut potential_models = Vec::new();
@@ -267,20 +286,21 @@ async fn main() -> Result<()> {
     
     // Determine filenames
     let file_stem = input_path.file_stem().context("Invalid filename")?;
-    let output_srt = output_dir.join(file_stem).with_extension("srt");
+    let output_subtitles = output_dir
+        .join(file_stem)
+        .with_extension(format.map_or(TRANSCRIPT_EXTENSION, |f| f.extension()));
     let output_txt = output_dir.join(file_stem).with_extension("txt");
 
     // Temp setup
     let temp_dir = TempDir::new()?;
     let converted_path = temp_dir.path().join("converted.wav");
 
-    // 1. Convert
-    convert_to_wav(&input_path, &converted_path).await?;
-
-    // 2. Split
+    // 1. Convert & 2. Split
     let temp_path = temp_dir.path().to_path_buf();
-    let chunks = task::spawn_blocking(move || {
-        split_audio(&converted_path, &temp_path)
+    let source_path = input_path.clone();
+    let (chunks, speech_regions) = task::spawn_blocking(move || {
+        convert_to_wav(&source_path, &converted_path, mix)?;
+        split_audio(&converted_path, &temp_path, vad_model.as_deref(), &chunking)
     }).await??;
 
     if chunks.is_empty() {
@@ -294,49 +314,32 @@ async fn main() -> Result<()> {
     
     for i in 0..NUM_WORKERS {
         println!("Initializing Worker {}...", i + 1);
-        let worker = WorkerProcess::new(&model_path)?;
+        let worker = WorkerProcess::new(&model_path, &decoding_args)?;
         pool_tx.send_blocking(worker)?;
     }
     println!("All workers ready.");
 
     // 4. Distribute Work (Worker Pool Pattern)
-    let results: Vec<Result<(AudioChunk, Vec<TranscriptionSegment>)>> = chunks.par_iter().map(|chunk| {
+    let results: Vec<Result<(AudioChunk, ChunkResult)>> = chunks.par_iter().map(|chunk| {
         let mut worker = pool_rx.recv_blocking().context("Failed to acquire worker from pool")?;
         print!("."); 
         std::io::stdout().flush().ok();
-        let res = worker.transcribe(&chunk.path).map(|segments| (chunk.clone(), segments));
+        let res = worker.transcribe(chunk, &speech_regions).map(|result| (chunk.clone(), result));
         pool_tx.send_blocking(worker).ok();
         res
     }).collect();
 
     println!("\nTranscription finished. Merging results...");
 
-    let mut srt_file = fs::File::create(&output_srt)?;
-    let mut global_index = 1;
-    let mut all_text = String::new();
-    
+    let mut chunk_transcripts = Vec::new();
+    let mut chunk_languages = Vec::new();
     for result in results {
        match result {
-           Ok((chunk, segments)) => {
-               for segment in segments {
-                    if chunk.index > 0 && segment.start < OVERLAP_DURATION as f32 { continue; }
-
-                    let abs_start = chunk.start_time as f32 + segment.start;
-                    let abs_end = chunk.start_time as f32 + segment.end;
-                    let text = cleanup_text(&segment.text);
-
-                    if text.is_empty() { continue; }
-
-                    writeln!(srt_file, "{}", global_index)?;
-                    writeln!(srt_file, "{} --> {}", format_timestamp(abs_start), format_timestamp(abs_end))?;
-                    writeln!(srt_file, "{}", text)?;
-                    writeln!(srt_file)?;
-
-                    if !all_text.is_empty() { all_text.push('\n'); }
-                    all_text.push_str(&text);
-
-                    global_index += 1;
+           Ok((chunk, result)) => {
+               if let Some(language) = result.language {
+                   chunk_languages.push((language, chunk.end_time - chunk.start_time));
                }
+               chunk_transcripts.push(ChunkTranscript::from_relative(chunk.start_time, chunk.end_time, result.segments));
            },
            Err(e) => {
                eprintln!("\n⚠️  Error processing chunk: {}", e);
@@ -344,11 +347,44 @@ async fn main() -> Result<()> {
        }
     }
 
+    // 5. Merge overlapping chunks and remove hallucinations
+    let merged = merge_chunks(chunk_transcripts, &MergeParams::default());
+    let (mut segments, report) = hallucination_filter.filter(merged, Some(&speech_regions));
+    if !report.is_empty() {
+        println!("\n🧹 Removed {} hallucinations:\n{}", report.removals.len(), report);
+    }
+    if let Some(restorer) = restorer.as_mut() {
+        let inserted = restore_segments(restorer.as_mut(), &mut segments)?;
+        println!("Restored {} punctuation marks", inserted);
+    }
+    if let Some(variant) = chinese_variant {
+        variant.converter().convert_segments(&mut segments);
+    }
+    if let Some(normalize) = &normalize {
+        normalize_segments(&mut segments, normalize);
+    }
+
+    // Subtitles get short, readable cues; the TXT and JSON keep whole segments
+    let output = match format {
+        Some(format) => format.render(&resegment(&segments, &ResegmentParams::default())),
+        None => {
+            let metadata = TranscriptMetadata {
+                engine: Some("whisper".to_string()),
+                model: Some(model_path.display().to_string()),
+                language: dominant_language(&chunk_languages),
+                duration: chunks.last().map(|chunk| chunk.end_time),
+            };
+            Transcript::from_segments(&segments, metadata).to_json()?
+        }
+    };
+    fs::write(&output_subtitles, output)?;
+    let all_text = segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join("\n");
+
     // 6. Output TXT if requested
     // 6. Output TXT (Always)
     fs::write(&output_txt, all_text)?;
     println!("Saved text to {:?}", output_txt);
-    println!("Saved SRT to {:?}", output_srt);
+    println!("Saved subtitles to {:?}", output_subtitles);
 
     // 7. Archive Input File
     let completed_dir = PathBuf::from("completed");
@@ -1,21 +1,59 @@
 use actix_multipart::Multipart;
+use actix_web::http::StatusCode;
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 use futures::{StreamExt, TryStreamExt};
-use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::future::Future;
 use std::sync::{Arc, Mutex};
-use async_channel;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
 use uuid::Uuid;
 
-use transcribe_rs::worker::{Task, TaskMap, TaskStatus, Worker};
+use serde::Deserialize;
+use transcribe_rs::audio::{load_audio_file_with_mix, MixStrategy, TARGET_SAMPLE_RATE};
+use transcribe_rs::chinese::ChineseVariant;
+use transcribe_rs::engines::parakeet::{
+    ParakeetEngine, ParakeetInferenceParams, SegmentationParams, TimestampGranularity,
+};
+use transcribe_rs::engines::whisper::WhisperEngine;
+use transcribe_rs::openai_compat::{self, render_response, AudioTask, ResponseFormat};
+use transcribe_rs::subtitle::{resegment, ResegmentParams, SubtitleFormat};
+use transcribe_rs::transcript::{Transcript, TranscriptMetadata, TRANSCRIPT_CONTENT_TYPE, TRANSCRIPT_EXTENSION};
+use transcribe_rs::hallucination::{FilterParams, HallucinationFilter};
+use transcribe_rs::store::{FileTaskStore, RetentionPolicy, TaskStore};
+use transcribe_rs::webhook::{WebhookParams, WebhookSender};
+use transcribe_rs::worker::{
+    resume_webhooks, transcribe_with_engine, ParallelTranscriptionParams, Task, TaskCancellations,
+    TaskStatus, Worker,
+};
+use transcribe_rs::TranscriptionEngine;
 
 const DEFAULT_NUM_WORKERS: usize = 3;
+const DEFAULT_TASK_DIR: &str = "tasks";
+/// How often task event streams check the store for changes
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Longest silence on a task event stream before a keep-alive comment
+const EVENT_KEEP_ALIVE: Duration = Duration::from_secs(15);
+/// How often finished tasks are checked against the retention limits
+const RETENTION_INTERVAL: Duration = Duration::from_secs(600);
 
 struct AppState {
-    tasks: TaskMap,
+    tasks: Arc<dyn TaskStore>,
     sender: async_channel::Sender<String>,
+    /// Tokens of the tasks workers are running
+    cancellations: TaskCancellations,
+    /// Checks the `callback_url` of uploads; `None` when webhooks are disabled
+    webhooks: Option<WebhookSender>,
+    /// Whisper engines kept loaded for the OpenAI-compatible routes
+    whisper: Arc<WhisperModels>,
+    /// Parakeet model, served for OpenAI `model` names starting with `parakeet`
+    parakeet: Option<Arc<ParakeetModel>>,
+    /// Limits the synchronous OpenAI-compatible routes to one transcription
+    /// (and one loaded Whisper engine) per worker
+    openai_permits: Arc<Semaphore>,
+    params: ParallelTranscriptionParams,
 }
 
 async fn index() -> HttpResponse {
@@ -37,7 +75,7 @@ async fn index() -> HttpResponse {
     <body>
         <div class="container">
             <h2>Upload Audio for Transcription</h2>
-            <input type="file" id="fileInput" accept=".wav,.mp3,.m4a">
+            <input type="file" id="fileInput" accept=".wav,.mp3,.m4a,.flac,.ogg,.opus">
             <button onclick="uploadFile()">Upload</button>
             <div id="status"></div>
         </div>
@@ -77,27 +115,33 @@ async fn index() -> HttpResponse {
                 }
             }
 
-            async function pollStatus(taskId) {
+            function pollStatus(taskId) {
                 const statusDiv = document.getElementById('status');
                 
-                const interval = setInterval(async () => {
-                    try {
-                        const res = await fetch(`/status/${taskId}`);
-                        const data = await res.json();
-                        
-                        if (data.status === 'Completed') {
-                            clearInterval(interval);
-                            statusDiv.innerHTML = `Transcription Completed!<br><a href="/download/${taskId}" target="_blank">Download SRT</a>`;
-                        } else if (data.status.startsWith('Failed')) {
-                            clearInterval(interval);
-                            statusDiv.textContent = 'Failed: ' + data.status;
-                        } else {
-                            statusDiv.textContent = `Status: ${data.status}...`;
-                        }
-                    } catch (e) {
-                        console.error(e);
+                // The server pushes the task whenever its status or progress changes
+                const events = new EventSource(`/tasks/${taskId}/events`);
+                events.onmessage = (event) => {
+                    const data = JSON.parse(event.data);
+
+                    if (data.status === 'Completed') {
+                        events.close();
+                        statusDiv.innerHTML = `Transcription Completed!<br><a href="/download/${taskId}" target="_blank">Download SRT</a> | <a href="/download/${taskId}?format=vtt" target="_blank">Download WebVTT</a> | <a href="/download/${taskId}?format=ass" target="_blank">Download ASS</a> | <a href="/download/${taskId}?format=json" target="_blank">Download JSON</a>`;
+                    } else if (data.status === 'Cancelled') {
+                        events.close();
+                        statusDiv.textContent = 'Cancelled';
+                    } else if (data.status.Failed !== undefined) {
+                        events.close();
+                        statusDiv.textContent = 'Failed: ' + data.status.Failed;
+                    } else if (data.progress) {
+                        const p = data.progress;
+                        const percent = Math.floor(100 * p.audio_processed / Math.max(p.audio_duration, 1));
+                        const eta = p.eta_seconds == null ? '' : `, about ${Math.ceil(p.eta_seconds)} s left`;
+                        statusDiv.textContent = `Status: ${data.status} (${p.chunks_done}/${p.chunks_total} chunks, ${percent}%${eta})...`;
+                    } else {
+                        statusDiv.textContent = `Status: ${data.status}...`;
                     }
-                }, 2000);
+                };
+                events.onerror = () => events.close();
             }
         </script>
     </body>
@@ -111,16 +155,31 @@ async fn upload(
     mut payload: Multipart,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let mut task_id = String::new();
+    let mut uploads = Vec::new();
+    let mut callback_url = None;
 
     while let Ok(Some(mut field)) = payload.try_next().await {
         let content_disposition = field.content_disposition();
+
+        // The only text field is the webhook URL; everything else is audio
+        if content_disposition.get_filename().is_none()
+            && content_disposition.get_name() == Some("callback_url")
+        {
+            let mut bytes = Vec::new();
+            while let Some(chunk) = field.next().await {
+                bytes.extend_from_slice(&chunk?);
+            }
+            let url = String::from_utf8(bytes)
+                .map_err(|_| actix_web::error::ErrorBadRequest("callback_url is not valid UTF-8"))?;
+            callback_url = Some(url.trim().to_string()).filter(|url| !url.is_empty());
+            continue;
+        }
+
         let filename = content_disposition
             .get_filename()
             .map_or_else(|| Uuid::new_v4().to_string(), |f| f.to_string());
 
         let id = Uuid::new_v4().to_string();
-        task_id = id.clone();
         
         let filepath = PathBuf::from(format!("uploads/{}", id)); // Use ID as filename to avoid collisions
         // Ensure uploads dir exists
@@ -139,20 +198,34 @@ async fn upload(
                 .await??;
         }
 
-        // Create Task
-        let task = Task {
-            id: id.clone(),
-            status: TaskStatus::Queued,
-            original_filename: filename,
-            file_path: filepath,
-            result_srt: None,
+        uploads.push(Task::new(id, filename, filepath));
+    }
+
+    // The callback may follow the file in the form, so tasks are queued last
+    if let Some(url) = &callback_url {
+        let rejection = if let Some(webhooks) = &data.webhooks {
+            webhooks.validate_url(url).await.err().map(|e| e.to_string())
+        } else {
+            Some("Webhooks are not enabled on this server (set WEBHOOK_SECRET)".to_string())
         };
+        if let Some(message) = rejection {
+            for task in &uploads {
+                fs::remove_file(&task.file_path).ok();
+            }
+            return Err(actix_web::error::ErrorBadRequest(message));
+        }
+    }
 
-        // Add to map
-        data.tasks.lock().unwrap().insert(id.clone(), task);
+    let mut task_id = String::new();
+    for mut task in uploads {
+        task.callback_url = callback_url.clone();
+        task_id = task.id.clone();
+
+        // Persist before queueing so a restart picks it up again
+        data.tasks.insert(task).map_err(actix_web::error::ErrorInternalServerError)?;
 
         // Send to worker
-        data.sender.send(id).await.expect("Worker channel closed");
+        data.sender.send(task_id.clone()).await.expect("Worker channel closed");
     }
 
     Ok(HttpResponse::Ok().json(serde_json::json!({ "task_id": task_id })))
@@ -160,30 +233,437 @@ async fn upload(
 
 async fn get_status(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
     let task_id = path.into_inner();
-    let tasks = data.tasks.lock().unwrap();
 
-    if let Some(task) = tasks.get(&task_id) {
-        HttpResponse::Ok().json(task)
+    match data.tasks.get(&task_id) {
+        Ok(Some(task)) => HttpResponse::Ok().json(task),
+        Ok(None) => HttpResponse::NotFound().body("Task not found"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Server-Sent Events stream of a task. The status JSON is sent whenever it
+/// changes, and the stream ends once the task finishes or is deleted. A
+/// `: keep-alive` comment is sent while nothing changes, so proxies and
+/// clients do not drop a stream that waits in the queue.
+async fn task_events(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let task_id = path.into_inner();
+
+    match data.tasks.get(&task_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Task not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let tasks = data.tasks.clone();
+    // The state is the last JSON sent, or `None` once the stream is over
+    let events = futures::stream::unfold(Some(String::new()), move |last| {
+        let tasks = tasks.clone();
+        let task_id = task_id.clone();
+        async move {
+            let last = last?;
+            let quiet_since = Instant::now();
+            loop {
+                let task = match tasks.get(&task_id) {
+                    Ok(Some(task)) => task,
+                    Ok(None) => return None,
+                    Err(e) => return Some((Err(e), None)),
+                };
+                let json = match serde_json::to_string(&task) {
+                    Ok(json) => json,
+                    Err(e) => return Some((Err(e.into()), None)),
+                };
+                if json != last {
+                    let finished = !matches!(task.status, TaskStatus::Queued | TaskStatus::Processing);
+                    let event = web::Bytes::from(format!("data: {}\n\n", json));
+                    return Some((Ok(event), (!finished).then_some(json)));
+                }
+                if quiet_since.elapsed() >= EVENT_KEEP_ALIVE {
+                    return Some((Ok(web::Bytes::from_static(b": keep-alive\n\n")), Some(last)));
+                }
+                actix_web::rt::time::sleep(EVENT_POLL_INTERVAL).await;
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+/// Cancel a queued or running task, or delete a finished one together with
+/// its result. The uploaded audio is removed in both cases.
+///
+/// A running task stays `Cancelled` until its worker stops decoding, which
+/// then removes the record; any other task is removed right away.
+async fn delete_task(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let task_id = path.into_inner();
+
+    let task = match data.tasks.get(&task_id) {
+        Ok(Some(task)) => task,
+        Ok(None) => return HttpResponse::NotFound().body("Task not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let task = if matches!(task.status, TaskStatus::Queued | TaskStatus::Processing) {
+        // Workers skip cancelled tasks still waiting in the queue, and a
+        // worker that already holds the task removes it once it lets go
+        if let Err(e) = data.tasks.set_status(&task_id, TaskStatus::Cancelled) {
+            return HttpResponse::InternalServerError().body(e.to_string());
+        }
+        if data.cancellations.cancel(&task_id) {
+            println!("Cancelling running task: {}", task_id);
+        } else if let Err(e) = data.tasks.remove(&task_id) {
+            return HttpResponse::InternalServerError().body(e.to_string());
+        }
+        Task { status: TaskStatus::Cancelled, ..task }
     } else {
-        HttpResponse::NotFound().body("Task not found")
+        match data.tasks.remove(&task_id) {
+            Ok(Some(task)) => task,
+            Ok(None) => return HttpResponse::NotFound().body("Task not found"),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        }
+    };
+
+    // Audio is decoded into memory up front, so a running task can lose its file
+    match web::block({
+        let path = task.file_path.clone();
+        move || fs::remove_file(path)
+    })
+    .await
+    {
+        Ok(Err(e)) if e.kind() != std::io::ErrorKind::NotFound => {
+            eprintln!("Failed to delete upload of task {}: {}", task_id, e);
+        }
+        Err(e) => eprintln!("Failed to delete upload of task {}: {}", task_id, e),
+        _ => {}
+    }
+
+    HttpResponse::Ok().json(task)
+}
+
+#[derive(Deserialize)]
+struct DownloadQuery {
+    /// Output format: `srt` (default), `vtt`, `ass` or `json`
+    format: Option<String>,
+    /// Convert Chinese text to `s`, `t`, `tw` or `hk`
+    chinese: Option<String>,
+}
+
+/// Format of a downloaded result
+enum DownloadFormat {
+    Subtitle(SubtitleFormat),
+    /// Versioned JSON transcript with metadata
+    Json,
+}
+
+impl DownloadFormat {
+    fn parse(s: Option<&str>) -> Result<Self, String> {
+        match s {
+            None => Ok(Self::Subtitle(SubtitleFormat::default())),
+            Some(s) if s.eq_ignore_ascii_case(TRANSCRIPT_EXTENSION) => Ok(Self::Json),
+            Some(s) => s.parse().map(Self::Subtitle),
+        }
     }
 }
 
-async fn download_srt(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+async fn download_result(
+    path: web::Path<String>,
+    query: web::Query<DownloadQuery>,
+    data: web::Data<AppState>,
+) -> impl Responder {
     let task_id = path.into_inner();
-    let tasks = data.tasks.lock().unwrap();
-
-    if let Some(task) = tasks.get(&task_id) {
-        if let Some(srt) = &task.result_srt {
-            HttpResponse::Ok()
-                .content_type("text/plain")
-                .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.srt\"", task.id)))
-                .body(srt.clone())
-        } else {
-            HttpResponse::BadRequest().body("Result not ready")
+    let format = match DownloadFormat::parse(query.format.as_deref()) {
+        Ok(format) => format,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let chinese_variant = match query.chinese.as_deref().map(str::parse::<ChineseVariant>) {
+        None => None,
+        Some(Ok(variant)) => Some(variant),
+        Some(Err(e)) => return HttpResponse::BadRequest().body(e),
+    };
+
+    let task = match data.tasks.get(&task_id) {
+        Ok(Some(task)) => task,
+        Ok(None) => return HttpResponse::NotFound().body("Task not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let mut transcript = match data.tasks.result(&task_id) {
+        Ok(Some(transcript)) => transcript,
+        Ok(None) => return HttpResponse::BadRequest().body("Result not ready"),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    if let Some(variant) = chinese_variant {
+        variant.converter().convert_transcript(&mut transcript);
+    }
+    let (content_type, extension, body) = match format {
+        DownloadFormat::Subtitle(format) => (
+            format.content_type(),
+            format.extension(),
+            format.render(&resegment(&transcript.to_segments(), &ResegmentParams::default())),
+        ),
+        DownloadFormat::Json => match transcript.to_json() {
+            Ok(json) => (TRANSCRIPT_CONTENT_TYPE, TRANSCRIPT_EXTENSION, json),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+    };
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.{}\"", task.id, extension),
+        ))
+        .body(body)
+}
+
+/// Fields of an OpenAI audio API request
+#[derive(Default)]
+struct AudioRequest {
+    file: Option<tempfile::NamedTempFile>,
+    model: Option<String>,
+    language: Option<String>,
+    prompt: Option<String>,
+    temperature: Option<f32>,
+    response_format: ResponseFormat,
+    granularities: Vec<openai_compat::TimestampGranularity>,
+}
+
+/// Error body in the format OpenAI clients parse
+fn openai_error(status: StatusCode, message: impl ToString) -> HttpResponse {
+    let error_type = if status.is_server_error() { "server_error" } else { "invalid_request_error" };
+    HttpResponse::build(status).json(serde_json::json!({
+        "error": {
+            "message": message.to_string(),
+            "type": error_type,
+            "param": null,
+            "code": null,
+        }
+    }))
+}
+
+async fn read_audio_request(mut payload: Multipart) -> Result<AudioRequest, actix_web::Error> {
+    let mut request = AudioRequest::default();
+
+    while let Some(mut field) = payload.try_next().await? {
+        let name = field.content_disposition().get_name().unwrap_or_default().to_string();
+
+        // Stream the audio into a temporary file that lives as long as the request
+        if name == "file" {
+            let mut f = web::block(tempfile::NamedTempFile::new).await??;
+            while let Some(chunk) = field.next().await {
+                let data = chunk?;
+                f = web::block(move || f.write_all(&data).map(|_| f)).await??;
+            }
+            request.file = Some(f);
+            continue;
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            bytes.extend_from_slice(&chunk?);
+        }
+        let value = String::from_utf8(bytes)
+            .map_err(|_| actix_web::error::ErrorBadRequest(format!("Field {} is not valid UTF-8", name)))?;
+        match name.as_str() {
+            "model" => request.model = Some(value),
+            "language" if !value.trim().is_empty() => request.language = Some(value.trim().to_string()),
+            "prompt" if !value.is_empty() => request.prompt = Some(value),
+            "temperature" => {
+                let temperature = value.trim().parse().map_err(|_| {
+                    actix_web::error::ErrorBadRequest(format!("Invalid temperature: {}", value))
+                })?;
+                request.temperature = Some(temperature);
+            }
+            "response_format" => {
+                request.response_format = value.parse().map_err(actix_web::error::ErrorBadRequest)?;
+            }
+            "timestamp_granularities[]" | "timestamp_granularities" => {
+                request.granularities.push(value.parse().map_err(actix_web::error::ErrorBadRequest)?);
+            }
+            // Unsupported options such as `stream` are ignored
+            _ => {}
         }
+    }
+
+    Ok(request)
+}
+
+/// Whisper engines served on the OpenAI routes. Engines are loaded when no
+/// idle one is left and kept for later requests, so at most one engine per
+/// `openai_permits` permit is ever loaded.
+struct WhisperModels {
+    path: PathBuf,
+    idle: Mutex<Vec<WhisperEngine>>,
+}
+
+impl WhisperModels {
+    fn new(path: PathBuf) -> Self {
+        Self { path, idle: Mutex::new(Vec::new()) }
+    }
+
+    fn transcribe(&self, audio_path: &Path, params: &ParallelTranscriptionParams) -> anyhow::Result<Transcript> {
+        let idle = self.idle.lock().map_err(|_| anyhow::anyhow!("Whisper engines are poisoned"))?.pop();
+        let mut engine = match idle {
+            Some(engine) => engine,
+            None => {
+                let mut engine = WhisperEngine::new();
+                engine
+                    .load_model(&self.path)
+                    .map_err(|e| anyhow::anyhow!("Failed to load Whisper model: {}", e))?;
+                engine
+            }
+        };
+        let result = transcribe_with_engine(&mut engine, audio_path, &self.path, params);
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.push(engine);
+        }
+        result
+    }
+}
+
+/// Parakeet model served on the OpenAI routes. The engine is loaded on the
+/// first request and kept for later ones.
+struct ParakeetModel {
+    dir: PathBuf,
+    engine: Mutex<Option<ParakeetEngine>>,
+}
+
+impl ParakeetModel {
+    fn new(dir: PathBuf) -> Self {
+        Self { dir, engine: Mutex::new(None) }
+    }
+
+    /// Transcribe into sentence-sized segments.
+    fn transcribe(&self, audio_path: &Path, mix: MixStrategy) -> anyhow::Result<Transcript> {
+        let samples = load_audio_file_with_mix(audio_path, mix).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let duration = samples.len() as f32 / TARGET_SAMPLE_RATE as f32;
+
+        let mut engine = self.engine.lock().map_err(|_| anyhow::anyhow!("Parakeet engine is poisoned"))?;
+        if engine.is_none() {
+            let mut loaded = ParakeetEngine::new();
+            loaded
+                .load_model(&self.dir)
+                .map_err(|e| anyhow::anyhow!("Failed to load Parakeet model: {}", e))?;
+            *engine = Some(loaded);
+        }
+        let params = ParakeetInferenceParams {
+            timestamp_granularity: TimestampGranularity::Segment,
+            segmentation: SegmentationParams::subtitles(),
+            restore_punctuation: true,
+        };
+        let result = engine
+            .as_mut()
+            .expect("engine loaded above")
+            .transcribe_samples(samples, Some(params))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        let metadata = TranscriptMetadata {
+            engine: Some("parakeet".to_string()),
+            model: Some(self.dir.display().to_string()),
+            language: None,
+            duration: Some(duration),
+        };
+        Ok(Transcript::from_result(&result, metadata))
+    }
+}
+
+/// Transcribe the audio of an OpenAI request with the model it names.
+async fn transcribe_openai(
+    data: web::Data<AppState>,
+    request: AudioRequest,
+    file: tempfile::NamedTempFile,
+    task: AudioTask,
+) -> Result<Transcript, HttpResponse> {
+    let model = request.model.unwrap_or_default();
+    let job: Box<dyn FnOnce() -> anyhow::Result<Transcript> + Send> = if model.starts_with("parakeet") {
+        let Some(parakeet) = data.parakeet.clone() else {
+            return Err(openai_error(StatusCode::NOT_FOUND, format!("Model {} is not available", model)));
+        };
+        if task == AudioTask::Translate {
+            return Err(openai_error(StatusCode::BAD_REQUEST, "Parakeet models cannot translate"));
+        }
+        let mix = data.params.mix;
+        Box::new(move || parakeet.transcribe(file.path(), mix))
     } else {
-        HttpResponse::NotFound().body("Task not found")
+        // Any other model name (whisper-1, gpt-4o-transcribe, ...) is served by Whisper
+        let mut params = data.params.clone();
+        if request.language.is_some() {
+            params.whisper.language = request.language;
+        }
+        if let Some(temperature) = request.temperature {
+            params.whisper.temperature = temperature;
+        }
+        params.whisper.initial_prompt = request.prompt;
+        params.whisper.translate = task == AudioTask::Translate;
+        params.whisper.token_timestamps |= request
+            .granularities
+            .contains(&openai_compat::TimestampGranularity::Word);
+        let whisper = data.whisper.clone();
+        Box::new(move || whisper.transcribe(file.path(), &params))
+    };
+
+    // Requests beyond the worker count wait here for an engine to be free
+    let _permit = data
+        .openai_permits
+        .acquire()
+        .await
+        .map_err(|e| openai_error(StatusCode::SERVICE_UNAVAILABLE, e))?;
+    match web::block(job).await {
+        Ok(Ok(transcript)) => Ok(transcript),
+        Ok(Err(e)) => Err(openai_error(StatusCode::INTERNAL_SERVER_ERROR, e)),
+        Err(e) => Err(openai_error(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// Read an OpenAI audio request, transcribe it with `transcribe` and render
+/// the transcript in the requested format.
+async fn openai_audio<F, Fut>(payload: Multipart, task: AudioTask, transcribe: F) -> HttpResponse
+where
+    F: FnOnce(AudioRequest, tempfile::NamedTempFile) -> Fut,
+    Fut: Future<Output = Result<Transcript, HttpResponse>>,
+{
+    let mut request = match read_audio_request(payload).await {
+        Ok(request) => request,
+        Err(e) => return openai_error(StatusCode::BAD_REQUEST, e),
+    };
+    let Some(file) = request.file.take() else {
+        return openai_error(StatusCode::BAD_REQUEST, "Missing required field: file");
+    };
+    let response_format = request.response_format;
+    let granularities = request.granularities.clone();
+
+    let transcript = match transcribe(request, file).await {
+        Ok(transcript) => transcript,
+        Err(response) => return response,
+    };
+    match render_response(&transcript, response_format, task, &granularities) {
+        Ok((content_type, body)) => HttpResponse::Ok().content_type(content_type).body(body),
+        Err(e) => openai_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+async fn openai_transcriptions(payload: Multipart, data: web::Data<AppState>) -> HttpResponse {
+    let task = AudioTask::Transcribe;
+    openai_audio(payload, task, |request, file| transcribe_openai(data, request, file, task)).await
+}
+
+async fn openai_translations(payload: Multipart, data: web::Data<AppState>) -> HttpResponse {
+    let task = AudioTask::Translate;
+    openai_audio(payload, task, |request, file| transcribe_openai(data, request, file, task)).await
+}
+
+/// Parsed value of an environment variable; a value that does not parse
+/// stops startup instead of silently falling back to the default
+fn env_parse<T: std::str::FromStr>(name: &str) -> std::io::Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(name) {
+        Ok(value) => value.trim().parse().map(Some).map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("Invalid {} {:?}: {}", name, value, e))
+        }),
+        Err(_) => Ok(None),
     }
 }
 
@@ -201,25 +681,153 @@ async fn main() -> std::io::Result<()> {
     println!("Server starting...");
     println!("Using model: {}", model_path);
     println!("Number of workers: {}", num_workers);
+    let parakeet_model = std::env::var("PARAKEET_MODEL").ok().map(PathBuf::from);
+    if let Some(dir) = &parakeet_model {
+        println!("Parakeet model: {}", dir.display());
+    }
 
-    // Shared state
-    let tasks: TaskMap = Arc::new(Mutex::new(HashMap::new()));
+    // Tasks and results persist on disk across restarts
+    let task_dir = std::env::var("TASK_DIR").unwrap_or_else(|_| DEFAULT_TASK_DIR.to_string());
+    println!("Task directory: {}", task_dir);
+    let tasks: Arc<dyn TaskStore> = Arc::new(
+        FileTaskStore::open(&task_dir).map_err(std::io::Error::other)?,
+    );
     let (tx, rx) = async_channel::bounded(100);
+    let cancellations = TaskCancellations::new();
+
+    let mut params = ParallelTranscriptionParams::default();
+    if let Ok(mix) = std::env::var("AUDIO_MIX") {
+        println!("Channel mix: {}", mix);
+        params.mix = mix
+            .parse::<MixStrategy>()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    }
+
+    // Silero VAD model and chunk bounds (seconds); defaults use the energy detector
+    if let Ok(vad_model) = std::env::var("VAD_MODEL") {
+        println!("VAD model: {}", vad_model);
+        params.vad_model = Some(PathBuf::from(vad_model));
+    }
+    if let Some(secs) = env_parse("MIN_CHUNK_SECONDS")? {
+        params.chunking.min_chunk_duration = secs;
+    }
+    if let Some(secs) = env_parse("MAX_CHUNK_SECONDS")? {
+        params.chunking.max_chunk_duration = secs;
+    }
+    if params.chunking.min_chunk_duration > params.chunking.max_chunk_duration {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "MIN_CHUNK_SECONDS must not exceed MAX_CHUNK_SECONDS",
+        ));
+    }
+    println!(
+        "Chunk length: {}s - {}s",
+        params.chunking.min_chunk_duration, params.chunking.max_chunk_duration
+    );
+
+    // Extra hallucination rules on top of the bundled ones
+    if let Ok(rules_path) = std::env::var("HALLUCINATION_RULES") {
+        println!("Using hallucination rules: {}", rules_path);
+        let filter = HallucinationFilter::with_rule_file(rules_path.as_ref(), FilterParams::default())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        params.hallucination_filter = Some(filter);
+    }
+
+    // Webhooks are signed, so they are only offered with a secret
+    let webhooks = match std::env::var("WEBHOOK_SECRET") {
+        Ok(secret) if !secret.is_empty() => {
+            println!("Webhooks enabled");
+            // Comma-separated hosts that may receive webhooks on private addresses
+            let allowed_hosts = std::env::var("WEBHOOK_ALLOWED_HOSTS")
+                .map(|hosts| hosts.split(',').map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).collect())
+                .unwrap_or_default();
+            let params = WebhookParams { allowed_hosts, ..Default::default() };
+            Some(WebhookSender::new(secret, params).map_err(std::io::Error::other)?)
+        }
+        _ => None,
+    };
 
     // Spawn multiple workers
     for worker_id in 0..num_workers {
         let worker_tasks = tasks.clone();
         let worker_model_path = PathBuf::from(&model_path);
         let worker_rx = rx.clone();
+        let worker_params = params.clone();
+        let worker_cancellations = cancellations.clone();
+        let worker_webhooks = webhooks.clone();
         tokio::spawn(async move {
-            let worker = Worker::new(worker_id, worker_rx, worker_tasks, worker_model_path);
+            let mut worker = Worker::new(worker_id, worker_rx, worker_tasks, worker_model_path)
+                .with_params(worker_params)
+                .with_cancellations(worker_cancellations);
+            if let Some(sender) = worker_webhooks {
+                worker = worker.with_webhooks(sender);
+            }
             worker.run().await;
         });
     }
 
+    // Re-queue tasks that were interrupted by the last shutdown
+    let unfinished = tasks
+        .unfinished()
+        .map_err(std::io::Error::other)?;
+    if !unfinished.is_empty() {
+        println!("Re-queueing {} unfinished tasks", unfinished.len());
+    }
+    for task in unfinished {
+        if let Err(e) = tasks.set_status(&task.id, TaskStatus::Queued) {
+            eprintln!("Failed to re-queue task {}: {}", task.id, e);
+            continue;
+        }
+        tx.send(task.id).await.expect("Worker channel closed");
+    }
+
+    // Finished tasks are kept until deleted unless a retention limit is set
+    let retention = RetentionPolicy {
+        max_age: env_parse::<u64>("TASK_RETENTION_HOURS")?.map(|hours| Duration::from_secs(hours.saturating_mul(3600))),
+        max_count: env_parse("TASK_RETENTION_COUNT")?,
+    };
+    if retention.is_enabled() {
+        println!("Task retention: {:?}", retention);
+        let removed = retention.apply(tasks.as_ref()).map_err(std::io::Error::other)?;
+        println!("Removed {} expired tasks", removed.len());
+
+        let tasks = tasks.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval_at(
+                tokio::time::Instant::now() + RETENTION_INTERVAL,
+                RETENTION_INTERVAL,
+            );
+            loop {
+                interval.tick().await;
+                let tasks = tasks.clone();
+                let retention = retention.clone();
+                match tokio::task::spawn_blocking(move || retention.apply(tasks.as_ref())).await {
+                    Ok(Ok(removed)) if !removed.is_empty() => println!("Removed {} expired tasks", removed.len()),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => eprintln!("Failed to remove expired tasks: {}", e),
+                    Err(e) => eprintln!("Failed to remove expired tasks: {}", e),
+                }
+            }
+        });
+    }
+
+    // Webhooks whose retries were cut short by the last shutdown
+    if let Some(sender) = &webhooks {
+        let resumed = resume_webhooks(&tasks, sender).map_err(std::io::Error::other)?;
+        if resumed > 0 {
+            println!("Resuming {} pending webhooks", resumed);
+        }
+    }
+
     let app_state = web::Data::new(AppState {
         tasks: tasks.clone(),
         sender: tx,
+        cancellations,
+        webhooks: webhooks.clone(),
+        whisper: Arc::new(WhisperModels::new(PathBuf::from(&model_path))),
+        parakeet: parakeet_model.map(|dir| Arc::new(ParakeetModel::new(dir))),
+        openai_permits: Arc::new(Semaphore::new(num_workers.max(1))),
+        params,
     });
 
     HttpServer::new(move || {
@@ -228,9 +836,136 @@ async fn main() -> std::io::Result<()> {
             .route("/", web::get().to(index))
             .route("/upload", web::post().to(upload))
             .route("/status/{id}", web::get().to(get_status))
-            .route("/download/{id}", web::get().to(download_srt))
+            .route("/tasks/{id}", web::delete().to(delete_task))
+            .route("/tasks/{id}/events", web::get().to(task_events))
+            .route("/download/{id}", web::get().to(download_result))
+            .route("/v1/audio/transcriptions", web::post().to(openai_transcriptions))
+            .route("/v1/audio/translations", web::post().to(openai_translations))
     })
     .bind(("0.0.0.0", 8080))?
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test;
+    use transcribe_rs::{TranscriptionSegment, TranscriptionWord};
+
+    const BOUNDARY: &str = "transcribe-rs-boundary";
+
+    /// Multipart form with the text `fields` and, if `with_file`, an audio file.
+    fn form(fields: &[(&str, &str)], with_file: bool) -> String {
+        let mut body = String::new();
+        for (name, value) in fields {
+            body += &format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                BOUNDARY, name, value
+            );
+        }
+        if with_file {
+            body += &format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.wav\"\r\n\
+                 Content-Type: audio/wav\r\n\r\nRIFF\r\n",
+                BOUNDARY
+            );
+        }
+        body + &format!("--{}--\r\n", BOUNDARY)
+    }
+
+    /// POST `body` to the transcription route, which answers with a fixed
+    /// transcript in the requested language instead of running a model.
+    async fn transcribe(body: String) -> (StatusCode, String, serde_json::Value, String) {
+        let app = test::init_service(App::new().route(
+            "/v1/audio/transcriptions",
+            web::post().to(|payload: Multipart| {
+                openai_audio(payload, AudioTask::Transcribe, |request, _file| async move {
+                    let segments = vec![TranscriptionSegment {
+                        start: 0.5,
+                        end: 1.5,
+                        text: "大家好".to_string(),
+                        words: Some(vec![TranscriptionWord {
+                            start: 0.5,
+                            end: 1.0,
+                            text: "大家".to_string(),
+                            ..Default::default()
+                        }]),
+                        ..Default::default()
+                    }];
+                    let metadata = TranscriptMetadata {
+                        language: request.language,
+                        ..Default::default()
+                    };
+                    Ok(Transcript::from_segments(&segments, metadata))
+                })
+            }),
+        ))
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri("/v1/audio/transcriptions")
+            .insert_header(("content-type", format!("multipart/form-data; boundary={}", BOUNDARY)))
+            .set_payload(body)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+        let json = serde_json::from_str(&body).unwrap_or_default();
+        (status, content_type, json, body)
+    }
+
+    #[actix_web::test]
+    async fn test_openai_verbose_json_with_granularities() {
+        let fields = [
+            ("model", "whisper-1"),
+            ("language", "zh"),
+            ("response_format", "verbose_json"),
+            ("timestamp_granularities[]", "word"),
+            ("timestamp_granularities[]", "segment"),
+        ];
+        let (status, content_type, json, _) = transcribe(form(&fields, true)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "application/json");
+        assert_eq!(json["language"], "zh");
+        assert_eq!(json["words"][0]["word"], "大家");
+        assert_eq!(json["segments"][0]["text"], "大家好");
+
+        // Without segment granularity only the words are listed
+        let fields = [("response_format", "verbose_json"), ("timestamp_granularities[]", "word")];
+        let (_, _, json, _) = transcribe(form(&fields, true)).await;
+        assert!(json.get("segments").is_none());
+        assert_eq!(json["words"][0]["word"], "大家");
+    }
+
+    #[actix_web::test]
+    async fn test_openai_subtitle_and_text_formats() {
+        let (status, content_type, _, body) = transcribe(form(&[("response_format", "srt")], true)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, SubtitleFormat::Srt.content_type());
+        assert!(body.contains("00:00:00,500 --> 00:00:01,500"));
+
+        // Files in the default json format carry just the text
+        let (_, _, json, _) = transcribe(form(&[], true)).await;
+        assert_eq!(json, serde_json::json!({ "text": "大家好" }));
+    }
+
+    #[actix_web::test]
+    async fn test_openai_rejects_bad_requests() {
+        let (status, _, json, _) = transcribe(form(&[("model", "whisper-1")], false)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"]["type"], "invalid_request_error");
+        assert_eq!(json["error"]["message"], "Missing required field: file");
+
+        let fields = [("timestamp_granularities[]", "sentence")];
+        let (status, _, json, _) = transcribe(form(&fields, true)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"]["type"], "invalid_request_error");
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use transcribe_rs::cli::flag_value;
+use transcribe_rs::subtitle::{parse_subtitles, resegment, ResegmentParams, SubtitleFormat};
+use transcribe_rs::transcript::{Transcript, TranscriptMetadata, TRANSCRIPT_EXTENSION};
+use transcribe_rs::TranscriptionSegment;
+
+/// What a subtitle file is converted into
+#[derive(Clone, Copy)]
+enum Target {
+    /// Plain text, one cue per line
+    Text,
+    Subtitle(SubtitleFormat),
+    Json,
+}
+
+impl Target {
+    fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "txt" | "text" => Ok(Self::Text),
+            ext if ext == TRANSCRIPT_EXTENSION => Ok(Self::Json),
+            other => Ok(Self::Subtitle(other.parse().map_err(anyhow::Error::msg)?)),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Subtitle(format) => format.extension(),
+            Self::Json => TRANSCRIPT_EXTENSION,
+        }
+    }
+
+    fn render(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        Ok(match self {
+            Self::Text => segments
+                .iter()
+                .flat_map(|s| s.text.lines())
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Self::Subtitle(format) => format.render(segments),
+            Self::Json => {
+                Transcript::from_segments(segments, TranscriptMetadata::default()).to_json()?
+            }
+        })
+    }
+}
+
+fn is_subtitle_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("srt") || e.eq_ignore_ascii_case("vtt"))
+}
+
+/// Collect subtitle files under `path` (recursively for directories)
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            collect_files(&entry, files)?;
+        }
+    } else if is_subtitle_file(path) {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+fn convert_file(path: &Path, target: Target, resegment_cues: bool) -> Result<PathBuf> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let mut segments =
+        parse_subtitles(&content).with_context(|| format!("Failed to parse {:?}", path))?;
+    if resegment_cues {
+        segments = resegment(&segments, &ResegmentParams::default());
+    }
+
+    let output_path = path.with_extension(target.extension());
+    if output_path == path {
+        anyhow::bail!("{:?} is already in the requested format", path);
+    }
+    fs::write(&output_path, target.render(&segments)?)?;
+    Ok(output_path)
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 || args[1].starts_with("--") {
+        eprintln!(
+            "Usage: {} <subtitle_file_or_dir> [--to txt|srt|vtt|ass|json] [--resegment]",
+            args[0]
+        );
+        eprintln!("Converts .srt and .vtt files; directories are searched recursively.");
+        eprintln!("--resegment splits long cues to a readable length and reading speed.");
+        std::process::exit(1);
+    }
+
+    let input = PathBuf::from(&args[1]);
+    let target = Target::parse(&flag_value(&args, "--to").unwrap_or_else(|| "txt".to_string()))?;
+    let resegment_cues = args.iter().any(|arg| arg == "--resegment");
+
+    if !input.exists() {
+        anyhow::bail!("Path not found: {:?}", input);
+    }
+    let mut files = Vec::new();
+    collect_files(&input, &mut files)?;
+    if files.is_empty() {
+        println!("No .srt or .vtt files found in {:?}", input);
+    }
+
+    for file in files {
+        match convert_file(&file, target, resegment_cues) {
+            Ok(output) => println!("Converted: {:?} -> {:?}", file, output),
+            Err(e) => eprintln!("Error converting {:?}: {:#}", file, e),
+        }
+    }
+
+    Ok(())
+}
@@ -1,61 +1,83 @@
 use anyhow::{Context, Result};
-use std::path::{Path, PathBuf};
-use transcribe_rs::worker::transcribe_parallel;
-
-/// Convert input audio to 16kHz, 16-bit, Mono WAV using FFmpeg
-fn convert_to_wav(input_path: &Path, output_path: &Path) -> Result<()> {
-    println!("Converting {:?} to {:?}", input_path, output_path);
-    
-    let status = std::process::Command::new("ffmpeg")
-        .arg("-i")
-        .arg(input_path)
-        .arg("-ar")
-        .arg("16000")
-        .arg("-ac")
-        .arg("1")
-        .arg("-c:a")
-        .arg("pcm_s16le")
-        .arg(output_path)
-        .arg("-y") // Overwrite output
-        .status()
-        .context("Failed to execute ffmpeg")?;
-
-    if !status.success() {
-        anyhow::bail!("FFmpeg conversion failed");
-    }
-
-    Ok(())
-}
+use std::path::PathBuf;
+use transcribe_rs::audio::MixStrategy;
+use transcribe_rs::chinese::ChineseVariant;
+use transcribe_rs::cli::{code_switching, flag_value, normalize, punctuation, whisper_params};
+use transcribe_rs::hallucination::{FilterParams, HallucinationFilter};
+use transcribe_rs::subtitle::{resegment, ResegmentParams, SubtitleFormat};
+use transcribe_rs::transcript::TRANSCRIPT_EXTENSION;
+use transcribe_rs::worker::{transcribe_parallel_transcript, ParallelTranscriptionParams};
 
 fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
     if args.len() < 3 {
-        eprintln!("Usage: {} <input_audio> <model_path>", args[0]);
+        eprintln!(
+            "Usage: {} <input_audio> <model_path> [--format srt|vtt|ass|json] [--chinese s|t|tw|hk] [--rules <file>]",
+            args[0]
+        );
+        eprintln!("Decoding: [--beam-size N (1 = greedy)] [--temperature T] [--no-fallback] [--threads N] [--word-timestamps]");
+        eprintln!("Mixed languages: [--code-switch zh,en] transcribes each speech region in its detected language");
+        eprintln!("Chunking: [--vad-model <silero.onnx>] [--min-chunk SECS] [--max-chunk SECS] (default: energy VAD, 60-300s)");
+        eprintln!("Channels: [--mix average|loudest|left|right|N] reduces multi-channel audio to mono (default: average)");
+        eprintln!("Text: [--normalize zh|en] fixes CJK/Latin spacing, punctuation width and full-width numbers");
+        eprintln!("      [--punctuate] adds punctuation at pauses, [--punctuation-model <dir>] uses an ONNX model instead");
         std::process::exit(1);
     }
 
     let input_path = PathBuf::from(&args[1]);
     let model_path = PathBuf::from(&args[2]);
-    
-    // Output SRT file name
-    let output_srt_path = input_path.with_extension("srt");
+    // `None` selects the JSON transcript instead of a subtitle format
+    let format = match flag_value(&args, "--format") {
+        Some(f) if f.eq_ignore_ascii_case(TRANSCRIPT_EXTENSION) => None,
+        Some(f) => Some(f.parse::<SubtitleFormat>().map_err(anyhow::Error::msg)?),
+        None => Some(SubtitleFormat::default()),
+    };
+
+    let mut params = ParallelTranscriptionParams {
+        chinese_variant: flag_value(&args, "--chinese")
+            .map(|v| v.parse::<ChineseVariant>().map_err(anyhow::Error::msg))
+            .transpose()?,
+        ..Default::default()
+    };
+    if let Some(mix) = flag_value(&args, "--mix") {
+        params.mix = mix.parse::<MixStrategy>().map_err(anyhow::Error::msg)?;
+    }
+    if let Some(vad_model) = flag_value(&args, "--vad-model") {
+        params.vad_model = Some(PathBuf::from(vad_model));
+    }
+    if let Some(secs) = flag_value(&args, "--min-chunk") {
+        params.chunking.min_chunk_duration = secs.parse().context("Invalid --min-chunk seconds")?;
+    }
+    if let Some(secs) = flag_value(&args, "--max-chunk") {
+        params.chunking.max_chunk_duration = secs.parse().context("Invalid --max-chunk seconds")?;
+    }
+    if params.chunking.min_chunk_duration > params.chunking.max_chunk_duration {
+        anyhow::bail!("--min-chunk must not exceed --max-chunk");
+    }
+    if let Some(rules) = flag_value(&args, "--rules") {
+        params.hallucination_filter =
+            Some(HallucinationFilter::with_rule_file(rules.as_ref(), FilterParams::default())?);
+    }
+    params.whisper = whisper_params(&args)?;
+    params.code_switching = code_switching(&args);
 
-    // Temp WAV file name
-    let temp_wav_path = input_path.with_extension("temp_16k.wav");
+    params.punctuation = punctuation(&args);
+    params.normalize = normalize(&args);
 
-    // 1. Convert to WAV
-    convert_to_wav(&input_path, &temp_wav_path)?;
+    // Output file name
+    let output_path = input_path.with_extension(format.map_or(TRANSCRIPT_EXTENSION, |f| f.extension()));
 
-    // 2. Transcribe
+    // 1. Transcribe (audio is decoded in-process, any supported format)
     println!("Starting transcription...");
-    let srt_content = transcribe_parallel(&temp_wav_path, &model_path)?;
-
-    // 3. Write SRT
-    std::fs::write(&output_srt_path, srt_content)?;
-    println!("Transcription saved to {:?}", output_srt_path);
+    let transcript = transcribe_parallel_transcript(&input_path, &model_path, &params)?;
 
-    // Cleanup
-    std::fs::remove_file(temp_wav_path).ok();
+    // 2. Write subtitles or the JSON transcript
+    let output = match format {
+        Some(format) => format.render(&resegment(&transcript.to_segments(), &ResegmentParams::default())),
+        None => transcript.to_json()?,
+    };
+    std::fs::write(&output_path, output)?;
+    println!("Transcription saved to {:?}", output_path);
 
     Ok(())
 }
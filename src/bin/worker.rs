@@ -1,30 +1,47 @@
 use anyhow::{Context, Result};
 use std::path::PathBuf;
 use std::io::{self, BufRead, Write};
-use transcribe_rs::engines::whisper::{WhisperEngine, WhisperInferenceParams};
+use transcribe_rs::audio::{read_wav_samples, TARGET_SAMPLE_RATE};
+use transcribe_rs::cli::{code_switching, whisper_params};
+use transcribe_rs::engines::whisper::WhisperEngine;
+use transcribe_rs::vad::{PlannedChunk, SpeechRegion};
+use transcribe_rs::worker::{dominant_language, transcribe_chunk};
 use transcribe_rs::{TranscriptionEngine, TranscriptionSegment};
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize)]
 struct WorkerRequest {
     chunk_path: String,
+    /// Index of the chunk in the recording, for log lines
+    #[serde(default)]
+    index: usize,
+    /// Speech regions of the chunk, relative to its start; used to plan
+    /// language spans with `--code-switch`
+    #[serde(default)]
+    regions: Vec<SpeechRegion>,
 }
 
 #[derive(Serialize, Deserialize)]
 struct WorkerResponse {
     success: bool,
     segments: Option<Vec<TranscriptionSegment>>,
+    /// Language Whisper detected or was given for the chunk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
     error: Option<String>,
 }
 
 fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: {} <model_path>", args[0]);
+        eprintln!("Usage: {} <model_path> [decoding options]", args[0]);
         std::process::exit(1);
     }
 
     let model_path = PathBuf::from(&args[1]);
+    // Same decoding options as the transcribe CLI, for every chunk
+    let params = whisper_params(&args[2..])?;
+    let code_switching = code_switching(&args[2..]);
 
     // 1. Load model ONCE
     eprintln!("[Worker] Loading model from {:?}...", model_path);
@@ -58,14 +75,32 @@ fn main() -> Result<()> {
 
                 // Transcribe
                 eprintln!("[Worker] Transcribing {:?}", request.chunk_path);
-                let params = WhisperInferenceParams::default();
                 let path = PathBuf::from(request.chunk_path);
-                
-                match engine.transcribe_file(&path, Some(params)) {
-                    Ok(result) => {
+                let result = read_wav_samples(&path)
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))
+                    .and_then(|samples| {
+                        let chunk = PlannedChunk {
+                            index: request.index,
+                            start: 0.0,
+                            end: samples.len() as f32 / TARGET_SAMPLE_RATE as f32,
+                        };
+                        transcribe_chunk(
+                            &mut engine,
+                            std::process::id() as usize,
+                            &samples,
+                            &request.regions,
+                            &chunk,
+                            &params,
+                            code_switching.as_ref(),
+                        )
+                    });
+
+                match result {
+                    Ok((transcript, languages)) => {
                         let response = WorkerResponse {
                             success: true,
-                            segments: result.segments, // Can be None
+                            segments: Some(transcript.segments),
+                            language: dominant_language(&languages),
                             error: None,
                         };
                         send_response(&response);
@@ -96,6 +131,7 @@ fn send_error(msg: String) {
     let response = WorkerResponse {
         success: false,
         segments: None,
+        language: None,
         error: Some(msg),
     };
     send_response(&response);
@@ -0,0 +1,283 @@
+//! Conversion between Simplified and Traditional Chinese.
+//!
+//! Whisper often answers in Simplified characters even for Taiwanese or Hong
+//! Kong speakers. This module converts transcript text to the wanted variant
+//! using the phrase and character dictionaries of
+//! [OpenCC](https://github.com/BYVoid/OpenCC), which are bundled with the
+//! crate (see `data/opencc/`). Conversion is dictionary based: at every
+//! position the longest matching phrase wins, so context-dependent
+//! characters such as 发 (發/髮) are converted correctly inside words.
+//!
+//! Conversions follow the OpenCC presets:
+//!
+//! | Target | Steps |
+//! | --- | --- |
+//! | [`ChineseVariant::Traditional`] | Simplified → Traditional (`s2t`) |
+//! | [`ChineseVariant::Taiwan`] | `s2t`, then Taiwan vocabulary and character forms (`s2twp`) |
+//! | [`ChineseVariant::HongKong`] | `s2t`, then Hong Kong character forms (`s2hk`) |
+//! | [`ChineseVariant::Simplified`] | regional forms back to standard Traditional, then `t2s` |
+//!
+//! Text that is already in the target variant is left unchanged, so mixed
+//! output can be normalized in one pass.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use transcribe_rs::chinese::ChineseVariant;
+//!
+//! let converter = ChineseVariant::Taiwan.converter();
+//! assert_eq!(converter.convert("这个软件的头发"), "這個軟體的頭髮");
+//! ```
+
+use crate::transcript::Transcript;
+use crate::TranscriptionSegment;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const ST_CHARACTERS: &str = include_str!("../data/opencc/STCharacters.txt");
+const ST_PHRASES: &str = include_str!("../data/opencc/STPhrases.txt");
+const TS_CHARACTERS: &str = include_str!("../data/opencc/TSCharacters.txt");
+const TS_PHRASES: &str = include_str!("../data/opencc/TSPhrases.txt");
+const TW_PHRASES: &str = include_str!("../data/opencc/TWPhrases.txt");
+const TW_VARIANTS: &str = include_str!("../data/opencc/TWVariants.txt");
+const TW_VARIANTS_REV_PHRASES: &str = include_str!("../data/opencc/TWVariantsRevPhrases.txt");
+const HK_VARIANTS: &str = include_str!("../data/opencc/HKVariants.txt");
+const HK_VARIANTS_REV_PHRASES: &str = include_str!("../data/opencc/HKVariantsRevPhrases.txt");
+
+/// Written variants of Chinese that text can be converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChineseVariant {
+    /// Simplified Chinese (`zh-Hans`)
+    Simplified,
+    /// Traditional Chinese with OpenCC standard character forms (`zh-Hant`)
+    Traditional,
+    /// Traditional Chinese with Taiwan vocabulary and character forms (`zh-TW`)
+    Taiwan,
+    /// Traditional Chinese with Hong Kong character forms (`zh-HK`)
+    HongKong,
+}
+
+impl ChineseVariant {
+    /// Shared converter for this variant, built on first use.
+    pub fn converter(self) -> &'static ChineseConverter {
+        static SIMPLIFIED: Lazy<ChineseConverter> =
+            Lazy::new(|| ChineseConverter::new(ChineseVariant::Simplified));
+        static TRADITIONAL: Lazy<ChineseConverter> =
+            Lazy::new(|| ChineseConverter::new(ChineseVariant::Traditional));
+        static TAIWAN: Lazy<ChineseConverter> =
+            Lazy::new(|| ChineseConverter::new(ChineseVariant::Taiwan));
+        static HONG_KONG: Lazy<ChineseConverter> =
+            Lazy::new(|| ChineseConverter::new(ChineseVariant::HongKong));
+
+        match self {
+            Self::Simplified => &SIMPLIFIED,
+            Self::Traditional => &TRADITIONAL,
+            Self::Taiwan => &TAIWAN,
+            Self::HongKong => &HONG_KONG,
+        }
+    }
+}
+
+impl FromStr for ChineseVariant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "s" | "simplified" | "zh-hans" | "zh-cn" | "cn" => Ok(Self::Simplified),
+            "t" | "traditional" | "zh-hant" => Ok(Self::Traditional),
+            "tw" | "taiwan" | "zh-tw" => Ok(Self::Taiwan),
+            "hk" | "hongkong" | "hong-kong" | "zh-hk" => Ok(Self::HongKong),
+            other => Err(format!(
+                "Unknown Chinese variant: {} (expected simplified, traditional, tw or hk)",
+                other
+            )),
+        }
+    }
+}
+
+/// A phrase dictionary applied by longest match.
+struct Dictionary {
+    entries: HashMap<String, String>,
+    /// Key lengths in chars for each first character, longest first
+    key_lengths: HashMap<char, Vec<usize>>,
+}
+
+impl Dictionary {
+    /// Build a dictionary from OpenCC files (`key<TAB>value [value...]`),
+    /// using the first value of each entry. With `reverse`, every value maps
+    /// back to its key instead. Later sources override earlier ones.
+    fn new(sources: &[(&str, bool)]) -> Self {
+        let mut entries = HashMap::new();
+        for &(source, reverse) in sources {
+            for line in source.lines() {
+                if line.starts_with('#') {
+                    continue;
+                }
+                let Some((key, values)) = line.split_once('\t') else {
+                    continue;
+                };
+                if reverse {
+                    for value in values.split_whitespace() {
+                        entries.insert(value.to_string(), key.to_string());
+                    }
+                } else if let Some(value) = values.split_whitespace().next() {
+                    entries.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+
+        let mut key_lengths: HashMap<char, Vec<usize>> = HashMap::new();
+        for key in entries.keys() {
+            if let Some(first) = key.chars().next() {
+                key_lengths.entry(first).or_default().push(key.chars().count());
+            }
+        }
+        for lengths in key_lengths.values_mut() {
+            lengths.sort_unstable_by(|a, b| b.cmp(a));
+            lengths.dedup();
+        }
+
+        Self {
+            entries,
+            key_lengths,
+        }
+    }
+
+    fn apply(&self, text: &str) -> String {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let byte_at = |i: usize| chars.get(i).map_or(text.len(), |&(b, _)| b);
+
+        let mut output = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let (start, c) = chars[i];
+            let matched = self.key_lengths.get(&c).and_then(|lengths| {
+                lengths
+                    .iter()
+                    .filter(|&&len| i + len <= chars.len())
+                    .find_map(|&len| {
+                        let value = self.entries.get(&text[start..byte_at(i + len)])?;
+                        Some((len, value))
+                    })
+            });
+
+            match matched {
+                Some((len, value)) => {
+                    output.push_str(value);
+                    i += len;
+                }
+                None => {
+                    output.push(c);
+                    i += 1;
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Converts text to one [`ChineseVariant`].
+///
+/// Building a converter parses the bundled dictionaries, which takes tens of
+/// milliseconds; prefer the shared instances from [`ChineseVariant::converter`].
+pub struct ChineseConverter {
+    target: ChineseVariant,
+    /// Dictionaries applied one after another
+    stages: Vec<Dictionary>,
+}
+
+impl ChineseConverter {
+    pub fn new(target: ChineseVariant) -> Self {
+        let to_traditional = || Dictionary::new(&[(ST_CHARACTERS, false), (ST_PHRASES, false)]);
+
+        let stages = match target {
+            ChineseVariant::Traditional => vec![to_traditional()],
+            ChineseVariant::Taiwan => vec![
+                to_traditional(),
+                Dictionary::new(&[(TW_PHRASES, false)]),
+                Dictionary::new(&[(TW_VARIANTS, false)]),
+            ],
+            ChineseVariant::HongKong => {
+                vec![to_traditional(), Dictionary::new(&[(HK_VARIANTS, false)])]
+            }
+            ChineseVariant::Simplified => vec![
+                Dictionary::new(&[
+                    (TW_VARIANTS, true),
+                    (TW_VARIANTS_REV_PHRASES, false),
+                    (HK_VARIANTS, true),
+                    (HK_VARIANTS_REV_PHRASES, false),
+                ]),
+                Dictionary::new(&[(TS_CHARACTERS, false), (TS_PHRASES, false)]),
+            ],
+        };
+
+        Self { target, stages }
+    }
+
+    /// The variant this converter produces.
+    pub fn target(&self) -> ChineseVariant {
+        self.target
+    }
+
+    pub fn convert(&self, text: &str) -> String {
+        self.stages
+            .iter()
+            .fold(text.to_string(), |text, stage| stage.apply(&text))
+    }
+
+    /// Convert the text and words of every segment in place.
+    pub fn convert_segments(&self, segments: &mut [TranscriptionSegment]) {
+        for segment in segments {
+            segment.text = self.convert(&segment.text);
+            for word in segment.words.iter_mut().flatten() {
+                word.text = self.convert(&word.text);
+            }
+        }
+    }
+
+    /// Convert the full text, segments and words of a transcript in place.
+    pub fn convert_transcript(&self, transcript: &mut Transcript) {
+        transcript.text = self.convert(&transcript.text);
+        for segment in &mut transcript.segments {
+            segment.text = self.convert(&segment.text);
+            for word in &mut segment.words {
+                word.word = self.convert(&word.word);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simplified_to_traditional_uses_phrases() {
+        let converter = ChineseVariant::Traditional.converter();
+        assert_eq!(converter.convert("头发和发展"), "頭髮和發展");
+        assert_eq!(converter.convert("Hello 世界!"), "Hello 世界!");
+    }
+
+    #[test]
+    fn test_taiwan_vocabulary() {
+        let converter = ChineseVariant::Taiwan.converter();
+        assert_eq!(converter.convert("我们用软件打印文件"), "我們用軟體列印檔案");
+        // Already Taiwanese text is left alone
+        assert_eq!(converter.convert("我們用軟體列印檔案"), "我們用軟體列印檔案");
+    }
+
+    #[test]
+    fn test_hong_kong_and_back_to_simplified() {
+        assert_eq!(ChineseVariant::Traditional.converter().convert("户口"), "戶口");
+        assert_eq!(ChineseVariant::HongKong.converter().convert("户口"), "户口");
+        assert_eq!(ChineseVariant::Simplified.converter().convert("頭髮裏的綫"), "头发里的线");
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("zh-TW".parse::<ChineseVariant>(), Ok(ChineseVariant::Taiwan));
+        assert_eq!("simplified".parse::<ChineseVariant>(), Ok(ChineseVariant::Simplified));
+        assert!("klingon".parse::<ChineseVariant>().is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Command-line helpers shared by the bundled binaries.
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+use crate::engines::whisper::{WhisperInferenceParams, WhisperSampling};
+use crate::normalize::NormalizeParams;
+use crate::punctuation::PunctuationParams;
+use crate::vad::CodeSwitchingParams;
+
+/// Options read by [`whisper_params`] and [`code_switching`] that take a value
+const WORKER_OPTIONS: &[&str] = &["--beam-size", "--temperature", "--threads", "--code-switch"];
+/// Switches read by [`whisper_params`]
+const WHISPER_SWITCHES: &[&str] = &["--no-fallback", "--word-timestamps"];
+
+/// Value of a `--flag value` or `--flag=value` command-line option
+pub fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == flag {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(flag)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(str::to_string)
+        }
+    })
+}
+
+/// Whisper decoding parameters from `--beam-size N` (1 = greedy),
+/// `--temperature T`, `--no-fallback`, `--threads N` and
+/// `--word-timestamps`
+pub fn whisper_params(args: &[String]) -> Result<WhisperInferenceParams> {
+    let mut params = WhisperInferenceParams::default();
+    if let Some(beam_size) = flag_value(args, "--beam-size") {
+        params.sampling = match beam_size.parse::<i32>().context("Invalid --beam-size")? {
+            n if n <= 1 => WhisperSampling::Greedy { best_of: 1 },
+            n => WhisperSampling::BeamSearch {
+                beam_size: n,
+                patience: -1.0,
+            },
+        };
+    }
+    if let Some(temperature) = flag_value(args, "--temperature") {
+        params.temperature = temperature.parse().context("Invalid --temperature")?;
+    }
+    if args.iter().any(|arg| arg == "--no-fallback") {
+        params.temperature_inc = 0.0;
+    }
+    if let Some(threads) = flag_value(args, "--threads") {
+        params.n_threads = Some(threads.parse().context("Invalid --threads")?);
+    }
+    if args.iter().any(|arg| arg == "--word-timestamps") {
+        params.token_timestamps = true;
+    }
+    Ok(params)
+}
+
+/// Code-switching mode from `--code-switch zh,en`; an empty list lets every
+/// region use any language
+pub fn code_switching(args: &[String]) -> Option<CodeSwitchingParams> {
+    flag_value(args, "--code-switch").map(|languages| CodeSwitchingParams {
+        languages: languages
+            .split(',')
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect(),
+        ..Default::default()
+    })
+}
+
+/// Punctuation restoration from `--punctuate` (pause-based) or
+/// `--punctuation-model <dir>` (ONNX model)
+pub fn punctuation(args: &[String]) -> Option<PunctuationParams> {
+    let model_dir = flag_value(args, "--punctuation-model");
+    if model_dir.is_none() && !args.iter().any(|arg| arg == "--punctuate") {
+        return None;
+    }
+    Some(PunctuationParams {
+        model_dir: model_dir.map(PathBuf::from),
+        ..Default::default()
+    })
+}
+
+/// Text normalization from `--normalize <language>`
+pub fn normalize(args: &[String]) -> Option<NormalizeParams> {
+    flag_value(args, "--normalize").map(|language| NormalizeParams::for_language(&language))
+}
+
+/// The arguments [`whisper_params`] and [`code_switching`] read, as
+/// `--flag value` pairs and switches, to pass on to worker processes
+pub fn worker_args(args: &[String]) -> Vec<String> {
+    let mut forwarded = Vec::new();
+    for &flag in WORKER_OPTIONS {
+        if let Some(value) = flag_value(args, flag) {
+            forwarded.extend([flag.to_string(), value]);
+        }
+    }
+    for &flag in WHISPER_SWITCHES {
+        if args.iter().any(|arg| arg == flag) {
+            forwarded.push(flag.to_string());
+        }
+    }
+    forwarded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_flag_value_forms() {
+        let args = args(&[
+            "tool",
+            "in.wav",
+            "--format",
+            "vtt",
+            "--mix=left",
+            "--formats",
+        ]);
+        assert_eq!(flag_value(&args, "--format").as_deref(), Some("vtt"));
+        assert_eq!(flag_value(&args, "--mix").as_deref(), Some("left"));
+        assert_eq!(flag_value(&args, "--formats"), None);
+        assert_eq!(flag_value(&args, "--rules"), None);
+    }
+
+    #[test]
+    fn test_worker_args_round_trip() {
+        let args = args(&[
+            "tool",
+            "in.wav",
+            "--format",
+            "srt",
+            "--beam-size=1",
+            "--no-fallback",
+            "--threads",
+            "8",
+            "--word-timestamps",
+            "--code-switch",
+            "zh,en",
+        ]);
+        let forwarded = worker_args(&args);
+        assert_eq!(
+            forwarded,
+            vec![
+                "--beam-size",
+                "1",
+                "--threads",
+                "8",
+                "--code-switch",
+                "zh,en",
+                "--no-fallback",
+                "--word-timestamps"
+            ]
+        );
+
+        let params = whisper_params(&forwarded).unwrap();
+        assert_eq!(params.sampling, WhisperSampling::Greedy { best_of: 1 });
+        assert_eq!(params.temperature_inc, 0.0);
+        assert_eq!(params.n_threads, Some(8));
+        assert!(params.token_timestamps);
+        assert_eq!(
+            code_switching(&forwarded).unwrap().languages,
+            vec!["zh", "en"]
+        );
+        assert!(whisper_params(&["--temperature".to_string(), "hot".to_string()]).is_err());
+    }
+}
@@ -65,6 +65,7 @@
 //!
 //! let params = ParakeetInferenceParams {
 //!     timestamp_granularity: TimestampGranularity::Word,  // Get word-level timestamps
+//!     ..Default::default()
 //! };
 //!
 //! let result = engine.transcribe_file(&PathBuf::from("audio.wav"), Some(params))?;
@@ -78,7 +79,11 @@
 //! ```
 
 use crate::{
-    engines::parakeet::{model::ParakeetModel, timestamps::convert_timestamps},
+    engines::parakeet::{
+        model::ParakeetModel,
+        timestamps::{convert_timestamps_with_params, restore_punctuation},
+    },
+    punctuation::{needs_punctuation, OnnxPunctuator, PausePunctuator, PunctuationError},
     TranscriptionEngine, TranscriptionResult,
 };
 use std::path::{Path, PathBuf};
@@ -173,6 +178,59 @@ impl ParakeetModelParams {
     }
 }
 
+/// Rules for splitting words into segments at `Word` and `Segment` granularity.
+///
+/// By default segments only end at sentence punctuation. Unpunctuated output
+/// then comes out as one long segment; pause and length limits keep segments
+/// short enough to be used as subtitles (see [`SegmentationParams::subtitles`]).
+#[derive(Debug, Clone)]
+pub struct SegmentationParams {
+    /// Characters that end a segment when a word contains them
+    pub separators: Vec<char>,
+    /// End a segment at pauses between words of at least this many milliseconds
+    pub max_gap_ms: Option<u32>,
+    /// Maximum number of words per segment
+    pub max_words: Option<usize>,
+    /// Maximum segment duration in seconds
+    pub max_duration: Option<f32>,
+}
+
+impl Default for SegmentationParams {
+    fn default() -> Self {
+        Self {
+            separators: vec!['.', '?', '!', '。', '？', '！'],
+            max_gap_ms: None,
+            max_words: None,
+            max_duration: None,
+        }
+    }
+}
+
+impl SegmentationParams {
+    /// Segments suitable for subtitles: split at sentence ends, pauses of
+    /// half a second, 20 words or 7 seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use transcribe_rs::engines::parakeet::{ParakeetInferenceParams, SegmentationParams, TimestampGranularity};
+    ///
+    /// let params = ParakeetInferenceParams {
+    ///     timestamp_granularity: TimestampGranularity::Segment,
+    ///     segmentation: SegmentationParams::subtitles(),
+    ///     ..Default::default()
+    /// };
+    /// ```
+    pub fn subtitles() -> Self {
+        Self {
+            max_gap_ms: Some(500),
+            max_words: Some(20),
+            max_duration: Some(7.0),
+            ..Default::default()
+        }
+    }
+}
+
 /// Parameters for configuring Parakeet inference behavior.
 ///
 /// Controls the level of detail in timestamp generation and other
@@ -181,12 +239,20 @@ impl ParakeetModelParams {
 pub struct ParakeetInferenceParams {
     /// The granularity level for timestamp generation
     pub timestamp_granularity: TimestampGranularity,
+    /// How words are grouped into segments
+    pub segmentation: SegmentationParams,
+    /// Restore punctuation when the model output has none, so segments can
+    /// be split into sentences. Uses the model loaded with
+    /// [`ParakeetEngine::load_punctuation_model`], or pauses between words.
+    pub restore_punctuation: bool,
 }
 
 impl Default for ParakeetInferenceParams {
     fn default() -> Self {
         Self {
             timestamp_granularity: TimestampGranularity::Token,
+            segmentation: SegmentationParams::default(),
+            restore_punctuation: false,
         }
     }
 }
@@ -213,6 +279,7 @@ impl Default for ParakeetInferenceParams {
 pub struct ParakeetEngine {
     loaded_model_path: Option<PathBuf>,
     model: Option<ParakeetModel>,
+    punctuator: Option<OnnxPunctuator>,
 }
 
 impl Default for ParakeetEngine {
@@ -239,8 +306,17 @@ impl ParakeetEngine {
         Self {
             loaded_model_path: None,
             model: None,
+            punctuator: None,
         }
     }
+
+    /// Load an ONNX punctuation model used when `restore_punctuation` is set.
+    ///
+    /// See [`crate::punctuation`] for the expected directory layout.
+    pub fn load_punctuation_model(&mut self, model_dir: &Path) -> Result<(), PunctuationError> {
+        self.punctuator = Some(OnnxPunctuator::new(model_dir)?);
+        Ok(())
+    }
 }
 
 impl Drop for ParakeetEngine {
@@ -287,15 +363,26 @@ impl TranscriptionEngine for ParakeetEngine {
         let parakeet_params = params.unwrap_or_default();
 
         // Get the timestamped result from the model
-        let timestamped_result = model.transcribe_samples(samples)?;
+        let mut timestamped_result = model.transcribe_samples(samples)?;
+
+        if parakeet_params.restore_punctuation && needs_punctuation(&timestamped_result.text) {
+            match self.punctuator.as_mut() {
+                Some(punctuator) => restore_punctuation(&mut timestamped_result, punctuator)?,
+                None => restore_punctuation(&mut timestamped_result, &mut PausePunctuator::default())?,
+            };
+        }
 
         // Convert timestamps based on requested granularity
-        let segments =
-            convert_timestamps(&timestamped_result, parakeet_params.timestamp_granularity);
+        let segments = convert_timestamps_with_params(
+            &timestamped_result,
+            parakeet_params.timestamp_granularity,
+            &parakeet_params.segmentation,
+        );
 
         Ok(TranscriptionResult {
             text: timestamped_result.text,
             segments: Some(segments),
+            language: None,
         })
     }
 }
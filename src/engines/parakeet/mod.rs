@@ -60,6 +60,7 @@
 //! // Configure for word-level timestamps
 //! let params = ParakeetInferenceParams {
 //!     timestamp_granularity: TimestampGranularity::Word,
+//!     ..Default::default()
 //! };
 //!
 //! let result = engine.transcribe_file(&PathBuf::from("audio.wav"), Some(params))?;
@@ -78,7 +79,7 @@ pub mod timestamps;
 
 pub use engine::{
     ParakeetEngine, ParakeetInferenceParams, ParakeetModelParams, QuantizationType,
-    TimestampGranularity,
+    SegmentationParams, TimestampGranularity,
 };
 pub use model::{ParakeetError, ParakeetModel, TimestampedResult};
-pub use timestamps::{convert_timestamps, WordBoundary};
+pub use timestamps::{convert_timestamps, convert_timestamps_with_params, WordBoundary};
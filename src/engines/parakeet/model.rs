@@ -24,8 +24,13 @@ pub struct TimestampedResult {
     pub text: String,
     pub timestamps: Vec<f32>,
     pub tokens: Vec<String>,
+    /// Softmax probability of each token when it was emitted
+    pub confidences: Vec<f32>,
 }
 
+/// Token ids with the encoder frame and probability of each
+type DecodedSequence = (Vec<i32>, Vec<usize>, Vec<f32>);
+
 #[derive(thiserror::Error, Debug)]
 pub enum ParakeetError {
     #[error("ORT error")]
@@ -331,9 +336,9 @@ impl ParakeetModel {
         // Decode for each batch item
         let mut results = Vec::new();
         for (encodings, &encodings_len) in encoder_out.outer_iter().zip(encoder_out_lens.iter()) {
-            let (tokens, timestamps) =
+            let (tokens, timestamps, confidences) =
                 self.decode_sequence(&encodings.view(), encodings_len as usize)?;
-            let result = self.decode_tokens(tokens, timestamps);
+            let result = self.decode_tokens(tokens, timestamps, confidences);
             results.push(result);
         }
 
@@ -344,10 +349,11 @@ impl ParakeetModel {
         &mut self,
         encodings: &ArrayViewD<f32>, // [time_steps, 1024]
         encodings_len: usize,
-    ) -> Result<(Vec<i32>, Vec<usize>), ParakeetError> {
+    ) -> Result<DecodedSequence, ParakeetError> {
         let mut prev_state = self.create_decoder_state()?;
         let mut tokens = Vec::new();
         let mut timestamps = Vec::new();
+        let mut confidences = Vec::new();
 
         let mut t = 0;
         let mut emitted_tokens = 0;
@@ -393,6 +399,7 @@ impl ParakeetModel {
                 prev_state = new_state;
                 tokens.push(token);
                 timestamps.push(t);
+                confidences.push(token_probability(vocab_logits, token as usize));
                 emitted_tokens += 1;
             }
 
@@ -403,21 +410,27 @@ impl ParakeetModel {
             }
         }
 
-        Ok((tokens, timestamps))
+        Ok((tokens, timestamps, confidences))
     }
 
-    fn decode_tokens(&self, ids: Vec<i32>, timestamps: Vec<usize>) -> TimestampedResult {
-        let tokens: Vec<String> = ids
+    fn decode_tokens(
+        &self,
+        ids: Vec<i32>,
+        timestamps: Vec<usize>,
+        confidences: Vec<f32>,
+    ) -> TimestampedResult {
+        let (tokens, confidences): (Vec<String>, Vec<f32>) = ids
             .iter()
-            .filter_map(|&id| {
+            .zip(confidences)
+            .filter_map(|(&id, confidence)| {
                 let idx = id as usize;
                 if idx < self.vocab.len() {
-                    Some(self.vocab[idx].clone())
+                    Some((self.vocab[idx].clone(), confidence))
                 } else {
                     None
                 }
             })
-            .collect();
+            .unzip();
 
         let text = match &*DECODE_SPACE_RE {
             Ok(regex) => regex
@@ -441,6 +454,7 @@ impl ParakeetModel {
             text,
             timestamps: float_timestamps,
             tokens,
+            confidences,
         }
     }
 
@@ -471,3 +485,10 @@ impl ParakeetModel {
         Ok(timestamped_result)
     }
 }
+
+/// Softmax probability of `token` given the vocabulary logits.
+fn token_probability(logits: &[f32], token: usize) -> f32 {
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let sum: f32 = logits.iter().map(|&l| (l - max).exp()).sum();
+    logits.get(token).map_or(0.0, |&l| (l - max).exp() / sum)
+}
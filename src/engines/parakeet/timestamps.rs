@@ -1,6 +1,8 @@
-use super::engine::TimestampGranularity;
+use super::engine::{SegmentationParams, TimestampGranularity};
 use super::model::TimestampedResult;
-use crate::TranscriptionSegment;
+use crate::punctuation::{PunctuationError, PunctuationRestorer};
+use crate::text::is_cjk;
+use crate::{TranscriptionSegment, TranscriptionWord};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
@@ -9,6 +11,7 @@ pub struct Token {
     pub t_start: f32,
     pub t_end: f32,
     pub is_blank: bool,
+    pub confidence: Option<f32>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -51,12 +54,23 @@ pub struct TokenInfo {
 pub fn convert_timestamps(
     timestamped_result: &TimestampedResult,
     granularity: TimestampGranularity,
+) -> Vec<TranscriptionSegment> {
+    convert_timestamps_with_params(timestamped_result, granularity, &SegmentationParams::default())
+}
+
+/// Like [`convert_timestamps`], with custom rules for splitting segments.
+pub fn convert_timestamps_with_params(
+    timestamped_result: &TimestampedResult,
+    granularity: TimestampGranularity,
+    segmentation: &SegmentationParams,
 ) -> Vec<TranscriptionSegment> {
     match granularity {
         TimestampGranularity::Token => convert_to_raw_token_segments(timestamped_result),
-        TimestampGranularity::Word => convert_to_hierarchical_word_segments(timestamped_result),
+        TimestampGranularity::Word => {
+            convert_to_hierarchical_word_segments(timestamped_result, segmentation)
+        }
         TimestampGranularity::Segment => {
-            convert_to_hierarchical_segment_segments(timestamped_result)
+            convert_to_hierarchical_segment_segments(timestamped_result, segmentation)
         }
     }
 }
@@ -84,6 +98,10 @@ fn convert_to_raw_token_segments(
             start: timestamp,
             end: end_timestamp,
             text: token.clone(), // Raw token text, including spaces and subword pieces
+            words: None,
+            confidence: timestamped_result.confidences.get(i).copied(),
+            no_speech_prob: None,
+            language: None,
         });
     }
 
@@ -93,12 +111,11 @@ fn convert_to_raw_token_segments(
 // Uses hierarchical approach for clean word-level timestamps
 fn convert_to_hierarchical_word_segments(
     timestamped_result: &TimestampedResult,
+    segmentation: &SegmentationParams,
 ) -> Vec<TranscriptionSegment> {
-    let segment_separators = ['.', '?', '!'];
     let word_separator = ' ';
 
-    let utterance =
-        build_utterance_from_tokens(timestamped_result, &segment_separators, word_separator);
+    let utterance = build_utterance_from_tokens(timestamped_result, segmentation, word_separator);
 
     extract_word_segments(&utterance)
 }
@@ -106,19 +123,18 @@ fn convert_to_hierarchical_word_segments(
 // Uses hierarchical approach for clean segment-level timestamps
 fn convert_to_hierarchical_segment_segments(
     timestamped_result: &TimestampedResult,
+    segmentation: &SegmentationParams,
 ) -> Vec<TranscriptionSegment> {
-    let segment_separators = ['.', '?', '!'];
     let word_separator = ' ';
 
-    let utterance =
-        build_utterance_from_tokens(timestamped_result, &segment_separators, word_separator);
+    let utterance = build_utterance_from_tokens(timestamped_result, segmentation, word_separator);
 
     extract_segment_segments(&utterance)
 }
 
 fn build_utterance_from_tokens(
     timestamped_result: &TimestampedResult,
-    segment_separators: &[char],
+    segmentation: &SegmentationParams,
     word_separator: char,
 ) -> Utterance {
     // Handle empty input
@@ -145,7 +161,7 @@ fn build_utterance_from_tokens(
     let words = group_tokens_into_words_hierarchical(&tokens, word_separator);
 
     // Step 3: Group words into segments
-    let segments = group_words_into_segments(&words, segment_separators);
+    let segments = group_words_into_segments(&words, segmentation);
 
     Utterance {
         text: timestamped_result.text.clone(),
@@ -174,6 +190,7 @@ fn create_tokens_from_timestamped_result(timestamped_result: &TimestampedResult)
             t_start: timestamp,
             t_end,
             is_blank: token_text.trim().is_empty(),
+            confidence: timestamped_result.confidences.get(i).copied(),
         });
     }
 
@@ -257,20 +274,34 @@ fn create_word_from_tokens(tokens: &[Token]) -> Word {
     }
 }
 
-fn group_words_into_segments(words: &[Word], segment_separators: &[char]) -> Vec<Segment> {
+fn group_words_into_segments(words: &[Word], segmentation: &SegmentationParams) -> Vec<Segment> {
     if words.is_empty() {
         return Vec::new();
     }
 
     let mut segments = Vec::new();
-    let mut current_segment_words = Vec::new();
+    let mut current_segment_words: Vec<Word> = Vec::new();
 
     for (i, word) in words.iter().enumerate() {
         current_segment_words.push(word.clone());
 
         // Check if word ends with segment separator or if it's the last word
-        let ends_segment =
-            word.text.chars().any(|c| segment_separators.contains(&c)) || i == words.len() - 1; // Always end on last word
+        let ends_segment = match words.get(i + 1) {
+            None => true, // Always end on last word
+            Some(next) => {
+                word.text.chars().any(|c| segmentation.separators.contains(&c))
+                    || segmentation.max_gap_ms.is_some_and(|gap| {
+                        (next.t_start - last_emission(word)) * 1000.0 >= gap as f32
+                    })
+                    || segmentation
+                        .max_words
+                        .is_some_and(|max| current_segment_words.len() >= max)
+                    // Break before the next word would make the segment too long
+                    || segmentation.max_duration.is_some_and(|max| {
+                        next.t_end - current_segment_words[0].t_start > max
+                    })
+            }
+        };
 
         if ends_segment {
             let segment = create_segment_from_words(&current_segment_words);
@@ -292,6 +323,13 @@ fn group_words_into_segments(words: &[Word], segment_separators: &[char]) -> Vec
     segments
 }
 
+/// When the last token of `word` was emitted. Tokens only carry a start
+/// time and each one lasts until the next, so a word's end time includes any
+/// pause that follows it.
+fn last_emission(word: &Word) -> f32 {
+    word.tokens.last().map_or(word.t_start, |token| token.t_start)
+}
+
 fn create_segment_from_words(words: &[Word]) -> Segment {
     if words.is_empty() {
         return Segment {
@@ -332,6 +370,10 @@ fn extract_word_segments(utterance: &Utterance) -> Vec<TranscriptionSegment> {
                     start: word.t_start,
                     end: word.t_end,
                     text: word.text.clone(),
+                    words: None,
+                    confidence: mean_confidence(&word.tokens),
+                    no_speech_prob: None,
+                    language: None,
                 });
             }
         }
@@ -349,13 +391,70 @@ fn extract_segment_segments(utterance: &Utterance) -> Vec<TranscriptionSegment>
             start: segment.t_start,
             end: segment.t_end,
             text: segment.text.clone(),
+            words: Some(
+                segment
+                    .words
+                    .iter()
+                    .map(|word| TranscriptionWord {
+                        start: word.t_start,
+                        end: word.t_end,
+                        text: word.text.clone(),
+                        confidence: mean_confidence(&word.tokens),
+                    })
+                    .collect(),
+            ),
+            confidence: mean_confidence(segment.words.iter().flat_map(|w| &w.tokens)),
+            no_speech_prob: None,
+            language: None,
         })
         .collect()
 }
 
+/// Append restored punctuation to the last token of each word, so that
+/// segment grouping can split an unpunctuated result into sentences.
+/// Returns the number of marks inserted.
+pub fn restore_punctuation(
+    timestamped_result: &mut TimestampedResult,
+    restorer: &mut dyn PunctuationRestorer,
+) -> Result<usize, PunctuationError> {
+    let tokens = create_tokens_from_timestamped_result(timestamped_result);
+    let words = group_tokens_into_words_hierarchical(&tokens, ' ');
+    let timings: Vec<TranscriptionWord> = words
+        .iter()
+        .map(|word| TranscriptionWord {
+            start: word.t_start,
+            end: word.t_end,
+            text: word.text.clone(),
+            confidence: None,
+        })
+        .collect();
+    let predictions = restorer.predict(&timings)?;
+
+    let mut inserted = 0;
+    let mut text = Vec::with_capacity(words.len());
+    for (word, prediction) in words.iter().zip(predictions) {
+        let mut word_text = word.text.clone();
+        let last_token = word.tokens.last().and_then(|t| t.token_id);
+        let ends_with_punct = word_text.ends_with(|c: char| !c.is_alphanumeric());
+        if let (Some(punctuation), Some(id), false) = (prediction, last_token, ends_with_punct) {
+            let full_width = word_text.ends_with(is_cjk);
+            let symbol = punctuation.symbol(full_width);
+            timestamped_result.tokens[id].push(symbol);
+            word_text.push(symbol);
+            inserted += 1;
+        }
+        text.push(word_text);
+    }
+
+    if inserted > 0 {
+        timestamped_result.text = text.join(" ");
+    }
+    Ok(inserted)
+}
+
 // Legacy functions for backward compatibility
 pub fn group_tokens_into_words(timestamped_result: &TimestampedResult) -> Vec<WordBoundary> {
-    let words = convert_to_hierarchical_word_segments(timestamped_result);
+    let words = convert_to_hierarchical_word_segments(timestamped_result, &SegmentationParams::default());
 
     words
         .into_iter()
@@ -366,3 +465,83 @@ pub fn group_tokens_into_words(timestamped_result: &TimestampedResult) -> Vec<Wo
         })
         .collect()
 }
+
+/// Average confidence of the tokens that have one.
+fn mean_confidence<'a>(tokens: impl IntoIterator<Item = &'a Token>) -> Option<f32> {
+    let confidences: Vec<f32> = tokens.into_iter().filter_map(|t| t.confidence).collect();
+    (!confidences.is_empty()).then(|| confidences.iter().sum::<f32>() / confidences.len() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One SentencePiece token per word, starting at the given times
+    fn result(words: &[(&str, f32)]) -> TimestampedResult {
+        TimestampedResult {
+            text: words.iter().map(|(w, _)| *w).collect::<Vec<_>>().join(" "),
+            timestamps: words.iter().map(|(_, t)| *t).collect(),
+            tokens: words.iter().map(|(w, _)| format!(" {}", w)).collect(),
+            confidences: Vec::new(),
+        }
+    }
+
+    fn texts(segments: &[TranscriptionSegment]) -> Vec<&str> {
+        segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_segments_split_on_custom_separators() {
+        let result = result(&[("你好。", 0.0), ("今天", 0.5), ("好嗎？", 1.0), ("好", 1.5)]);
+        let segments = convert_timestamps(&result, TimestampGranularity::Segment);
+        assert_eq!(texts(&segments), vec!["你好。", "今天 好嗎？", "好"]);
+
+        let ascii_only = SegmentationParams {
+            separators: vec!['.'],
+            ..Default::default()
+        };
+        let segments =
+            convert_timestamps_with_params(&result, TimestampGranularity::Segment, &ascii_only);
+        assert_eq!(segments.len(), 1);
+    }
+
+    #[test]
+    fn test_segments_split_on_pauses_and_limits() {
+        let result = result(&[
+            ("so", 0.0),
+            ("we", 0.2),
+            ("left", 0.4),
+            ("then", 1.5),
+            ("it", 1.7),
+            ("rained", 1.9),
+            ("all", 2.1),
+            ("day", 2.3),
+        ]);
+        let params = SegmentationParams {
+            max_gap_ms: Some(500),
+            max_words: Some(4),
+            ..Default::default()
+        };
+        let segments =
+            convert_timestamps_with_params(&result, TimestampGranularity::Segment, &params);
+        assert_eq!(texts(&segments), vec!["so we left", "then it rained all", "day"]);
+
+        // One word every 0.3 s, no pauses
+        let result = self::result(&[
+            ("one", 0.0),
+            ("two", 0.3),
+            ("three", 0.6),
+            ("four", 0.9),
+            ("five", 1.2),
+            ("six", 1.5),
+            ("seven", 1.8),
+        ]);
+        let params = SegmentationParams {
+            max_duration: Some(1.0),
+            ..Default::default()
+        };
+        let segments =
+            convert_timestamps_with_params(&result, TimestampGranularity::Segment, &params);
+        assert_eq!(texts(&segments), vec!["one two three", "four five six seven"]);
+    }
+}
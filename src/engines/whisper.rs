@@ -39,7 +39,7 @@
 //! ## With Custom Parameters and Initial Prompt
 //!
 //! ```rust,no_run
-//! use transcribe_rs::{TranscriptionEngine, engines::whisper::{WhisperEngine, WhisperInferenceParams}};
+//! use transcribe_rs::{TranscriptionEngine, engines::whisper::{WhisperEngine, WhisperInferenceParams, WhisperSampling}};
 //! use std::path::PathBuf;
 //!
 //! let mut engine = WhisperEngine::new();
@@ -55,14 +55,42 @@
 //!     ..Default::default()
 //! };
 //!
+//! // Faster greedy decoding with shorter segments
+//! let fast = WhisperInferenceParams {
+//!     sampling: WhisperSampling::Greedy { best_of: 1 },
+//!     temperature_inc: 0.0,
+//!     max_len: 40,
+//!     split_on_word: true,
+//!     token_timestamps: true,
+//!     n_threads: Some(8),
+//!     ..Default::default()
+//! };
+//!
 //! let result = engine.transcribe_file(&PathBuf::from("audio.wav"), Some(params))?;
 //! println!("Transcription: {}", result.text);
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
-
-use crate::{TranscriptionEngine, TranscriptionResult, TranscriptionSegment};
+//!
+//! ## Repetition Loops
+//!
+//! Whisper can get stuck repeating one phrase for minutes. Segments whose
+//! text is too repetitive (see [`RepetitionRetryParams`]) are decoded again
+//! without previous-text conditioning and with rising temperature. The
+//! retries of the last transcription are available from
+//! [`WhisperEngine::last_retries`].
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::text::is_cjk;
+use crate::{TranscriptionEngine, TranscriptionResult, TranscriptionSegment, TranscriptionWord};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::collections::HashSet;
+use std::ffi::c_void;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState};
 
 /// Parameters for configuring Whisper model loading.
 ///
@@ -72,10 +100,52 @@ use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextPar
 #[derive(Debug, Clone, Default)]
 pub struct WhisperModelParams {}
 
+/// Decoding strategy used by Whisper.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhisperSampling {
+    /// Pick the most likely token at each step. Fastest.
+    Greedy {
+        /// Number of candidates sampled when decoding at a temperature above 0
+        best_of: i32,
+    },
+    /// Keep the `beam_size` most likely sequences. Slower but more accurate.
+    BeamSearch {
+        beam_size: i32,
+        /// Beam search patience; not implemented by whisper.cpp, keep at -1.0
+        patience: f32,
+    },
+}
+
+impl Default for WhisperSampling {
+    fn default() -> Self {
+        Self::BeamSearch {
+            beam_size: 3,
+            patience: -1.0,
+        }
+    }
+}
+
+impl From<WhisperSampling> for SamplingStrategy {
+    fn from(sampling: WhisperSampling) -> Self {
+        match sampling {
+            WhisperSampling::Greedy { best_of } => Self::Greedy { best_of },
+            WhisperSampling::BeamSearch {
+                beam_size,
+                patience,
+            } => Self::BeamSearch {
+                beam_size,
+                patience,
+            },
+        }
+    }
+}
+
 /// Parameters for configuring Whisper inference behavior.
 ///
 /// These parameters control various aspects of the transcription process,
-/// including language detection, output formatting, and noise suppression.
+/// including language detection, output formatting, noise suppression and
+/// decoding. The decoding defaults match whisper.cpp, except for beam search
+/// with a beam size of 3.
 #[derive(Debug, Clone)]
 pub struct WhisperInferenceParams {
     /// Target language for transcription (e.g., "en", "es", "fr").
@@ -112,6 +182,54 @@ pub struct WhisperInferenceParams {
     /// context, vocabulary hints, or style guidance to the model.
     /// Limited to 224 tokens maximum.
     pub initial_prompt: Option<String>,
+
+    /// Greedy or beam search decoding
+    pub sampling: WhisperSampling,
+
+    /// Initial sampling temperature; 0.0 is deterministic
+    pub temperature: f32,
+
+    /// Temperature increase for each fallback when a decoding fails the
+    /// entropy or log probability threshold. 0.0 disables the fallback.
+    pub temperature_inc: f32,
+
+    /// Decodings with a token entropy above this are retried at a higher temperature
+    pub entropy_thold: f32,
+
+    /// Decodings with an average log probability below this are retried at a higher temperature
+    pub logprob_thold: f32,
+
+    /// Maximum segment length in characters; 0 means no limit.
+    /// Requires `token_timestamps`.
+    pub max_len: i32,
+
+    /// Split segments at word boundaries instead of tokens when `max_len` applies
+    pub split_on_word: bool,
+
+    /// Whether to compute token-level timestamps. Segments then carry word
+    /// timings, with one entry per character for CJK text.
+    pub token_timestamps: bool,
+
+    /// Number of CPU threads; None uses min(4, available cores)
+    pub n_threads: Option<i32>,
+
+    /// Whether to decode each window without the text of the previous one.
+    /// Conditioning on previous text keeps style consistent but makes
+    /// repetition loops more likely.
+    pub no_context: bool,
+
+    /// Whether to compute `no_speech_prob` for each segment. This costs one
+    /// extra encoder pass per 30 seconds of audio.
+    pub compute_no_speech_prob: bool,
+
+    /// Detection and re-decoding of repetition loops.
+    /// Set to None to keep the first decoding as-is.
+    pub repetition_retry: Option<RepetitionRetryParams>,
+
+    /// Flag that stops decoding once set; the transcription then fails.
+    /// whisper.cpp checks it during decoding, so it also interrupts the
+    /// audio currently being decoded.
+    pub abort: Option<Arc<AtomicBool>>,
 }
 
 impl Default for WhisperInferenceParams {
@@ -127,10 +245,113 @@ impl Default for WhisperInferenceParams {
             suppress_non_speech_tokens: true,
             no_speech_thold: 0.2,
             initial_prompt: None,
+            sampling: WhisperSampling::default(),
+            temperature: 0.0,
+            temperature_inc: 0.2,
+            entropy_thold: 2.4,
+            logprob_thold: -1.0,
+            max_len: 0,
+            split_on_word: false,
+            token_timestamps: false,
+            n_threads: None,
+            no_context: true,
+            compute_no_speech_prob: false,
+            repetition_retry: Some(RepetitionRetryParams::default()),
+            abort: None,
         }
     }
 }
 
+/// Parameters for detecting repetition loops and re-decoding them.
+///
+/// A segment counts as a loop when its zlib compression ratio or its share
+/// of repeated character n-grams exceeds the thresholds. Such a segment's
+/// audio is decoded again once per entry in `temperatures`, without
+/// conditioning on previous text, until the result is no longer a loop.
+#[derive(Debug, Clone)]
+pub struct RepetitionRetryParams {
+    /// Maximum ratio of text size to zlib-compressed size (Whisper uses 2.4)
+    pub compression_ratio_threshold: f32,
+    /// Length of the character n-grams compared for repetition
+    pub ngram_size: usize,
+    /// Maximum share (0.0-1.0) of n-grams that repeat an earlier one
+    pub max_repeated_ngram_ratio: f32,
+    /// Segments with fewer letters and digits than this are never loops
+    pub min_chars: usize,
+    /// Sampling temperature of each retry; 0.0 decodes greedily
+    pub temperatures: Vec<f32>,
+}
+
+impl Default for RepetitionRetryParams {
+    fn default() -> Self {
+        Self {
+            compression_ratio_threshold: 2.4,
+            ngram_size: 4,
+            max_repeated_ngram_ratio: 0.5,
+            min_chars: 20,
+            temperatures: vec![0.0, 0.4, 0.8],
+        }
+    }
+}
+
+impl RepetitionRetryParams {
+    /// Whether `text` looks like a repetition loop.
+    pub fn is_repetitive(&self, text: &str) -> bool {
+        let chars: Vec<char> = text
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect();
+        if chars.len() < self.min_chars {
+            return false;
+        }
+
+        compression_ratio(text) > self.compression_ratio_threshold
+            || repeated_ngram_ratio(&chars, self.ngram_size) > self.max_repeated_ngram_ratio
+    }
+}
+
+/// Size of `text` divided by its zlib-compressed size.
+///
+/// Repetitive text compresses well, so loops have a high ratio.
+pub fn compression_ratio(text: &str) -> f32 {
+    if text.is_empty() {
+        return 0.0;
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    let compressed = encoder
+        .write_all(text.as_bytes())
+        .and_then(|_| encoder.finish())
+        .map_or(text.len(), |c| c.len());
+    text.len() as f32 / compressed as f32
+}
+
+/// Share of the `n`-grams of `chars` that repeat an earlier n-gram.
+fn repeated_ngram_ratio(chars: &[char], n: usize) -> f32 {
+    if n == 0 || chars.len() < n {
+        return 0.0;
+    }
+    let ngrams: Vec<&[char]> = chars.windows(n).collect();
+    let unique: HashSet<&[char]> = ngrams.iter().copied().collect();
+    1.0 - unique.len() as f32 / ngrams.len() as f32
+}
+
+/// A segment that was detected as a repetition loop and decoded again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetriedSegment {
+    /// Start of the segment in seconds
+    pub start: f32,
+    /// End of the segment in seconds
+    pub end: f32,
+    /// Text of the first decoding
+    pub original_text: String,
+    /// Number of re-decodings that were run
+    pub attempts: usize,
+    /// Whether a retry produced text that is no longer a loop. When all
+    /// retries fail the least repetitive result is kept.
+    pub resolved: bool,
+}
+
 /// Whisper speech recognition engine.
 ///
 /// This engine uses OpenAI's Whisper model for speech-to-text transcription.
@@ -154,6 +375,7 @@ pub struct WhisperEngine {
     loaded_model_path: Option<PathBuf>,
     state: Option<whisper_rs::WhisperState>,
     context: Option<whisper_rs::WhisperContext>,
+    last_retries: Vec<RetriedSegment>,
 }
 
 impl Default for WhisperEngine {
@@ -181,8 +403,401 @@ impl WhisperEngine {
             loaded_model_path: None,
             state: None,
             context: None,
+            last_retries: Vec::new(),
+        }
+    }
+
+    /// Segments of the last transcription that were re-decoded because they
+    /// looked like repetition loops.
+    pub fn last_retries(&self) -> &[RetriedSegment] {
+        &self.last_retries
+    }
+
+    /// Identify the spoken language from the first 30 seconds of `samples`
+    /// (16 kHz mono).
+    ///
+    /// Returns every language Whisper knows with its probability, most
+    /// likely first. Requires a multilingual model.
+    ///
+    /// ```rust,no_run
+    /// use transcribe_rs::{TranscriptionEngine, engines::whisper::WhisperEngine};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut engine = WhisperEngine::new();
+    /// engine.load_model(&PathBuf::from("models/whisper-medium-q4_1.bin"))?;
+    ///
+    /// let samples = transcribe_rs::audio::load_audio_file(&PathBuf::from("audio.mp3"))?;
+    /// let ranked = engine.detect_language(&samples)?;
+    /// println!("{} ({:.0}%)", ranked[0].language, ranked[0].probability * 100.0);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn detect_language(
+        &mut self,
+        samples: &[f32],
+    ) -> Result<Vec<LanguageProbability>, Box<dyn std::error::Error>> {
+        let state = self
+            .state
+            .as_mut()
+            .ok_or("Model not loaded. Call load_model() first.")?;
+
+        let threads = default_threads();
+        let window = (WINDOW_SECONDS * TARGET_SAMPLE_RATE as f32) as usize;
+        state.pcm_to_mel(&samples[..samples.len().min(window)], threads)?;
+        let (_, probs) = state.lang_detect(0, threads)?;
+
+        let mut ranked: Vec<LanguageProbability> = probs
+            .iter()
+            .enumerate()
+            .filter_map(|(id, &probability)| {
+                Some(LanguageProbability {
+                    language: whisper_rs::get_lang_str(id as i32)?.to_string(),
+                    probability,
+                })
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+        Ok(ranked)
+    }
+}
+
+/// A language and how likely it is to be spoken, from
+/// [`WhisperEngine::detect_language`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageProbability {
+    /// ISO 639-1 code as used by Whisper, e.g. `"zh"` or `"en"`
+    pub language: String,
+    /// Probability in `[0.0, 1.0]`
+    pub probability: f32,
+}
+
+/// Run one decoding pass over `samples`.
+///
+/// With `retry_temperature`, decodes greedily at exactly that temperature
+/// without conditioning on previous text, as used for loop retries.
+fn decode(
+    context: &WhisperContext,
+    state: &mut WhisperState,
+    whisper_params: &WhisperInferenceParams,
+    retry_temperature: Option<f32>,
+    samples: &[f32],
+) -> Result<Vec<TranscriptionSegment>, Box<dyn std::error::Error>> {
+    let mut full_params = match retry_temperature {
+        None => {
+            let mut params = FullParams::new(whisper_params.sampling.into());
+            params.set_temperature(whisper_params.temperature);
+            params.set_temperature_inc(whisper_params.temperature_inc);
+            params.set_no_context(whisper_params.no_context);
+            params
+        }
+        Some(temperature) => {
+            let mut retry_params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+            retry_params.set_temperature(temperature);
+            retry_params.set_temperature_inc(0.0);
+            retry_params.set_no_context(true);
+            retry_params
         }
+    };
+    full_params.set_entropy_thold(whisper_params.entropy_thold);
+    full_params.set_logprob_thold(whisper_params.logprob_thold);
+    full_params.set_max_len(whisper_params.max_len);
+    full_params.set_split_on_word(whisper_params.split_on_word);
+    full_params.set_token_timestamps(whisper_params.token_timestamps);
+    if let Some(n_threads) = whisper_params.n_threads {
+        full_params.set_n_threads(n_threads);
     }
+    full_params.set_language(whisper_params.language.as_deref());
+    full_params.set_translate(whisper_params.translate);
+    full_params.set_print_special(whisper_params.print_special);
+    full_params.set_print_progress(whisper_params.print_progress);
+    full_params.set_print_realtime(whisper_params.print_realtime);
+    full_params.set_print_timestamps(whisper_params.print_timestamps);
+    full_params.set_suppress_blank(whisper_params.suppress_blank);
+    full_params.set_suppress_non_speech_tokens(whisper_params.suppress_non_speech_tokens);
+    full_params.set_no_speech_thold(whisper_params.no_speech_thold);
+
+    if let Some(ref prompt) = whisper_params.initial_prompt {
+        full_params.set_initial_prompt(prompt);
+    }
+    // The safe closure variant of whisper-rs 0.13 hands its trampoline a
+    // pointer of the wrong type, so the flag is passed as raw user data.
+    if let Some(abort) = &whisper_params.abort {
+        // SAFETY: `whisper_params` keeps the flag alive until `full` returns,
+        // and the callback only reads it.
+        unsafe {
+            full_params.set_abort_callback(Some(abort_requested));
+            full_params.set_abort_callback_user_data(Arc::as_ptr(abort) as *mut c_void);
+        }
+    }
+
+    state.full(full_params, samples)?;
+
+    let num_segments = state.full_n_segments()?;
+    let mut segments = Vec::new();
+    for i in 0..num_segments {
+        let text = state.full_get_segment_text(i)?;
+        let start = state.full_get_segment_t0(i)? as f32 / 100.0;
+        let end = state.full_get_segment_t1(i)? as f32 / 100.0;
+
+        let mut tokens = Vec::new();
+        for j in 0..state.full_n_tokens(i)? {
+            let id = state.full_get_token_id(i, j)?;
+            if id >= context.token_eot() {
+                continue; // special and timestamp tokens
+            }
+            let data = state.full_get_token_data(i, j)?;
+            tokens.push(TimedToken {
+                bytes: context.token_to_cstr(id)?.to_bytes().to_vec(),
+                start: data.t0 as f32 / 100.0,
+                end: data.t1 as f32 / 100.0,
+                probability: data.p,
+            });
+        }
+        let confidence = (!tokens.is_empty())
+            .then(|| tokens.iter().map(|t| t.probability).sum::<f32>() / tokens.len() as f32);
+        // Token times are only meaningful with token timestamps enabled
+        let words = whisper_params
+            .token_timestamps
+            .then(|| group_words(&tokens));
+
+        segments.push(TranscriptionSegment {
+            start,
+            end,
+            text,
+            words,
+            confidence,
+            no_speech_prob: None,
+            language: None,
+        });
+    }
+    Ok(segments)
+}
+
+/// Abort callback for whisper.cpp; `user_data` points at the `abort` flag
+/// of [`WhisperInferenceParams`].
+unsafe extern "C" fn abort_requested(user_data: *mut c_void) -> bool {
+    (*(user_data as *const AtomicBool)).load(Ordering::SeqCst)
+}
+
+/// Length of Whisper's input window in seconds.
+const WINDOW_SECONDS: f32 = 30.0;
+
+/// Thread count whisper.cpp uses by default.
+fn default_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get().min(4))
+}
+
+/// Probability of the no-speech token for each 30 second window of
+/// `samples`, taken from the first decoder step as in Whisper.
+fn no_speech_probs(
+    context: &WhisperContext,
+    state: &mut WhisperState,
+    samples: &[f32],
+    threads: usize,
+) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    let window = (WINDOW_SECONDS * TARGET_SAMPLE_RATE as f32) as usize;
+    let mut probs = Vec::new();
+    for chunk in samples.chunks(window) {
+        state.pcm_to_mel(chunk, threads)?;
+        state.encode(0, threads)?;
+        state.decode(&[context.token_sot()], 0, threads)?;
+        let logits = state.get_logits()?;
+
+        let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let sum: f32 = logits.iter().map(|&l| (l - max).exp()).sum();
+        let no_speech = logits[context.token_nosp() as usize];
+        probs.push((no_speech - max).exp() / sum);
+    }
+    Ok(probs)
+}
+
+/// A text token with its timing, before grouping into words.
+struct TimedToken {
+    /// Raw token bytes; a multi-byte character may span several tokens
+    bytes: Vec<u8>,
+    start: f32,
+    end: f32,
+    probability: f32,
+}
+
+/// Opening brackets and quotes, which belong to the following word.
+const OPENING_PUNCTUATION: &[char] = &[
+    '(', '[', '{', '"', '\'', '“', '‘', '「', '『', '（', '［', '｛', '《', '〈', '【',
+];
+
+/// Collects characters into words.
+#[derive(Default)]
+struct WordBuilder {
+    words: Vec<TranscriptionWord>,
+    current: Option<TranscriptionWord>,
+    /// The current word is a single CJK character
+    current_is_cjk: bool,
+    /// Probabilities of the tokens in the current word, by token index
+    current_tokens: Vec<(usize, f32)>,
+}
+
+impl WordBuilder {
+    fn flush(&mut self) {
+        if let Some(mut word) = self.current.take() {
+            let sum: f32 = self.current_tokens.iter().map(|&(_, p)| p).sum();
+            word.confidence = Some(sum / self.current_tokens.len().max(1) as f32);
+            self.words.push(word);
+        }
+        self.current_is_cjk = false;
+        self.current_tokens.clear();
+    }
+
+    /// Add character `c` of the token at `token` with `probability`.
+    fn push(&mut self, c: char, start: f32, end: f32, token: usize, probability: f32) {
+        if c.is_whitespace() {
+            self.flush();
+            return;
+        }
+
+        let is_cjk_unit = is_cjk(c) && c.is_alphanumeric();
+        let has_content = self
+            .current
+            .as_ref()
+            .is_some_and(|w| w.text.chars().any(char::is_alphanumeric));
+        let starts_word = OPENING_PUNCTUATION.contains(&c)
+            || (is_cjk_unit && has_content)
+            || (c.is_alphanumeric() && self.current_is_cjk);
+        if starts_word {
+            self.flush();
+        }
+
+        match &mut self.current {
+            Some(word) => {
+                word.text.push(c);
+                word.end = end;
+            }
+            None => {
+                self.current = Some(TranscriptionWord {
+                    start,
+                    end,
+                    text: c.to_string(),
+                    confidence: None,
+                })
+            }
+        }
+        self.current_is_cjk |= is_cjk_unit;
+        if self.current_tokens.last().is_none_or(|&(last, _)| last != token) {
+            self.current_tokens.push((token, probability));
+        }
+    }
+}
+
+/// Group tokens into words.
+///
+/// Words end at whitespace, and every CJK ideograph, kana or hangul syllable
+/// is a word of its own. Punctuation attaches to the preceding word, opening
+/// brackets and quotes to the following one. A token's time span is divided
+/// evenly among its non-space characters, and a word's confidence is the
+/// average probability of its tokens.
+fn group_words(tokens: &[TimedToken]) -> Vec<TranscriptionWord> {
+    let mut builder = WordBuilder::default();
+    let mut pending = Vec::new();
+    let mut pending_start = 0.0;
+    let mut pending_probabilities = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        if pending.is_empty() {
+            pending_start = token.start;
+            pending_probabilities.clear();
+        }
+        pending.extend_from_slice(&token.bytes);
+        pending_probabilities.push(token.probability);
+        let text = match std::str::from_utf8(&pending) {
+            Ok(text) => text.to_string(),
+            // Character continues in the next token
+            Err(e) if e.error_len().is_none() => continue,
+            Err(_) => String::from_utf8_lossy(&pending).into_owned(),
+        };
+        pending.clear();
+        // A character split over several tokens counts as one token
+        let probability =
+            pending_probabilities.iter().sum::<f32>() / pending_probabilities.len() as f32;
+
+        let char_count = text.chars().filter(|c| !c.is_whitespace()).count().max(1) as f32;
+        let step = (token.end - pending_start).max(0.0) / char_count;
+        let mut start = pending_start;
+        for c in text.chars() {
+            let end = if c.is_whitespace() { start } else { start + step };
+            builder.push(c, start, end, index, probability);
+            start = end;
+        }
+    }
+
+    builder.flush();
+    builder.words
+}
+
+/// Re-decode segments that look like repetition loops, recording each retry.
+fn retry_repetitive_segments(
+    context: &WhisperContext,
+    state: &mut WhisperState,
+    whisper_params: &WhisperInferenceParams,
+    retry: &RepetitionRetryParams,
+    samples: &[f32],
+    segments: Vec<TranscriptionSegment>,
+    retries: &mut Vec<RetriedSegment>,
+) -> Result<Vec<TranscriptionSegment>, Box<dyn std::error::Error>> {
+    let mut output = Vec::with_capacity(segments.len());
+
+    for segment in segments {
+        let first = (segment.start * TARGET_SAMPLE_RATE as f32) as usize;
+        let last = ((segment.end * TARGET_SAMPLE_RATE as f32) as usize).min(samples.len());
+        if !retry.is_repetitive(&segment.text) || first >= last || retry.temperatures.is_empty() {
+            output.push(segment);
+            continue;
+        }
+
+        // Least repetitive candidate so far, by compression ratio
+        let mut best: Option<(f32, Vec<TranscriptionSegment>)> = None;
+        let mut attempts = 0;
+        let mut resolved = false;
+        for &temperature in &retry.temperatures {
+            attempts += 1;
+            let mut candidate = decode(
+                context,
+                state,
+                whisper_params,
+                Some(temperature),
+                &samples[first..last],
+            )?;
+            for seg in &mut candidate {
+                seg.shift(segment.start);
+                seg.end = seg.end.min(segment.end);
+            }
+
+            let text: String = candidate.iter().map(|s| s.text.as_str()).collect();
+            if !retry.is_repetitive(&text) {
+                best = Some((0.0, candidate));
+                resolved = true;
+                break;
+            }
+            let ratio = compression_ratio(&text);
+            if best.as_ref().is_none_or(|(best_ratio, _)| ratio < *best_ratio) {
+                best = Some((ratio, candidate));
+            }
+        }
+
+        retries.push(RetriedSegment {
+            start: segment.start,
+            end: segment.end,
+            original_text: segment.text.clone(),
+            attempts,
+            resolved,
+        });
+
+        match best {
+            Some((ratio, candidate)) if resolved || ratio < compression_ratio(&segment.text) => {
+                output.extend(candidate)
+            }
+            _ => output.push(segment),
+        }
+    }
+
+    Ok(output)
 }
 
 impl Drop for WhisperEngine {
@@ -226,56 +841,125 @@ impl TranscriptionEngine for WhisperEngine {
         samples: Vec<f32>,
         params: Option<Self::InferenceParams>,
     ) -> Result<TranscriptionResult, Box<dyn std::error::Error>> {
-        let state = self
-            .state
-            .as_mut()
-            .ok_or("Model not loaded. Call load_model() first.")?;
+        let (Some(context), Some(state)) = (self.context.as_ref(), self.state.as_mut()) else {
+            return Err("Model not loaded. Call load_model() first.".into());
+        };
 
         let whisper_params = params.unwrap_or_default();
-
-        let mut full_params = FullParams::new(SamplingStrategy::BeamSearch {
-            beam_size: 3,
-            patience: -1.0,
-        });
-        full_params.set_language(whisper_params.language.as_deref());
-        full_params.set_translate(whisper_params.translate);
-        full_params.set_print_special(whisper_params.print_special);
-        full_params.set_print_progress(whisper_params.print_progress);
-        full_params.set_print_realtime(whisper_params.print_realtime);
-        full_params.set_print_timestamps(whisper_params.print_timestamps);
-        full_params.set_suppress_blank(whisper_params.suppress_blank);
-        full_params.set_suppress_non_speech_tokens(whisper_params.suppress_non_speech_tokens);
-        full_params.set_no_speech_thold(whisper_params.no_speech_thold);
-        
-        if let Some(ref prompt) = whisper_params.initial_prompt {
-            full_params.set_initial_prompt(prompt);
+        self.last_retries.clear();
+
+        let mut segments = decode(context, state, &whisper_params, None, &samples)?;
+        // Read before retries replace the decoder state
+        let language = match whisper_params.language.as_deref() {
+            Some(language) if language != "auto" => Some(language.to_string()),
+            _ => whisper_rs::get_lang_str(state.full_lang_id_from_state()?).map(str::to_string),
+        };
+        if let Some(retry) = &whisper_params.repetition_retry {
+            segments = retry_repetitive_segments(
+                context,
+                state,
+                &whisper_params,
+                retry,
+                &samples,
+                segments,
+                &mut self.last_retries,
+            )?;
         }
 
-        state.full(full_params, &samples)?;
-
-        let num_segments = state
-            .full_n_segments()
-            .expect("failed to get number of segments");
-
-        let mut segments = Vec::new();
-        let mut full_text = String::new();
-
-        for i in 0..num_segments {
-            let text = state.full_get_segment_text(i)?;
-            let start = state.full_get_segment_t0(i)? as f32 / 100.0;
-            let end = state.full_get_segment_t1(i)? as f32 / 100.0;
+        if whisper_params.compute_no_speech_prob {
+            let threads = whisper_params.n_threads.map_or_else(default_threads, |n| n.max(1) as usize);
+            let probs = no_speech_probs(context, state, &samples, threads)?;
+            for segment in &mut segments {
+                let window = (segment.start / WINDOW_SECONDS) as usize;
+                segment.no_speech_prob = probs.get(window).or(probs.last()).copied();
+            }
+        }
 
-            segments.push(TranscriptionSegment {
-                start,
-                end,
-                text: text.clone(),
-            });
-            full_text.push_str(&text);
+        for segment in &mut segments {
+            segment.language = language.clone();
         }
 
+        let full_text: String = segments.iter().map(|s| s.text.as_str()).collect();
+
         Ok(TranscriptionResult {
             text: full_text.trim().to_string(),
             segments: Some(segments),
+            language,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_repetition_loops() {
+        let retry = RepetitionRetryParams::default();
+        assert!(retry.is_repetitive("好的好的好的好的好的好的好的好的好的好的好的好的"));
+        assert!(retry.is_repetitive(&"I'm sorry. ".repeat(8)));
+        assert!(!retry.is_repetitive("今天我們要討論下一季的預算，還有各部門的人力配置。"));
+        assert!(!retry.is_repetitive("The quick brown fox jumps over the lazy dog."));
+        // Too short to judge
+        assert!(!retry.is_repetitive("好的好的好的"));
+    }
+
+    #[test]
+    fn test_compression_ratio() {
+        assert!(compression_ratio(&"la ".repeat(100)) > 10.0);
+        assert!(compression_ratio("A normal sentence without much repetition.") < 1.5);
+        assert_eq!(compression_ratio(""), 0.0);
+    }
+
+    fn token(text: &str, start: f32, end: f32) -> TimedToken {
+        TimedToken {
+            bytes: text.as_bytes().to_vec(),
+            start,
+            end,
+            probability: 0.9,
+        }
+    }
+
+    fn texts(words: &[TranscriptionWord]) -> Vec<&str> {
+        words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_group_words_latin() {
+        let words = group_words(&[
+            token(" Hel", 0.0, 0.2),
+            token("lo", 0.2, 0.4),
+            token(",", 0.4, 0.5),
+            token(" (big", 0.6, 0.9),
+            token(") world", 0.9, 1.5),
+        ]);
+        assert_eq!(texts(&words), ["Hello,", "(big)", "world"]);
+        assert_eq!((words[0].start, words[0].end), (0.0, 0.5));
+        assert!((words[0].confidence.unwrap() - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_group_words_cjk_per_character() {
+        // "你" split across two tokens as partial UTF-8
+        let ni = "你".as_bytes();
+        let words = group_words(&[
+            TimedToken {
+                bytes: ni[..2].to_vec(),
+                start: 0.0,
+                end: 0.1,
+                probability: 0.2,
+            },
+            TimedToken {
+                bytes: ni[2..].to_vec(),
+                start: 0.1,
+                end: 0.3,
+                probability: 0.4,
+            },
+            token("好。", 0.3, 0.5),
+            token("「OK」", 0.5, 0.9),
+        ]);
+        assert_eq!(texts(&words), ["你", "好。", "「OK」"]);
+        assert_eq!((words[0].start, words[0].end), (0.0, 0.3));
+        assert!((words[0].confidence.unwrap() - 0.3).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,788 @@
+//! Removal of hallucinated text from transcripts.
+//!
+//! Whisper-style models invent text where there is none: subtitle credits
+//! ("Amara.org"), channel plugs ("請不吝點贊訂閱..."), the same phrase looped
+//! over and over, or whole sentences over silence. [`HallucinationFilter`]
+//! removes these using
+//!
+//! - **rule sets** of literal phrases, regular expressions and "exact"
+//!   segment texts, either shared or per language, loaded from JSON files
+//!   ([`HallucinationRules`]); a default set is bundled with the crate,
+//! - **loop detection** within a segment ("好的好的好的好的...") and across
+//!   consecutive identical segments,
+//! - **silence detection**: segments that barely overlap detected speech.
+//!
+//! Every change is recorded in a [`FilterReport`].
+//!
+//! # Rule files
+//!
+//! ```json
+//! {
+//!   "phrases": ["Amara.org"],
+//!   "patterns": ["(?i)subtitles? by .*"],
+//!   "exact": [],
+//!   "languages": {
+//!     "zh": { "phrases": ["請不吝點贊訂閱轉發打賞支持明鏡與點點欄目"], "exact": ["謝謝觀看"] }
+//!   }
+//! }
+//! ```
+//!
+//! `phrases` and `patterns` are cut out of any segment; `exact` entries drop
+//! a segment only when its whole text matches (ignoring case, spacing and
+//! punctuation), for phrases like "Thank you." that are also said for real.
+//!
+//! Per-language rules apply to segments in that language, taken from
+//! [`FilterParams::language`], the segment's own `language` or the language
+//! passed to [`HallucinationFilter::filter_in_language`]. When no language
+//! is known, the phrases and patterns of every language apply but no
+//! per-language `exact` rules do. Because `exact` texts are real speech as
+//! often as not, they only drop a segment that also looks like non-speech:
+//! little overlap with detected speech or a high `no_speech_prob`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use transcribe_rs::hallucination::HallucinationFilter;
+//! use transcribe_rs::TranscriptionSegment;
+//!
+//! let segments = vec![
+//!     TranscriptionSegment { start: 0.0, end: 3.0, text: "Welcome back.".to_string(), ..Default::default() },
+//!     TranscriptionSegment { start: 3.0, end: 5.0, text: "字幕由Amara.org社區提供".to_string(), ..Default::default() },
+//!     TranscriptionSegment { start: 5.0, end: 6.0, text: "Thank you.".to_string(), ..Default::default() },
+//! ];
+//!
+//! // "Thank you." is an English exact rule, but the language is unknown and
+//! // nothing suggests it was not said
+//! let (kept, report) = HallucinationFilter::default().filter(segments, None);
+//! assert_eq!(kept.len(), 2);
+//! assert_eq!(report.removals.len(), 1);
+//! ```
+
+use crate::vad::SpeechRegion;
+use crate::TranscriptionSegment;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use thiserror::Error;
+
+const BUILTIN_RULES: &str = include_str!("../data/hallucinations.json");
+
+#[derive(Error, Debug)]
+pub enum HallucinationError {
+    #[error("Failed to read rule file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid rule file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid pattern '{pattern}': {source}")]
+    Pattern {
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+}
+
+/// One list of rules.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSet {
+    /// Literal text removed wherever it occurs
+    pub phrases: Vec<String>,
+    /// Regular expressions removed wherever they match
+    pub patterns: Vec<String>,
+    /// Segment texts that are dropped when they make up the whole segment
+    pub exact: Vec<String>,
+}
+
+impl RuleSet {
+    fn extend(&mut self, other: RuleSet) {
+        self.phrases.extend(other.phrases);
+        self.patterns.extend(other.patterns);
+        self.exact.extend(other.exact);
+    }
+}
+
+/// Rules for every language plus per-language lists, keyed by ISO 639-1 code.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HallucinationRules {
+    #[serde(flatten)]
+    pub common: RuleSet,
+    #[serde(default)]
+    pub languages: HashMap<String, RuleSet>,
+}
+
+impl HallucinationRules {
+    /// The rules bundled with the crate (`data/hallucinations.json`).
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_RULES).expect("bundled hallucination rules are valid")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, HallucinationError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, HallucinationError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Add the rules of `other` to these.
+    pub fn extend(&mut self, other: HallucinationRules) {
+        self.common.extend(other.common);
+        for (language, rules) in other.languages {
+            self.languages.entry(language).or_default().extend(rules);
+        }
+    }
+}
+
+/// Thresholds for loop and silence detection.
+#[derive(Debug, Clone)]
+pub struct FilterParams {
+    /// Apply the per-language rules of this language (e.g. `"zh"` or
+    /// `"zh-TW"`) to every segment; when `None`, each segment's detected
+    /// language is used
+    pub language: Option<String>,
+    /// Repetitions of a phrase inside one segment that count as a loop
+    pub loop_min_repeats: usize,
+    /// Minimum length in characters of a loop, so short laughter ("哈哈哈")
+    /// or agreement ("對對對") is kept
+    pub loop_min_chars: usize,
+    /// Consecutive segments with the same text that count as a loop; all but
+    /// the first are dropped
+    pub segment_loop_min_repeats: usize,
+    /// Minimum fraction of a segment that must overlap detected speech
+    pub min_speech_ratio: f32,
+    /// An `exact` match is dropped when less than this fraction of the
+    /// segment overlaps detected speech...
+    pub exact_max_speech_ratio: f32,
+    /// ...or when its `no_speech_prob` is at least this high
+    pub exact_min_no_speech_prob: f32,
+}
+
+impl Default for FilterParams {
+    fn default() -> Self {
+        Self {
+            language: None,
+            loop_min_repeats: 3,
+            loop_min_chars: 16,
+            segment_loop_min_repeats: 3,
+            min_speech_ratio: 0.1,
+            exact_max_speech_ratio: 0.5,
+            exact_min_no_speech_prob: 0.5,
+        }
+    }
+}
+
+/// Why text was removed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "rule", rename_all = "snake_case")]
+pub enum RemovalReason {
+    /// Matched a literal phrase rule
+    Phrase(String),
+    /// Matched a pattern rule
+    Pattern(String),
+    /// The whole segment matched an exact rule
+    Exact(String),
+    /// A phrase repeated in a loop within the segment
+    Loop,
+    /// The segment repeated the previous ones
+    RepeatedSegment,
+    /// The segment lies (almost) entirely outside detected speech
+    Silence,
+}
+
+impl fmt::Display for RemovalReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Phrase(rule) => write!(f, "phrase \"{}\"", rule),
+            Self::Pattern(rule) => write!(f, "pattern /{}/", rule),
+            Self::Exact(rule) => write!(f, "exact \"{}\"", rule),
+            Self::Loop => write!(f, "repetition loop"),
+            Self::RepeatedSegment => write!(f, "repeated segment"),
+            Self::Silence => write!(f, "no speech"),
+        }
+    }
+}
+
+/// A single removal made by the filter.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Removal {
+    /// Start of the affected segment in seconds
+    pub start: f32,
+    /// End of the affected segment in seconds
+    pub end: f32,
+    /// The text that was removed
+    pub text: String,
+    pub reason: RemovalReason,
+    /// Whether the whole segment was dropped
+    pub segment_dropped: bool,
+}
+
+/// Everything a [`HallucinationFilter::filter`] call removed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FilterReport {
+    pub removals: Vec<Removal>,
+}
+
+impl FilterReport {
+    pub fn is_empty(&self) -> bool {
+        self.removals.is_empty()
+    }
+
+    /// Number of segments that were dropped entirely.
+    pub fn dropped_segments(&self) -> usize {
+        self.removals.iter().filter(|r| r.segment_dropped).count()
+    }
+}
+
+impl fmt::Display for FilterReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for removal in &self.removals {
+            writeln!(
+                f,
+                "[{:.2}s - {:.2}s] {} \"{}\" ({})",
+                removal.start,
+                removal.end,
+                if removal.segment_dropped {
+                    "dropped"
+                } else {
+                    "removed"
+                },
+                removal.text,
+                removal.reason
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// One [`RuleSet`] ready for matching.
+#[derive(Debug, Clone)]
+struct CompiledRules {
+    phrases: Vec<String>,
+    patterns: Vec<Regex>,
+    /// Normalized exact texts with the original rule
+    exact: Vec<(String, String)>,
+}
+
+impl CompiledRules {
+    fn new(rules: RuleSet) -> Result<Self, HallucinationError> {
+        let patterns = rules
+            .patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|source| HallucinationError::Pattern {
+                    pattern: pattern.clone(),
+                    source,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut phrases: Vec<String> = rules
+            .phrases
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .collect();
+        // Longer phrases first, so a phrase containing another is removed whole
+        phrases.sort_by_key(|p| std::cmp::Reverse(p.chars().count()));
+
+        Ok(Self {
+            phrases,
+            patterns,
+            exact: rules
+                .exact
+                .into_iter()
+                .map(|rule| (normalize(&rule), rule))
+                .filter(|(normalized, _)| !normalized.is_empty())
+                .collect(),
+        })
+    }
+}
+
+/// `"zh-TW"` → `"zh"`
+fn base_language(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or(language)
+        .to_ascii_lowercase()
+}
+
+/// Compiled hallucination rules plus detection thresholds.
+#[derive(Debug, Clone)]
+pub struct HallucinationFilter {
+    /// Common rules plus those of one language, by base language code
+    languages: HashMap<String, CompiledRules>,
+    /// Common rules only, for languages without rules of their own
+    common: CompiledRules,
+    /// Common rules plus the phrases and patterns of every language, for
+    /// segments in an unknown language
+    any_language: CompiledRules,
+    params: FilterParams,
+}
+
+impl Default for HallucinationFilter {
+    /// The bundled rules with default thresholds.
+    fn default() -> Self {
+        Self::new(&HallucinationRules::builtin(), FilterParams::default())
+            .expect("bundled hallucination patterns compile")
+    }
+}
+
+impl HallucinationFilter {
+    pub fn new(
+        rules: &HallucinationRules,
+        params: FilterParams,
+    ) -> Result<Self, HallucinationError> {
+        let mut languages: HashMap<String, RuleSet> = HashMap::new();
+        let mut any_language = rules.common.clone();
+        for (language, set) in &rules.languages {
+            languages
+                .entry(base_language(language))
+                .or_insert_with(|| rules.common.clone())
+                .extend(set.clone());
+            any_language.extend(RuleSet {
+                exact: Vec::new(),
+                ..set.clone()
+            });
+        }
+
+        Ok(Self {
+            languages: languages
+                .into_iter()
+                .map(|(language, set)| Ok((language, CompiledRules::new(set)?)))
+                .collect::<Result<_, HallucinationError>>()?,
+            common: CompiledRules::new(rules.common.clone())?,
+            any_language: CompiledRules::new(any_language)?,
+            params,
+        })
+    }
+
+    /// Rules for a segment in `language`.
+    fn rules_for(&self, language: Option<&str>) -> &CompiledRules {
+        match language.map(base_language) {
+            Some(language) => self.languages.get(&language).unwrap_or(&self.common),
+            None => &self.any_language,
+        }
+    }
+
+    /// Bundled rules extended with the rules in `path`.
+    pub fn with_rule_file(path: &Path, params: FilterParams) -> Result<Self, HallucinationError> {
+        let mut rules = HallucinationRules::builtin();
+        rules.extend(HallucinationRules::from_file(path)?);
+        Self::new(&rules, params)
+    }
+
+    /// Remove hallucinations from `segments`.
+    ///
+    /// Pass the detected speech regions to also drop segments over silence.
+    pub fn filter(
+        &self,
+        segments: Vec<TranscriptionSegment>,
+        speech: Option<&[SpeechRegion]>,
+    ) -> (Vec<TranscriptionSegment>, FilterReport) {
+        self.filter_in_language(segments, speech, None)
+    }
+
+    /// [`filter`](Self::filter) for a transcript in `language`, used for
+    /// segments that do not carry a language of their own.
+    pub fn filter_in_language(
+        &self,
+        segments: Vec<TranscriptionSegment>,
+        speech: Option<&[SpeechRegion]>,
+        language: Option<&str>,
+    ) -> (Vec<TranscriptionSegment>, FilterReport) {
+        let mut report = FilterReport::default();
+        let mut kept: Vec<TranscriptionSegment> = Vec::with_capacity(segments.len());
+
+        for mut segment in segments {
+            let ratio = speech.map(|speech| speech_ratio(&segment, speech));
+            if ratio.is_some_and(|ratio| ratio < self.params.min_speech_ratio) {
+                report
+                    .removals
+                    .push(dropped(&segment, RemovalReason::Silence));
+                continue;
+            }
+
+            let segment_language = self
+                .params
+                .language
+                .as_deref()
+                .or(segment.language.as_deref())
+                .or(language);
+            let rules = self.rules_for(segment_language);
+            let (text, removals) = self.clean_text(rules, &segment.text);
+
+            let normalized = normalize(&text);
+            let non_speech = ratio.is_some_and(|ratio| ratio < self.params.exact_max_speech_ratio)
+                || segment
+                    .no_speech_prob
+                    .is_some_and(|p| p >= self.params.exact_min_no_speech_prob);
+            let exact = rules.exact.iter().find(|(n, _)| *n == normalized);
+            if let Some((_, rule)) = exact.filter(|_| non_speech) {
+                report
+                    .removals
+                    .push(dropped(&segment, RemovalReason::Exact(rule.clone())));
+                continue;
+            }
+            if normalized.is_empty() {
+                if let Some((_, reason)) = removals.last() {
+                    // Nothing but punctuation is left
+                    report.removals.push(dropped(&segment, reason.clone()));
+                    continue;
+                }
+            }
+
+            if !removals.is_empty() {
+                // Word timings no longer match the text
+                segment.words = None;
+            }
+            report
+                .removals
+                .extend(removals.into_iter().map(|(text, reason)| Removal {
+                    start: segment.start,
+                    end: segment.end,
+                    text,
+                    reason,
+                    segment_dropped: false,
+                }));
+            segment.text = text;
+            kept.push(segment);
+        }
+
+        let kept = self.drop_repeated_segments(kept, &mut report);
+        report.removals.sort_by(|a, b| a.start.total_cmp(&b.start));
+        (kept, report)
+    }
+
+    /// Apply phrase, pattern and loop rules to one text. Returns the cleaned
+    /// text and what was removed.
+    fn clean_text(
+        &self,
+        rules: &CompiledRules,
+        text: &str,
+    ) -> (String, Vec<(String, RemovalReason)>) {
+        let mut text = text.to_string();
+        let mut removals = Vec::new();
+
+        for phrase in &rules.phrases {
+            if text.contains(phrase.as_str()) {
+                text = text.replace(phrase.as_str(), "");
+                removals.push((phrase.clone(), RemovalReason::Phrase(phrase.clone())));
+            }
+        }
+        for pattern in &rules.patterns {
+            let matched: Vec<&str> = pattern.find_iter(&text).map(|m| m.as_str()).collect();
+            if !matched.is_empty() {
+                let matched = matched.join(" ");
+                text = pattern.replace_all(&text, "").into_owned();
+                removals.push((matched, RemovalReason::Pattern(pattern.to_string())));
+            }
+        }
+
+        let (collapsed, looped) = collapse_loops(
+            &text,
+            self.params.loop_min_repeats,
+            self.params.loop_min_chars,
+        );
+        if !looped.is_empty() {
+            text = collapsed;
+            removals.push((looped, RemovalReason::Loop));
+        }
+
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (text, removals)
+    }
+
+    /// Keep only the first segment of runs of identical consecutive segments
+    /// that are long enough to be a loop.
+    fn drop_repeated_segments(
+        &self,
+        segments: Vec<TranscriptionSegment>,
+        report: &mut FilterReport,
+    ) -> Vec<TranscriptionSegment> {
+        let keys: Vec<String> = segments.iter().map(|s| normalize(&s.text)).collect();
+        let mut kept = Vec::with_capacity(segments.len());
+
+        let mut run_start = 0;
+        let mut run_len = 0;
+        for (i, segment) in segments.into_iter().enumerate() {
+            if i == 0 || keys[i] != keys[run_start] {
+                run_start = i;
+                run_len = keys[i..].iter().take_while(|k| **k == keys[i]).count();
+            }
+            if i > run_start && run_len >= self.params.segment_loop_min_repeats {
+                report
+                    .removals
+                    .push(dropped(&segment, RemovalReason::RepeatedSegment));
+            } else {
+                kept.push(segment);
+            }
+        }
+        kept
+    }
+}
+
+fn dropped(segment: &TranscriptionSegment, reason: RemovalReason) -> Removal {
+    Removal {
+        start: segment.start,
+        end: segment.end,
+        text: segment.text.clone(),
+        reason,
+        segment_dropped: true,
+    }
+}
+
+/// Lowercased letters and digits only, for comparing texts.
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Fraction of the segment's duration that overlaps speech.
+fn speech_ratio(segment: &TranscriptionSegment, speech: &[SpeechRegion]) -> f32 {
+    let duration = segment.end - segment.start;
+    if duration <= 0.0 {
+        return 1.0;
+    }
+    let overlap: f32 = speech
+        .iter()
+        .map(|r| (r.end.min(segment.end) - r.start.max(segment.start)).max(0.0))
+        .sum();
+    overlap / duration
+}
+
+/// Collapse phrases repeated `min_repeats` or more times in a row (spanning
+/// at least `min_chars` characters) to a single occurrence.
+///
+/// Returns the collapsed text and the removed repetitions.
+fn collapse_loops(text: &str, min_repeats: usize, min_chars: usize) -> (String, String) {
+    let chars: Vec<char> = text.chars().collect();
+    let n = chars.len();
+    let mut output = String::with_capacity(text.len());
+    let mut removed = String::new();
+
+    let mut i = 0;
+    while i < n {
+        let mut found = None;
+        for unit in 1..=(n - i) / min_repeats.max(2) {
+            let pattern = &chars[i..i + unit];
+            let mut repeats = 1;
+            while i + (repeats + 1) * unit <= n
+                && &chars[i + repeats * unit..i + (repeats + 1) * unit] == pattern
+            {
+                repeats += 1;
+            }
+            if repeats >= min_repeats && repeats * unit >= min_chars {
+                found = Some((unit, repeats));
+                break;
+            }
+        }
+
+        match found {
+            Some((unit, repeats)) => {
+                let pattern = &chars[i..i + unit];
+                let mut end = i + repeats * unit;
+                // A final repetition without its trailing separator
+                // ("sorry. sorry. sorry.") belongs to the loop too
+                let trimmed = pattern
+                    .iter()
+                    .rposition(|c| !c.is_whitespace())
+                    .map_or(0, |p| p + 1);
+                let keep = if end + trimmed <= n && chars[end..end + trimmed] == pattern[..trimmed]
+                {
+                    end += trimmed;
+                    &pattern[..trimmed]
+                } else {
+                    pattern
+                };
+                let looped_from = i + keep.len();
+                output.extend(keep);
+                removed.extend(&chars[looped_from..end]);
+                i = end;
+            }
+            None => {
+                output.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+
+    (output, removed.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f32, end: f32, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            start,
+            end,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn texts(segments: &[TranscriptionSegment]) -> Vec<&str> {
+        segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let segments = vec![
+            seg(
+                0.0,
+                2.0,
+                "今天的會議到此結束。請不吝點贊訂閱轉發打賞支持明鏡與點點欄目",
+            ),
+            TranscriptionSegment {
+                no_speech_prob: Some(0.8),
+                ..seg(2.0, 3.0, "謝謝觀看!")
+            },
+            seg(3.0, 5.0, "感謝大家今天謝謝觀看的朋友"),
+        ];
+
+        let (kept, report) = HallucinationFilter::default().filter_in_language(segments, None, Some("zh"));
+        assert_eq!(
+            texts(&kept),
+            vec!["今天的會議到此結束。", "感謝大家今天謝謝觀看的朋友"]
+        );
+        assert_eq!(report.removals.len(), 2);
+        assert!(report.removals[1].segment_dropped);
+        assert_eq!(
+            report.removals[1].reason,
+            RemovalReason::Exact("謝謝觀看".to_string())
+        );
+    }
+
+    #[test]
+    fn test_exact_rules_need_language_and_non_speech() {
+        let speech = vec![SpeechRegion {
+            start: 0.0,
+            end: 3.4,
+        }];
+        let segments = vec![
+            seg(0.0, 2.0, "Thank you."),
+            seg(3.0, 5.0, "Thank you."),
+            TranscriptionSegment {
+                language: Some("zh".to_string()),
+                ..seg(3.0, 5.0, "Bye.")
+            },
+        ];
+        let filter = HallucinationFilter::default();
+
+        // Only the English segment that is mostly over silence is dropped
+        let (kept, report) = filter.filter_in_language(segments.clone(), Some(&speech), Some("en"));
+        assert_eq!(kept.len(), 2);
+        assert_eq!(report.removals[0].start, 3.0);
+        assert_eq!(
+            report.removals[0].reason,
+            RemovalReason::Exact("Thank you.".to_string())
+        );
+
+        // Without a language, no per-language exact rules apply
+        let (kept, _) = filter.filter_in_language(segments.clone(), Some(&speech), None);
+        assert_eq!(kept.len(), 3);
+
+        // Without evidence of non-speech, exact rules never apply
+        let (kept, _) = filter.filter_in_language(segments, None, Some("en"));
+        assert_eq!(kept.len(), 3);
+    }
+
+    #[test]
+    fn test_zh_patterns_keep_following_speech() {
+        let segments = vec![
+            seg(0.0, 2.0, "字幕志願者 李宗盛"),
+            seg(2.0, 5.0, "請不吝點贊訂閱轉發,我們今天要討論的是明年的預算"),
+        ];
+        let (kept, _) = HallucinationFilter::default().filter_in_language(segments, None, Some("zh"));
+        assert_eq!(
+            texts(&kept),
+            vec!["請不吝點贊訂閱轉發,我們今天要討論的是明年的預算"]
+        );
+    }
+
+    #[test]
+    fn test_rule_file_and_language_selection() {
+        let rules = HallucinationRules::from_json(
+            r#"{ "patterns": ["(?i)brought to you by \\w+"], "languages": { "zh": { "phrases": ["明鏡"] } } }"#,
+        )
+        .unwrap();
+        let segments = vec![
+            seg(0.0, 2.0, "Hello, brought to you by ACME"),
+            seg(2.0, 3.0, "明鏡新聞"),
+        ];
+
+        let english = FilterParams {
+            language: Some("en".to_string()),
+            ..Default::default()
+        };
+        let (kept, _) = HallucinationFilter::new(&rules, english)
+            .unwrap()
+            .filter(segments.clone(), None);
+        assert_eq!(texts(&kept), vec!["Hello,", "明鏡新聞"]);
+
+        let chinese = FilterParams {
+            language: Some("zh-TW".to_string()),
+            ..Default::default()
+        };
+        let (kept, _) = HallucinationFilter::new(&rules, chinese)
+            .unwrap()
+            .filter(segments, None);
+        assert_eq!(texts(&kept), vec!["Hello,", "新聞"]);
+
+        assert!(matches!(
+            HallucinationRules::from_json(r#"{ "patterns": ["("] }"#)
+                .map(|rules| HallucinationFilter::new(&rules, FilterParams::default())),
+            Ok(Err(HallucinationError::Pattern { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_collapse_loops() {
+        assert_eq!(
+            collapse_loops("我覺得好的好的好的好的好的好的好的好的", 3, 16),
+            (
+                "我覺得好的".to_string(),
+                "好的好的好的好的好的好的好的".to_string()
+            )
+        );
+        assert_eq!(
+            collapse_loops("I'm sorry. I'm sorry. I'm sorry. I'm sorry.", 3, 16).0,
+            "I'm sorry."
+        );
+        assert_eq!(collapse_loops("哈哈哈，對對對", 3, 16).0, "哈哈哈，對對對");
+    }
+
+    #[test]
+    fn test_repeated_segments_keep_first() {
+        let segments = vec![
+            seg(0.0, 1.0, "Hello."),
+            seg(1.0, 2.0, "Let's go."),
+            seg(2.0, 3.0, "let's go"),
+            seg(3.0, 4.0, "Let's go."),
+            seg(4.0, 5.0, "Let's go."),
+            seg(5.0, 6.0, "Bye now."),
+        ];
+
+        let (kept, report) = HallucinationFilter::default().filter(segments, None);
+        assert_eq!(texts(&kept), vec!["Hello.", "Let's go.", "Bye now."]);
+        assert_eq!(report.dropped_segments(), 3);
+    }
+
+    #[test]
+    fn test_segments_over_silence_are_dropped() {
+        let speech = vec![SpeechRegion {
+            start: 0.0,
+            end: 4.0,
+        }];
+        let segments = vec![seg(0.5, 3.5, "real speech"), seg(10.0, 12.0, "invented")];
+
+        let (kept, report) = HallucinationFilter::default().filter(segments, Some(&speech));
+        assert_eq!(texts(&kept), vec!["real speech"]);
+        assert_eq!(report.removals[0].reason, RemovalReason::Silence);
+    }
+}
@@ -8,7 +8,7 @@
 //! - **Multiple Engines**: Support for both Whisper and Parakeet transcription engines
 //! - **Flexible Model Loading**: Load models with custom parameters (quantization, etc.)
 //! - **Timestamped Results**: Get detailed timing information for transcribed segments
-//! - **Audio Processing**: Built-in WAV file processing with proper format validation
+//! - **Audio Processing**: Built-in decoding of WAV, MP3, M4A/AAC, FLAC and Ogg/Vorbis without ffmpeg
 //! - **Unified API**: Common trait-based interface for all transcription engines
 //!
 //! ## Model Format Requirements
@@ -41,15 +41,26 @@
 //!
 //! ## Audio Requirements
 //!
-//! Input audio files must be:
-//! - WAV format
-//! - 16 kHz sample rate
-//! - 16-bit samples
-//! - Mono (single channel)
+//! `transcribe_file` accepts WAV (any sample rate, bit depth or channel count),
+//! MP3, M4A/AAC, FLAC and Ogg/Vorbis. Audio is decoded, down-mixed and
+//! resampled to 16 kHz mono in-process. `transcribe_samples` expects samples
+//! that are already 16 kHz mono.
 
 pub mod audio;
+pub mod chinese;
+pub mod cli;
 pub mod engines;
+pub mod hallucination;
+pub mod merge;
+pub mod normalize;
+pub mod openai_compat;
+pub mod punctuation;
+pub mod store;
 pub mod subtitle;
+pub mod text;
+pub mod transcript;
+pub mod vad;
+pub mod webhook;
 pub mod worker;
 
 pub mod remote;
@@ -61,20 +72,24 @@ use std::path::Path;
 /// The result of a transcription operation.
 ///
 /// Contains both the full transcribed text and detailed timing information
-/// for individual segments within the audio.
-#[derive(Debug)]
+/// for individual segments within the audio. See [`transcript::Transcript`]
+/// for the versioned JSON document with additional metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionResult {
     /// The complete transcribed text from the audio
     pub text: String,
     /// Individual segments with timing information
     pub segments: Option<Vec<TranscriptionSegment>>,
+    /// Spoken language as an ISO 639-1 code, when the engine reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
 }
 
 /// A single transcribed segment with timing information.
 ///
 /// Represents a portion of the transcribed audio with start and end timestamps
 /// and the corresponding text content.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TranscriptionSegment {
     /// Start time of the segment in seconds
     pub start: f32,
@@ -82,6 +97,45 @@ pub struct TranscriptionSegment {
     pub end: f32,
     /// The transcribed text for this segment
     pub text: String,
+    /// Word-level timings, when the engine produced them. CJK text has one
+    /// entry per character.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub words: Option<Vec<TranscriptionWord>>,
+    /// Average token probability of the segment in `[0.0, 1.0]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// Probability that the segment contains no speech (Whisper only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_speech_prob: Option<f32>,
+    /// Spoken language of the segment as an ISO 639-1 code, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl TranscriptionSegment {
+    /// Move the segment and its words by `offset` seconds.
+    pub fn shift(&mut self, offset: f32) {
+        self.start += offset;
+        self.end += offset;
+        for word in self.words.iter_mut().flatten() {
+            word.start += offset;
+            word.end += offset;
+        }
+    }
+}
+
+/// A single timed word (or CJK character) within a [`TranscriptionSegment`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionWord {
+    /// Start time of the word in seconds
+    pub start: f32,
+    /// End time of the word in seconds
+    pub end: f32,
+    /// The word, including attached punctuation but no surrounding spaces
+    pub text: String,
+    /// Average probability of the word's tokens in `[0.0, 1.0]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
 }
 
 /// Common interface for speech transcription engines.
@@ -178,17 +232,14 @@ pub trait TranscriptionEngine {
         params: Option<Self::InferenceParams>,
     ) -> Result<TranscriptionResult, Box<dyn std::error::Error>>;
 
-    /// Transcribe audio from a WAV file.
+    /// Transcribe audio from a file.
     ///
-    /// The WAV file must meet the following requirements:
-    /// - 16 kHz sample rate
-    /// - 16-bit samples
-    /// - Mono (single channel)
-    /// - PCM format
+    /// Any format supported by [`audio::load_audio_file`] is accepted. Multi-channel
+    /// audio is averaged to mono and resampled to 16 kHz before transcription.
     ///
     /// # Arguments
     ///
-    /// * `wav_path` - Path to the WAV file to transcribe
+    /// * `wav_path` - Path to the audio file to transcribe
     /// * `params` - Optional engine-specific inference parameters
     ///
     /// # Returns
@@ -199,7 +250,7 @@ pub trait TranscriptionEngine {
         wav_path: &Path,
         params: Option<Self::InferenceParams>,
     ) -> Result<TranscriptionResult, Box<dyn std::error::Error>> {
-        let samples = audio::read_wav_samples(wav_path)?;
+        let samples = audio::load_audio_file(wav_path)?;
         self.transcribe_samples(samples, params)
     }
 }
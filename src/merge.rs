@@ -0,0 +1,376 @@
+//! Merging of per-chunk transcripts into a single segment list.
+//!
+//! Long recordings are transcribed in chunks, and adjacent chunks may overlap
+//! (fixed-size splitting, or forced cuts inside long speech). The overlapping
+//! audio is then transcribed twice, usually with slightly different
+//! timestamps and wording. This module aligns the two versions by text
+//! similarity and timing and keeps exactly one of them. When nothing lines
+//! up, it falls back to cutting at the middle of the overlap and drops any
+//! duplicate left at the seam.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use transcribe_rs::merge::{merge_chunks, ChunkTranscript, MergeParams};
+//! use transcribe_rs::TranscriptionSegment;
+//!
+//! let seg = |start: f32, end: f32, text: &str| TranscriptionSegment {
+//!     start,
+//!     end,
+//!     text: text.to_string(),
+//!     ..Default::default()
+//! };
+//!
+//! let chunks = vec![
+//!     ChunkTranscript {
+//!         start: 0.0,
+//!         end: 30.0,
+//!         segments: vec![seg(0.0, 12.0, "first sentence"), seg(25.0, 29.5, "the overlap")],
+//!     },
+//!     ChunkTranscript {
+//!         start: 20.0,
+//!         end: 50.0,
+//!         segments: vec![seg(25.2, 29.0, "the overlap"), seg(31.0, 40.0, "last sentence")],
+//!     },
+//! ];
+//!
+//! let merged = merge_chunks(chunks, &MergeParams::default());
+//! assert_eq!(merged.len(), 3);
+//! ```
+
+use crate::TranscriptionSegment;
+use std::cmp::Ordering;
+
+/// Repeated text shorter than this many characters is not trimmed at a seam.
+const MIN_REPEAT_CHARS: usize = 4;
+
+/// Transcript of one chunk of a longer recording.
+#[derive(Debug, Clone)]
+pub struct ChunkTranscript {
+    /// Start of the chunk's audio in seconds (global time)
+    pub start: f32,
+    /// End of the chunk's audio in seconds (global time)
+    pub end: f32,
+    /// Segments with global timestamps
+    pub segments: Vec<TranscriptionSegment>,
+}
+
+impl ChunkTranscript {
+    /// Build a chunk transcript from segments timed relative to the chunk start.
+    pub fn from_relative(start: f32, end: f32, segments: Vec<TranscriptionSegment>) -> Self {
+        let segments = segments
+            .into_iter()
+            .map(|mut seg| {
+                seg.shift(start);
+                seg
+            })
+            .collect();
+
+        Self {
+            start,
+            end,
+            segments,
+        }
+    }
+}
+
+/// Parameters controlling how overlapping chunks are aligned.
+#[derive(Debug, Clone)]
+pub struct MergeParams {
+    /// Minimum text similarity (0.0-1.0) for two segments to count as the
+    /// same speech
+    pub similarity_threshold: f32,
+    /// Maximum start-time difference (seconds) between two versions of the
+    /// same segment
+    pub time_tolerance: f32,
+}
+
+impl Default for MergeParams {
+    fn default() -> Self {
+        Self {
+            similarity_threshold: 0.6,
+            time_tolerance: 2.0,
+        }
+    }
+}
+
+/// Merge chunk transcripts into one ordered, de-duplicated segment list.
+///
+/// Chunks may be passed in any order. Chunks that do not overlap are simply
+/// concatenated; overlapping chunks are stitched as described in the module
+/// documentation. Empty segments are dropped.
+pub fn merge_chunks(
+    mut chunks: Vec<ChunkTranscript>,
+    params: &MergeParams,
+) -> Vec<TranscriptionSegment> {
+    chunks.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(Ordering::Equal));
+
+    let mut merged: Vec<TranscriptionSegment> = Vec::new();
+    let mut covered_until = f32::NEG_INFINITY;
+
+    for chunk in chunks {
+        let mut incoming: Vec<TranscriptionSegment> = chunk
+            .segments
+            .into_iter()
+            .filter(|seg| !seg.text.trim().is_empty())
+            .collect();
+        incoming.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(Ordering::Equal));
+
+        if chunk.start >= covered_until {
+            merged.extend(incoming);
+        } else {
+            let overlap_end = covered_until.min(chunk.end);
+            stitch(&mut merged, incoming, chunk.start, overlap_end, params);
+        }
+
+        covered_until = covered_until.max(chunk.end);
+    }
+
+    merged
+}
+
+/// Join `incoming` onto `merged` across the overlap `[overlap_start, overlap_end]`.
+fn stitch(
+    merged: &mut Vec<TranscriptionSegment>,
+    incoming: Vec<TranscriptionSegment>,
+    overlap_start: f32,
+    overlap_end: f32,
+    params: &MergeParams,
+) {
+    let cut = (overlap_start + overlap_end) / 2.0;
+
+    // Only segments inside the overlap can be duplicates of each other
+    let tail_from = merged
+        .iter()
+        .position(|seg| seg.end > overlap_start)
+        .unwrap_or(merged.len());
+    let head_to = incoming
+        .iter()
+        .position(|seg| seg.start >= overlap_end)
+        .unwrap_or(incoming.len());
+
+    // The matching pair closest to the middle of the overlap is the most
+    // reliable anchor: both chunks had plenty of context around it
+    let mut anchor: Option<(usize, usize, f32)> = None;
+    for (i, a) in merged.iter().enumerate().skip(tail_from) {
+        for (j, b) in incoming.iter().enumerate().take(head_to) {
+            if !is_same_speech(a, b, params) {
+                continue;
+            }
+            let distance = (midpoint(a) - cut).abs();
+            if anchor.is_none_or(|(_, _, best)| distance < best) {
+                anchor = Some((i, j, distance));
+            }
+        }
+    }
+
+    match anchor {
+        Some((i, j, _)) => {
+            // Earlier chunk up to the anchor, later chunk after it
+            merged.truncate(i + 1);
+            merged.extend(incoming.into_iter().skip(j + 1));
+        }
+        None => {
+            merged.retain(|seg| midpoint(seg) < cut);
+            for mut seg in incoming.into_iter().filter(|seg| midpoint(seg) >= cut) {
+                if let Some(last) = merged.last() {
+                    if is_same_speech(last, &seg, params) {
+                        continue;
+                    }
+                    let trimmed = trim_repeated_prefix(&last.text, &seg.text);
+                    if trimmed.trim().is_empty() {
+                        continue;
+                    }
+                    if trimmed != seg.text {
+                        // Word timings no longer match the text
+                        seg.words = None;
+                        seg.text = trimmed;
+                    }
+                }
+                merged.push(seg);
+            }
+        }
+    }
+}
+
+fn midpoint(seg: &TranscriptionSegment) -> f32 {
+    (seg.start + seg.end) / 2.0
+}
+
+fn is_same_speech(a: &TranscriptionSegment, b: &TranscriptionSegment, params: &MergeParams) -> bool {
+    (a.start - b.start).abs() <= params.time_tolerance
+        && text_similarity(&a.text, &b.text) >= params.similarity_threshold
+}
+
+/// Remove the start of `next` when it repeats the end of `prev`.
+///
+/// Handles sentences that were cut at different points by two chunks, e.g.
+/// `"we should ship it"` followed by `"ship it on Friday"`.
+fn trim_repeated_prefix(prev: &str, next: &str) -> String {
+    let prev_chars: Vec<char> = prev.trim_end().chars().collect();
+    let next_trimmed = next.trim_start();
+    let next_chars: Vec<char> = next_trimmed.chars().collect();
+
+    let longest = (MIN_REPEAT_CHARS..=prev_chars.len().min(next_chars.len()))
+        .rev()
+        .find(|&k| prev_chars[prev_chars.len() - k..] == next_chars[..k]);
+
+    match longest {
+        Some(k) => next_chars[k..].iter().collect::<String>().trim_start().to_string(),
+        None => next.to_string(),
+    }
+}
+
+/// Similarity of two texts in `[0.0, 1.0]`, ignoring case, spacing and punctuation.
+///
+/// Uses the Sørensen–Dice coefficient over character bigrams, which works the
+/// same for CJK text (no spaces) and space-separated languages.
+pub fn text_similarity(a: &str, b: &str) -> f32 {
+    let normalize = |s: &str| -> Vec<char> {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let a = normalize(a);
+    let b = normalize(b);
+
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.len() < 2 || b.len() < 2 {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+
+    let mut a_bigrams: Vec<(char, char)> = a.windows(2).map(|w| (w[0], w[1])).collect();
+    let b_bigrams: Vec<(char, char)> = b.windows(2).map(|w| (w[0], w[1])).collect();
+    let total = a_bigrams.len() + b_bigrams.len();
+
+    let mut shared = 0;
+    for bigram in &b_bigrams {
+        if let Some(pos) = a_bigrams.iter().position(|x| x == bigram) {
+            a_bigrams.swap_remove(pos);
+            shared += 1;
+        }
+    }
+
+    (2 * shared) as f32 / total as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f32, end: f32, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            start,
+            end,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn texts(segments: &[TranscriptionSegment]) -> Vec<&str> {
+        segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(text_similarity("Hello, world!", "hello world"), 1.0);
+        assert!(text_similarity("今天天氣很好", "今天天氣真好") > 0.5);
+        assert!(text_similarity("今天天氣很好", "我們明天開會") < 0.2);
+    }
+
+    #[test]
+    fn test_non_overlapping_chunks_are_concatenated() {
+        let chunks = vec![
+            ChunkTranscript {
+                start: 30.0,
+                end: 60.0,
+                segments: vec![seg(31.0, 35.0, "second")],
+            },
+            ChunkTranscript {
+                start: 0.0,
+                end: 30.0,
+                segments: vec![seg(1.0, 5.0, "first"), seg(6.0, 7.0, "  ")],
+            },
+        ];
+
+        let merged = merge_chunks(chunks, &MergeParams::default());
+        assert_eq!(texts(&merged), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_duplicate_sentences_in_overlap_are_kept_once() {
+        let chunks = vec![
+            ChunkTranscript {
+                start: 0.0,
+                end: 300.0,
+                segments: vec![
+                    seg(280.0, 288.0, "我們今天要討論預算"),
+                    seg(289.0, 295.0, "還有下一季的計畫"),
+                    seg(296.0, 300.0, "第一"),
+                ],
+            },
+            ChunkTranscript {
+                start: 290.0,
+                end: 600.0,
+                segments: vec![
+                    seg(290.0, 295.2, "還有下一季的計劃"),
+                    seg(296.1, 301.5, "第一個議題是人力"),
+                    seg(302.0, 310.0, "請大家發言"),
+                ],
+            },
+        ];
+
+        let merged = merge_chunks(chunks, &MergeParams::default());
+        assert_eq!(
+            texts(&merged),
+            vec!["我們今天要討論預算", "還有下一季的計畫", "第一個議題是人力", "請大家發言"]
+        );
+    }
+
+    #[test]
+    fn test_unmatched_overlap_cuts_at_midpoint() {
+        let chunks = vec![
+            ChunkTranscript {
+                start: 0.0,
+                end: 20.0,
+                segments: vec![seg(2.0, 8.0, "alpha"), seg(12.0, 14.0, "bravo")],
+            },
+            ChunkTranscript {
+                start: 10.0,
+                end: 30.0,
+                segments: vec![seg(10.5, 13.0, "something else"), seg(16.0, 19.0, "charlie")],
+            },
+        ];
+
+        let merged = merge_chunks(chunks, &MergeParams::default());
+        assert_eq!(texts(&merged), vec!["alpha", "bravo", "charlie"]);
+    }
+
+    #[test]
+    fn test_repeated_prefix_is_trimmed_at_seam() {
+        let chunks = vec![
+            ChunkTranscript {
+                start: 0.0,
+                end: 20.0,
+                segments: vec![seg(10.0, 14.5, "we should ship it")],
+            },
+            ChunkTranscript {
+                start: 10.0,
+                end: 30.0,
+                segments: vec![seg(15.5, 19.0, "ship it on Friday")],
+            },
+        ];
+
+        let merged = merge_chunks(chunks, &MergeParams::default());
+        assert_eq!(texts(&merged), vec!["we should ship it", "on Friday"]);
+    }
+
+    #[test]
+    fn test_from_relative_offsets_segments() {
+        let chunk = ChunkTranscript::from_relative(100.0, 130.0, vec![seg(1.0, 2.5, "x")]);
+        assert_eq!((chunk.segments[0].start, chunk.segments[0].end), (101.0, 102.5));
+    }
+}
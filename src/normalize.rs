@@ -0,0 +1,347 @@
+//! Spacing, punctuation and number normalization for mixed CJK/Latin text.
+//!
+//! Whisper output mixes half-width and full-width punctuation, writes digits
+//! in either width and is inconsistent about spaces between Chinese and
+//! English words. [`normalize_text`] cleans this up in three passes:
+//!
+//! 1. Numbers: full-width digits and letters become ASCII, and full-width
+//!    separators between digits (`３．５`, `１，０００`) become `.` and `,`.
+//! 2. Punctuation: converted to the width of the target locale. Full-width
+//!    punctuation is only used next to CJK text, so English sentences inside
+//!    a Chinese transcript keep their half-width punctuation.
+//! 3. Spacing: spaces between CJK and Latin runs are inserted or removed;
+//!    spaces between CJK characters and around full-width punctuation are
+//!    always removed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use transcribe_rs::normalize::{normalize_text, NormalizeParams};
+//!
+//! let params = NormalizeParams::default();
+//! assert_eq!(normalize_text("我們用iPhone 15拍照,效果很好!", &params), "我們用 iPhone 15 拍照，效果很好！");
+//! ```
+
+use crate::text::is_cjk;
+use crate::TranscriptionSegment;
+
+/// How spaces between CJK and Latin runs are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CjkLatinSpacing {
+    /// One space between CJK and Latin runs (`使用 Rust 開發`)
+    #[default]
+    Insert,
+    /// No space between CJK and Latin runs (`使用Rust開發`)
+    Remove,
+    /// Leave spacing unchanged
+    Keep,
+}
+
+/// Target width for punctuation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PunctuationWidth {
+    /// Full-width after CJK text (`，。！？`), as in Chinese and Japanese
+    #[default]
+    FullWidth,
+    /// Half-width everywhere (`,.!?`), as in English or Korean
+    HalfWidth,
+    /// Leave punctuation unchanged
+    Keep,
+}
+
+/// Options for [`normalize_text`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizeParams {
+    pub spacing: CjkLatinSpacing,
+    pub punctuation: PunctuationWidth,
+    /// Convert full-width digits and letters to ASCII
+    pub half_width_numbers: bool,
+}
+
+impl Default for NormalizeParams {
+    fn default() -> Self {
+        Self {
+            spacing: CjkLatinSpacing::Insert,
+            punctuation: PunctuationWidth::FullWidth,
+            half_width_numbers: true,
+        }
+    }
+}
+
+impl NormalizeParams {
+    /// Conventions for a language (ISO 639-1): full-width punctuation for
+    /// Chinese and Japanese, half-width for everything else.
+    pub fn for_language(language: &str) -> Self {
+        let language = language.split(['-', '_']).next().unwrap_or_default();
+        let punctuation = match language.to_ascii_lowercase().as_str() {
+            "zh" | "ja" | "yue" => PunctuationWidth::FullWidth,
+            _ => PunctuationWidth::HalfWidth,
+        };
+        Self {
+            spacing: CjkLatinSpacing::Insert,
+            punctuation,
+            half_width_numbers: true,
+        }
+    }
+}
+
+/// Half-width punctuation and its full-width form.
+const PUNCTUATION_PAIRS: &[(char, char)] = &[
+    (',', '，'),
+    ('.', '。'),
+    ('!', '！'),
+    ('?', '？'),
+    (':', '：'),
+    (';', '；'),
+    ('(', '（'),
+    (')', '）'),
+];
+
+/// Symbols that belong to an adjacent Latin word, e.g. `50%` or `C++`
+const LATIN_SYMBOLS: &[char] = &['%', '#', '@', '&', '$', '+'];
+
+/// An ideograph, kana or hangul syllable (not CJK punctuation).
+fn is_cjk_letter(c: char) -> bool {
+    is_cjk(c) && c.is_alphanumeric()
+}
+
+/// Full-width punctuation such as `，` or `「`.
+fn is_cjk_punctuation(c: char) -> bool {
+    is_cjk(c) && !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+fn is_latin(c: char) -> bool {
+    (c.is_alphanumeric() && !is_cjk(c)) || LATIN_SYMBOLS.contains(&c)
+}
+
+/// Normalize `text` according to `params`.
+pub fn normalize_text(text: &str, params: &NormalizeParams) -> String {
+    let mut chars: Vec<char> = text.chars().collect();
+    if params.half_width_numbers {
+        chars = normalize_numbers(&chars);
+    }
+    chars = match params.punctuation {
+        PunctuationWidth::FullWidth => to_full_width(&chars),
+        PunctuationWidth::HalfWidth => to_half_width(&chars),
+        PunctuationWidth::Keep => chars,
+    };
+    match params.spacing {
+        CjkLatinSpacing::Keep => chars.into_iter().collect(),
+        spacing => fix_spacing(&chars, spacing),
+    }
+}
+
+/// Normalize the text and words of every segment in place.
+pub fn normalize_segments(segments: &mut [TranscriptionSegment], params: &NormalizeParams) {
+    for segment in segments {
+        segment.text = normalize_text(&segment.text, params);
+        for word in segment.words.iter_mut().flatten() {
+            word.text = normalize_text(&word.text, params);
+        }
+    }
+}
+
+fn normalize_numbers(chars: &[char]) -> Vec<char> {
+    let mut output: Vec<char> = chars
+        .iter()
+        .map(|&c| match c {
+            '０'..='９' | 'Ａ'..='Ｚ' | 'ａ'..='ｚ' => {
+                char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)
+            }
+            '％' => '%',
+            '\u{3000}' => ' ',
+            c => c,
+        })
+        .collect();
+
+    // Separators between digits, e.g. 3．5 or 1，000
+    for i in 1..output.len().saturating_sub(1) {
+        if output[i - 1].is_ascii_digit() && output[i + 1].is_ascii_digit() {
+            output[i] = match output[i] {
+                '．' | '。' => '.',
+                '，' => ',',
+                '：' => ':',
+                c => c,
+            };
+        }
+    }
+    output
+}
+
+fn to_full_width(chars: &[char]) -> Vec<char> {
+    let mut output: Vec<char> = Vec::with_capacity(chars.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let Some(&(_, full)) = PUNCTUATION_PAIRS.iter().find(|(half, _)| *half == c) else {
+            output.push(c);
+            continue;
+        };
+
+        let between_digits = i > 0
+            && chars[i - 1].is_ascii_digit()
+            && chars.get(i + 1).is_some_and(char::is_ascii_digit);
+        let ellipsis = c == '.' && (chars.get(i + 1) == Some(&'.') || output.last() == Some(&'.'));
+        let next = chars[i + 1..].iter().copied().find(|c| !c.is_whitespace());
+        let prev = output.iter().rev().copied().find(|c| !c.is_whitespace());
+        // An opening parenthesis belongs to the text after it, everything else
+        // to the text before it. A Latin word inside a CJK sentence counts as
+        // CJK context when CJK text follows (`用了API,然後`), an English
+        // sentence does not (`Hello, world. 然後` keeps its punctuation).
+        let cjk_context = if c == '(' {
+            next.is_some_and(is_cjk_letter)
+        } else {
+            prev.is_some_and(is_cjk)
+                || (next.is_none_or(is_cjk_letter) && ends_with_embedded_latin(&output))
+        };
+
+        output.push(if cjk_context && !between_digits && !ellipsis {
+            full
+        } else {
+            c
+        });
+    }
+    output
+}
+
+/// Whether `text` ends with Latin words that directly follow CJK text.
+fn ends_with_embedded_latin(text: &[char]) -> bool {
+    text.iter()
+        .rev()
+        .find(|&&c| !is_latin(c) && !c.is_whitespace())
+        .is_some_and(|&c| is_cjk_letter(c))
+}
+
+fn to_half_width(chars: &[char]) -> Vec<char> {
+    let mut output = Vec::with_capacity(chars.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let half = match c {
+            '、' => ',',
+            c => PUNCTUATION_PAIRS
+                .iter()
+                .find(|(_, full)| *full == c)
+                .map_or(c, |&(half, _)| half),
+        };
+        output.push(half);
+
+        // Full-width punctuation carries its own spacing; half-width needs a space
+        let next = chars.get(i + 1).copied();
+        if half != c && half != '(' && next.is_some_and(|n| n.is_alphanumeric()) {
+            output.push(' ');
+        }
+    }
+    output
+}
+
+fn fix_spacing(chars: &[char], spacing: CjkLatinSpacing) -> String {
+    let mut output = String::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            // Look at the whole run of whitespace at once
+            let run_end = chars[i..]
+                .iter()
+                .position(|c| !c.is_whitespace())
+                .map_or(chars.len(), |n| i + n);
+            let prev = output.chars().last();
+            let next = chars.get(run_end).copied();
+            if let (Some(prev), Some(next)) = (prev, next) {
+                let drop = is_cjk_punctuation(prev)
+                    || is_cjk_punctuation(next)
+                    || (is_cjk_letter(prev) && is_cjk_letter(next))
+                    || (spacing == CjkLatinSpacing::Remove
+                        && ((is_cjk_letter(prev) && is_latin(next))
+                            || (is_latin(prev) && is_cjk_letter(next))));
+                if !drop {
+                    output.push(' ');
+                }
+            }
+            i = run_end;
+            continue;
+        }
+
+        if spacing == CjkLatinSpacing::Insert {
+            if let Some(prev) = output.chars().last() {
+                if (is_cjk_letter(prev) && is_latin(c)) || (is_latin(prev) && is_cjk_letter(c)) {
+                    output.push(' ');
+                }
+            }
+        }
+        output.push(c);
+        i += 1;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(text: &str) -> String {
+        normalize_text(text, &NormalizeParams::default())
+    }
+
+    #[test]
+    fn test_inserts_spaces_between_scripts() {
+        assert_eq!(
+            normalize("今天用Rust寫了3個API"),
+            "今天用 Rust 寫了 3 個 API"
+        );
+        assert_eq!(normalize("有50%的人用C++開發"), "有 50% 的人用 C++ 開發");
+        // Spaces inside CJK text and extra spaces are removed
+        assert_eq!(
+            normalize("我們 今天   要討論  the  plan"),
+            "我們今天要討論 the plan"
+        );
+    }
+
+    #[test]
+    fn test_removes_spaces_between_scripts() {
+        let params = NormalizeParams {
+            spacing: CjkLatinSpacing::Remove,
+            ..Default::default()
+        };
+        assert_eq!(
+            normalize_text("使用 Rust 開發 web app", &params),
+            "使用Rust開發web app"
+        );
+    }
+
+    #[test]
+    fn test_full_width_punctuation_only_in_cjk_context() {
+        assert_eq!(normalize("好的, 我們開始吧!"), "好的，我們開始吧！");
+        assert_eq!(normalize("他說(大概)會來."), "他說（大概）會來。");
+        // English sentences and numbers keep their punctuation
+        assert_eq!(
+            normalize("他說 Hello, world. 然後走了"),
+            "他說 Hello, world. 然後走了"
+        );
+        assert_eq!(
+            normalize("價格是3.5元,時間12:30"),
+            "價格是 3.5 元，時間 12:30"
+        );
+        assert_eq!(normalize("嗯..."), "嗯...");
+        assert_eq!(
+            normalize("今天用Rust寫了３個API,很好!"),
+            "今天用 Rust 寫了 3 個 API，很好！"
+        );
+    }
+
+    #[test]
+    fn test_half_width_punctuation() {
+        let params = NormalizeParams::for_language("en");
+        assert_eq!(
+            normalize_text("Hello，world！OK？", &params),
+            "Hello, world! OK?"
+        );
+        assert_eq!(normalize_text("第一、第二", &params), "第一, 第二");
+    }
+
+    #[test]
+    fn test_normalizes_full_width_numbers() {
+        assert_eq!(
+            normalize("共１，０００元，折扣３．５％"),
+            "共 1,000 元，折扣 3.5%"
+        );
+        assert_eq!(normalize("ＡＢＣ公司"), "ABC 公司");
+    }
+}
@@ -0,0 +1,314 @@
+//! Responses in the format of the OpenAI audio API.
+//!
+//! The server exposes `/v1/audio/transcriptions` and
+//! `/v1/audio/translations` so clients written against the OpenAI API (such
+//! as [`crate::remote::openai`]) can use a self-hosted model unchanged. This
+//! module renders a [`Transcript`] in each of the API's `response_format`s:
+//!
+//! - `json`: `{"text": "..."}`
+//! - `text`: the plain text
+//! - `srt` / `vtt`: subtitles
+//! - `verbose_json`: text, language, duration, segments and, when requested
+//!   through `timestamp_granularities[]`, words
+//!
+//! # Examples
+//!
+//! ```rust
+//! use transcribe_rs::openai_compat::{render_response, AudioTask, ResponseFormat};
+//! use transcribe_rs::transcript::{Transcript, TranscriptMetadata};
+//! use transcribe_rs::TranscriptionSegment;
+//!
+//! let segments = vec![TranscriptionSegment { start: 0.0, end: 1.5, text: "Hello".to_string(), ..Default::default() }];
+//! let transcript = Transcript::from_segments(&segments, TranscriptMetadata::default());
+//!
+//! let (content_type, body) = render_response(&transcript, ResponseFormat::Json, AudioTask::Transcribe, &[])?;
+//! assert_eq!(content_type, "application/json");
+//! assert_eq!(body, r#"{"text":"Hello"}"#);
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::engines::whisper::compression_ratio;
+use crate::subtitle::{resegment, ResegmentParams, SubtitleFormat};
+use crate::transcript::Transcript;
+
+/// `response_format` of a transcription request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    Text,
+    Srt,
+    VerboseJson,
+    Vtt,
+}
+
+impl FromStr for ResponseFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "json" => Ok(Self::Json),
+            "text" => Ok(Self::Text),
+            "srt" => Ok(Self::Srt),
+            "verbose_json" => Ok(Self::VerboseJson),
+            "vtt" => Ok(Self::Vtt),
+            other => Err(format!(
+                "Unsupported response_format: {} (expected json, text, srt, verbose_json or vtt)",
+                other
+            )),
+        }
+    }
+}
+
+/// Entry of `timestamp_granularities[]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampGranularity {
+    Word,
+    Segment,
+}
+
+impl FromStr for TimestampGranularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "word" => Ok(Self::Word),
+            "segment" => Ok(Self::Segment),
+            other => Err(format!("Unsupported timestamp granularity: {}", other)),
+        }
+    }
+}
+
+/// Which endpoint produced the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioTask {
+    /// `/v1/audio/transcriptions`
+    Transcribe,
+    /// `/v1/audio/translations`, always into English
+    Translate,
+}
+
+impl AudioTask {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Transcribe => "transcribe",
+            Self::Translate => "translate",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonResponse<'a> {
+    text: &'a str,
+}
+
+#[derive(Serialize)]
+struct VerboseJsonResponse<'a> {
+    task: &'static str,
+    language: &'a str,
+    duration: f32,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    segments: Option<Vec<VerboseSegment<'a>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    words: Option<Vec<VerboseWord<'a>>>,
+}
+
+/// Segment with every field OpenAI clients expect. Values the local engines
+/// do not produce are filled with neutral defaults.
+#[derive(Serialize)]
+struct VerboseSegment<'a> {
+    id: usize,
+    seek: usize,
+    start: f32,
+    end: f32,
+    text: &'a str,
+    tokens: Vec<i32>,
+    temperature: f32,
+    avg_logprob: f32,
+    compression_ratio: f32,
+    no_speech_prob: f32,
+}
+
+#[derive(Serialize)]
+struct VerboseWord<'a> {
+    word: &'a str,
+    start: f32,
+    end: f32,
+}
+
+/// Render `transcript` as the body of an OpenAI audio API response.
+///
+/// Returns the content type and body. `granularities` only affects
+/// `verbose_json`: segments are included unless only words were requested.
+pub fn render_response(
+    transcript: &Transcript,
+    format: ResponseFormat,
+    task: AudioTask,
+    granularities: &[TimestampGranularity],
+) -> Result<(&'static str, String), serde_json::Error> {
+    let subtitles = |format: SubtitleFormat| {
+        let segments = resegment(&transcript.to_segments(), &ResegmentParams::default());
+        (format.content_type(), format.render(&segments))
+    };
+
+    Ok(match format {
+        ResponseFormat::Json => (
+            "application/json",
+            serde_json::to_string(&JsonResponse {
+                text: &transcript.text,
+            })?,
+        ),
+        ResponseFormat::Text => ("text/plain; charset=utf-8", transcript.text.clone()),
+        ResponseFormat::Srt => subtitles(SubtitleFormat::Srt),
+        ResponseFormat::Vtt => subtitles(SubtitleFormat::Vtt),
+        ResponseFormat::VerboseJson => {
+            let include_segments =
+                granularities.is_empty() || granularities.contains(&TimestampGranularity::Segment);
+            let include_words = granularities.contains(&TimestampGranularity::Word);
+            let segments = transcript
+                .segments
+                .iter()
+                .map(|segment| VerboseSegment {
+                    id: segment.id,
+                    // Offset of the segment in 10 ms frames, as reported by Whisper
+                    seek: (segment.start * 100.0).round() as usize,
+                    start: segment.start,
+                    end: segment.end,
+                    text: &segment.text,
+                    tokens: Vec::new(),
+                    temperature: 0.0,
+                    avg_logprob: segment
+                        .confidence
+                        .map_or(0.0, |c| c.max(f32::MIN_POSITIVE).ln()),
+                    compression_ratio: compression_ratio(&segment.text),
+                    no_speech_prob: segment.no_speech_prob.unwrap_or(0.0),
+                })
+                .collect();
+            let words = transcript
+                .segments
+                .iter()
+                .flat_map(|segment| &segment.words)
+                .map(|word| VerboseWord {
+                    word: &word.word,
+                    start: word.start,
+                    end: word.end,
+                })
+                .collect();
+
+            let language = match task {
+                AudioTask::Translate => "en",
+                AudioTask::Transcribe => {
+                    transcript.metadata.language.as_deref().unwrap_or_default()
+                }
+            };
+            let response = VerboseJsonResponse {
+                task: task.as_str(),
+                language,
+                duration: transcript
+                    .metadata
+                    .duration
+                    .or_else(|| transcript.segments.last().map(|s| s.end))
+                    .unwrap_or(0.0),
+                text: &transcript.text,
+                segments: include_segments.then_some(segments),
+                words: include_words.then_some(words),
+            };
+            ("application/json", serde_json::to_string(&response)?)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::TranscriptMetadata;
+    use crate::{TranscriptionSegment, TranscriptionWord};
+
+    fn transcript() -> Transcript {
+        let segments = vec![TranscriptionSegment {
+            start: 0.5,
+            end: 1.5,
+            text: "大家好".to_string(),
+            confidence: Some(0.5),
+            words: Some(vec![TranscriptionWord {
+                start: 0.5,
+                end: 1.0,
+                text: "大家".to_string(),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }];
+        let metadata = TranscriptMetadata {
+            language: Some("zh".to_string()),
+            duration: Some(2.0),
+            ..Default::default()
+        };
+        Transcript::from_segments(&segments, metadata)
+    }
+
+    #[test]
+    fn test_parse_request_fields() {
+        assert_eq!("verbose_json".parse(), Ok(ResponseFormat::VerboseJson));
+        assert!("xml".parse::<ResponseFormat>().is_err());
+        assert_eq!("word".parse(), Ok(TimestampGranularity::Word));
+    }
+
+    #[test]
+    fn test_verbose_json_granularities() {
+        let (_, body) = render_response(
+            &transcript(),
+            ResponseFormat::VerboseJson,
+            AudioTask::Transcribe,
+            &[],
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["task"], "transcribe");
+        assert_eq!(json["language"], "zh");
+        assert_eq!(json["duration"], 2.0);
+        assert_eq!(json["segments"][0]["seek"], 50);
+        assert!((json["segments"][0]["avg_logprob"].as_f64().unwrap() - 0.5f64.ln()).abs() < 1e-5);
+        assert!(json.get("words").is_none());
+
+        let (_, body) = render_response(
+            &transcript(),
+            ResponseFormat::VerboseJson,
+            AudioTask::Translate,
+            &[TimestampGranularity::Word],
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["language"], "en");
+        assert_eq!(json["words"][0]["word"], "大家");
+        assert!(json.get("segments").is_none());
+    }
+
+    #[test]
+    fn test_text_and_subtitles() {
+        let (content_type, body) = render_response(
+            &transcript(),
+            ResponseFormat::Text,
+            AudioTask::Transcribe,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            (content_type, body.as_str()),
+            ("text/plain; charset=utf-8", "大家好")
+        );
+
+        let (_, body) = render_response(
+            &transcript(),
+            ResponseFormat::Srt,
+            AudioTask::Transcribe,
+            &[],
+        )
+        .unwrap();
+        assert!(body.contains("00:00:00,500 --> 00:00:01,500"));
+    }
+}
@@ -0,0 +1,634 @@
+//! Punctuation restoration for unpunctuated transcripts.
+//!
+//! Chinese Whisper output often has no punctuation at all, and Parakeet can
+//! only split segments at sentence punctuation, so an unpunctuated transcript
+//! turns into one giant segment that is useless as subtitles. This module
+//! predicts the punctuation that follows each word and inserts it.
+//!
+//! # Restorers
+//!
+//! - [`OnnxPunctuator`]: a BERT-style token-classification model loaded
+//!   through `ort`. Most accurate, requires a model directory.
+//! - [`PausePunctuator`]: inserts commas and periods at pauses between words.
+//!   No model required, used as the fallback.
+//!
+//! A model directory contains:
+//! ```text
+//! punctuation-model/
+//! ├── model.onnx    # Token classification model (input_ids, attention_mask -> logits)
+//! ├── vocab.txt     # WordPiece vocabulary, one token per line
+//! └── labels.txt    # One label per class: O, COMMA, PERIOD, QUESTION, EXCLAMATION
+//! ```
+//!
+//! Punctuation is full-width after CJK text (`，。？！`) and half-width
+//! elsewhere. Existing punctuation is never replaced or duplicated.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use transcribe_rs::punctuation::{restore_segments, PausePunctuator};
+//! use transcribe_rs::TranscriptionSegment;
+//!
+//! let mut segments = vec![
+//!     TranscriptionSegment { start: 0.0, end: 2.0, text: "今天天氣很好".to_string(), ..Default::default() },
+//!     TranscriptionSegment { start: 3.0, end: 5.0, text: "我們去公園吧".to_string(), ..Default::default() },
+//! ];
+//! restore_segments(&mut PausePunctuator::default(), &mut segments)?;
+//! assert_eq!(segments[0].text, "今天天氣很好。");
+//! assert_eq!(segments[1].text, "我們去公園吧。");
+//! # Ok::<(), transcribe_rs::punctuation::PunctuationError>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use ndarray::Array2;
+use ort::execution_providers::CPUExecutionProvider;
+use ort::inputs;
+use ort::session::builder::GraphOptimizationLevel;
+use ort::session::Session;
+use ort::value::TensorRef;
+
+use crate::text::is_cjk;
+use crate::{TranscriptionSegment, TranscriptionWord};
+
+/// Maximum number of word pieces per model run, excluding `[CLS]` and `[SEP]`
+const MAX_PIECES: usize = 510;
+
+/// Words longer than this many characters are mapped to `[UNK]`
+const MAX_WORD_CHARS: usize = 100;
+
+/// Punctuation that already ends a word and must not be added to
+const EXISTING_PUNCTUATION: &str = ",.!?;:，。、；：！？…";
+
+/// Punctuation that ends a sentence
+const SENTENCE_PUNCTUATION: &str = ".!?。！？";
+
+#[derive(thiserror::Error, Debug)]
+pub enum PunctuationError {
+    #[error("ORT error")]
+    Ort(#[from] ort::Error),
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+    #[error("ndarray shape error")]
+    Shape(#[from] ndarray::ShapeError),
+    #[error("Model output not found: {0}")]
+    OutputNotFound(String),
+    #[error("Vocabulary is missing the {0} token")]
+    MissingToken(String),
+    #[error("Unknown punctuation label: {0}")]
+    UnknownLabel(String),
+}
+
+/// Punctuation mark inserted after a word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Punctuation {
+    Comma,
+    Period,
+    Question,
+    Exclamation,
+}
+
+impl Punctuation {
+    /// The mark itself, full-width for CJK text.
+    pub fn symbol(self, full_width: bool) -> char {
+        match (self, full_width) {
+            (Punctuation::Comma, false) => ',',
+            (Punctuation::Comma, true) => '，',
+            (Punctuation::Period, false) => '.',
+            (Punctuation::Period, true) => '。',
+            (Punctuation::Question, false) => '?',
+            (Punctuation::Question, true) => '？',
+            (Punctuation::Exclamation, false) => '!',
+            (Punctuation::Exclamation, true) => '！',
+        }
+    }
+
+    /// Parse a class label from `labels.txt`; `O` means no punctuation.
+    fn from_label(label: &str) -> Result<Option<Self>, PunctuationError> {
+        match label.trim() {
+            "O" | "" => Ok(None),
+            "COMMA" | "," | "，" => Ok(Some(Punctuation::Comma)),
+            "PERIOD" | "." | "。" => Ok(Some(Punctuation::Period)),
+            "QUESTION" | "?" | "？" => Ok(Some(Punctuation::Question)),
+            "EXCLAMATION" | "!" | "！" => Ok(Some(Punctuation::Exclamation)),
+            other => Err(PunctuationError::UnknownLabel(other.to_string())),
+        }
+    }
+}
+
+/// Common interface for punctuation restorers.
+pub trait PunctuationRestorer {
+    /// Predict the punctuation that follows each word. Returns one entry per word.
+    fn predict(
+        &mut self,
+        words: &[TranscriptionWord],
+    ) -> Result<Vec<Option<Punctuation>>, PunctuationError>;
+}
+
+/// Pause lengths used by [`PausePunctuator`].
+#[derive(Debug, Clone)]
+pub struct PauseParams {
+    /// Pause (seconds) after a word that earns a comma
+    pub comma_gap: f32,
+    /// Pause (seconds) after a word that ends a sentence
+    pub period_gap: f32,
+}
+
+impl Default for PauseParams {
+    fn default() -> Self {
+        Self {
+            comma_gap: 0.3,
+            period_gap: 0.8,
+        }
+    }
+}
+
+/// Heuristic restorer: a comma after short pauses, a period after long ones
+/// and at the very end.
+#[derive(Debug, Clone, Default)]
+pub struct PausePunctuator {
+    pub params: PauseParams,
+}
+
+impl PunctuationRestorer for PausePunctuator {
+    fn predict(
+        &mut self,
+        words: &[TranscriptionWord],
+    ) -> Result<Vec<Option<Punctuation>>, PunctuationError> {
+        Ok(words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| match words.get(i + 1) {
+                None => Some(Punctuation::Period),
+                Some(next) => {
+                    let gap = next.start - word.end;
+                    if gap >= self.params.period_gap {
+                        Some(Punctuation::Period)
+                    } else if gap >= self.params.comma_gap {
+                        Some(Punctuation::Comma)
+                    } else {
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+}
+
+/// WordPiece vocabulary of a BERT-style model.
+#[derive(Debug, Clone)]
+struct WordPieceVocab {
+    ids: HashMap<String, i64>,
+    unk: i64,
+    cls: i64,
+    sep: i64,
+    lowercase: bool,
+}
+
+impl WordPieceVocab {
+    /// Parse a `vocab.txt` with one token per line, the line number being the id.
+    fn parse(content: &str) -> Result<Self, PunctuationError> {
+        let ids: HashMap<String, i64> = content
+            .lines()
+            .enumerate()
+            .map(|(id, token)| (token.trim_end().to_string(), id as i64))
+            .collect();
+        let special = |token: &str| {
+            ids.get(token)
+                .copied()
+                .ok_or_else(|| PunctuationError::MissingToken(token.to_string()))
+        };
+        // Uncased vocabularies contain no upper-case letters besides `[UNK]` and friends
+        let lowercase = !ids
+            .keys()
+            .filter(|token| !token.starts_with('['))
+            .any(|token| token.chars().any(char::is_uppercase));
+
+        Ok(Self {
+            unk: special("[UNK]")?,
+            cls: special("[CLS]")?,
+            sep: special("[SEP]")?,
+            lowercase,
+            ids,
+        })
+    }
+
+    /// Split one word into WordPiece ids by greedy longest match.
+    fn word_pieces(&self, word: &str) -> Vec<i64> {
+        let chars: Vec<char> = word
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(|c| {
+                let lower: Vec<char> = if self.lowercase {
+                    c.to_lowercase().collect()
+                } else {
+                    vec![c]
+                };
+                lower
+            })
+            .collect();
+        if chars.is_empty() || chars.len() > MAX_WORD_CHARS {
+            return vec![self.unk];
+        }
+
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let piece = (start + 1..=chars.len()).rev().find_map(|end| {
+                let text: String = chars[start..end].iter().collect();
+                let key = if start > 0 {
+                    format!("##{}", text)
+                } else {
+                    text
+                };
+                self.ids.get(&key).map(|&id| (id, end))
+            });
+            match piece {
+                Some((id, end)) => {
+                    pieces.push(id);
+                    start = end;
+                }
+                None => return vec![self.unk],
+            }
+        }
+        pieces
+    }
+}
+
+/// Restorer backed by a token-classification model on ONNX Runtime.
+pub struct OnnxPunctuator {
+    session: Session,
+    vocab: WordPieceVocab,
+    labels: Vec<Option<Punctuation>>,
+    token_type_ids: bool,
+}
+
+impl OnnxPunctuator {
+    /// Load `model.onnx`, `vocab.txt` and `labels.txt` from a model directory.
+    pub fn new<P: AsRef<Path>>(model_dir: P) -> Result<Self, PunctuationError> {
+        let model_dir = model_dir.as_ref();
+        let session = Session::builder()?
+            .with_optimization_level(GraphOptimizationLevel::Level3)?
+            .with_execution_providers(vec![CPUExecutionProvider::default().build()])?
+            .commit_from_file(model_dir.join("model.onnx"))?;
+        let token_type_ids = session
+            .inputs
+            .iter()
+            .any(|input| input.name == "token_type_ids");
+
+        let vocab = WordPieceVocab::parse(&fs::read_to_string(model_dir.join("vocab.txt"))?)?;
+        let labels = fs::read_to_string(model_dir.join("labels.txt"))?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(Punctuation::from_label)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            session,
+            vocab,
+            labels,
+            token_type_ids,
+        })
+    }
+
+    /// Classify one window of words. `pieces` holds the word pieces of each word.
+    fn classify(
+        &mut self,
+        pieces: &[Vec<i64>],
+    ) -> Result<Vec<Option<Punctuation>>, PunctuationError> {
+        let mut ids = vec![self.vocab.cls];
+        // Each word is labelled by its first piece
+        let mut first_piece = Vec::with_capacity(pieces.len());
+        for word in pieces {
+            first_piece.push(ids.len());
+            ids.extend_from_slice(word);
+        }
+        ids.push(self.vocab.sep);
+
+        let len = ids.len();
+        let input_ids = Array2::from_shape_vec((1, len), ids)?;
+        let attention_mask = Array2::<i64>::ones((1, len));
+        let token_types = Array2::<i64>::zeros((1, len));
+        let mut inputs = inputs![
+            "input_ids" => TensorRef::from_array_view(input_ids.view())?,
+            "attention_mask" => TensorRef::from_array_view(attention_mask.view())?,
+        ];
+        if self.token_type_ids {
+            inputs.push((
+                "token_type_ids".into(),
+                TensorRef::from_array_view(token_types.view())?.into(),
+            ));
+        }
+        let outputs = self.session.run(inputs)?;
+
+        let logits = outputs
+            .get("logits")
+            .ok_or_else(|| PunctuationError::OutputNotFound("logits".to_string()))?
+            .try_extract_array::<f32>()?;
+        let logits = logits.to_shape((len, self.labels.len()))?;
+
+        Ok(first_piece
+            .into_iter()
+            .map(|i| {
+                let row = logits.row(i);
+                let best = row
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.total_cmp(b.1))
+                    .map_or(0, |(label, _)| label);
+                self.labels.get(best).copied().flatten()
+            })
+            .collect())
+    }
+}
+
+impl PunctuationRestorer for OnnxPunctuator {
+    fn predict(
+        &mut self,
+        words: &[TranscriptionWord],
+    ) -> Result<Vec<Option<Punctuation>>, PunctuationError> {
+        let pieces: Vec<Vec<i64>> = words
+            .iter()
+            .map(|word| {
+                let mut pieces = self.vocab.word_pieces(&word.text);
+                pieces.truncate(MAX_PIECES);
+                pieces
+            })
+            .collect();
+
+        // Split long transcripts into windows that fit the model
+        let mut predictions = Vec::with_capacity(words.len());
+        let mut start = 0;
+        while start < pieces.len() {
+            let mut count = 0;
+            let mut end = start;
+            while end < pieces.len() && count + pieces[end].len() <= MAX_PIECES {
+                count += pieces[end].len();
+                end += 1;
+            }
+            predictions.extend(self.classify(&pieces[start..end])?);
+            start = end;
+        }
+        Ok(predictions)
+    }
+}
+
+/// Options for restoring punctuation in a transcript.
+#[derive(Debug, Clone, Default)]
+pub struct PunctuationParams {
+    /// Directory of an ONNX punctuation model; pauses are used when `None`
+    pub model_dir: Option<PathBuf>,
+    /// Pause lengths for the heuristic fallback
+    pub pause: PauseParams,
+}
+
+/// Load the ONNX restorer if a model directory is given, otherwise the
+/// pause-based fallback.
+pub fn load_restorer(
+    params: &PunctuationParams,
+) -> Result<Box<dyn PunctuationRestorer + Send>, PunctuationError> {
+    match &params.model_dir {
+        Some(model_dir) => Ok(Box::new(OnnxPunctuator::new(model_dir)?)),
+        None => Ok(Box::new(PausePunctuator {
+            params: params.pause.clone(),
+        })),
+    }
+}
+
+/// Whether `text` lacks sentence punctuation and needs restoration.
+pub fn needs_punctuation(text: &str) -> bool {
+    text.chars().any(char::is_alphanumeric) && !text.contains(|c| SENTENCE_PUNCTUATION.contains(c))
+}
+
+/// Whether `c` is an ideograph, kana or hangul syllable.
+fn is_cjk_letter(c: char) -> bool {
+    is_cjk(c) && c.is_alphanumeric()
+}
+
+/// Split text into the units punctuation can follow: single CJK characters
+/// and Latin words, with trailing punctuation attached.
+fn split_units(text: &str) -> Vec<Range<usize>> {
+    let mut units: Vec<Range<usize>> = Vec::new();
+    // Whether the last unit is a Latin run that may continue
+    let mut open = false;
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        if c.is_whitespace() {
+            open = false;
+            continue;
+        }
+        let touching = units.last().is_some_and(|unit| unit.end == i);
+        if is_cjk_letter(c) {
+            units.push(i..end);
+            open = false;
+        } else if touching && (open || !c.is_alphanumeric()) {
+            if let Some(unit) = units.last_mut() {
+                unit.end = end;
+            }
+        } else {
+            units.push(i..end);
+            open = true;
+        }
+    }
+    units.retain(|unit| text[unit.clone()].chars().any(char::is_alphanumeric));
+    units
+}
+
+/// A unit of a segment's text, located in the segment and its words.
+struct Unit {
+    segment: usize,
+    range: Range<usize>,
+    word: Option<usize>,
+    timing: TranscriptionWord,
+}
+
+/// Locate each unit in the segment's words by counting non-space characters,
+/// or spread the units evenly over the segment when there are no words.
+fn segment_units(index: usize, segment: &TranscriptionSegment) -> Vec<Unit> {
+    let ranges = split_units(&segment.text);
+    let non_space = |text: &str| text.chars().filter(|c| !c.is_whitespace()).count();
+    let total = non_space(&segment.text).max(1) as f32;
+
+    // Non-space character count at the end of each word
+    let word_ends: Vec<usize> = segment
+        .words
+        .iter()
+        .flatten()
+        .scan(0, |count, word| {
+            *count += non_space(&word.text);
+            Some(*count)
+        })
+        .collect();
+
+    ranges
+        .into_iter()
+        .map(|range| {
+            let first = non_space(&segment.text[..range.start]);
+            let last = first + non_space(&segment.text[range.clone()]);
+            let word_at = |offset: usize| word_ends.iter().position(|&end| end >= offset);
+            let words = segment.words.as_deref().unwrap_or_default();
+            let (word, start, end) = match (word_at(first + 1), word_at(last)) {
+                (Some(first_word), Some(last_word)) => (
+                    Some(last_word),
+                    words[first_word].start,
+                    words[last_word].end,
+                ),
+                _ => {
+                    let at = |offset: usize| {
+                        segment.start + (segment.end - segment.start) * offset as f32 / total
+                    };
+                    (None, at(first), at(last))
+                }
+            };
+            Unit {
+                segment: index,
+                timing: TranscriptionWord {
+                    start,
+                    end,
+                    text: segment.text[range.clone()].to_string(),
+                    confidence: None,
+                },
+                range,
+                word,
+            }
+        })
+        .collect()
+}
+
+/// Restore punctuation in every segment that [needs it](needs_punctuation).
+///
+/// All segments are passed to the restorer together, so models see the
+/// surrounding text and pauses between segments count. Returns the number of
+/// marks inserted.
+pub fn restore_segments(
+    restorer: &mut dyn PunctuationRestorer,
+    segments: &mut [TranscriptionSegment],
+) -> Result<usize, PunctuationError> {
+    let units: Vec<Unit> = segments
+        .iter()
+        .enumerate()
+        .flat_map(|(i, segment)| segment_units(i, segment))
+        .collect();
+    let timings: Vec<TranscriptionWord> = units.iter().map(|unit| unit.timing.clone()).collect();
+    let predictions = restorer.predict(&timings)?;
+    let targets: Vec<bool> = segments
+        .iter()
+        .map(|s| needs_punctuation(&s.text))
+        .collect();
+
+    // Insert from the back so earlier byte offsets stay valid
+    let mut inserted = 0;
+    for (unit, prediction) in units.iter().zip(predictions).rev() {
+        let Some(punctuation) = prediction else {
+            continue;
+        };
+        let segment = &mut segments[unit.segment];
+        let text = &segment.text[unit.range.clone()];
+        if !targets[unit.segment] || text.ends_with(|c| EXISTING_PUNCTUATION.contains(c)) {
+            continue;
+        }
+        let last_letter = text.chars().rev().find(|c| c.is_alphanumeric());
+        let symbol = punctuation.symbol(last_letter.is_some_and(is_cjk));
+
+        segment.text.insert(unit.range.end, symbol);
+        if let Some(word) = unit.word.and_then(|w| segment.words.as_mut()?.get_mut(w)) {
+            if !word.text.ends_with(|c| EXISTING_PUNCTUATION.contains(c)) {
+                word.text.push(symbol);
+            }
+        }
+        inserted += 1;
+    }
+    Ok(inserted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f32, end: f32, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            start,
+            end,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn word(start: f32, end: f32, text: &str) -> TranscriptionWord {
+        TranscriptionWord {
+            start,
+            end,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_split_units() {
+        let units = |text: &str| -> Vec<String> {
+            split_units(text)
+                .into_iter()
+                .map(|r| text[r].to_string())
+                .collect()
+        };
+        assert_eq!(units("你好，世界"), vec!["你", "好，", "世", "界"]);
+        assert_eq!(
+            units("用Rust寫 3.5 版"),
+            vec!["用", "Rust", "寫", "3.5", "版"]
+        );
+        assert_eq!(units("hello, world"), vec!["hello,", "world"]);
+    }
+
+    #[test]
+    fn test_pause_punctuation_uses_word_gaps() {
+        let mut segments = vec![TranscriptionSegment {
+            words: Some(vec![
+                word(0.0, 0.4, "so"),
+                word(0.5, 0.9, "we"),
+                word(1.4, 1.8, "left"),
+                word(3.0, 3.5, "then"),
+                word(3.6, 4.0, "rain"),
+            ]),
+            ..seg(0.0, 4.0, "so we left then rain")
+        }];
+        let inserted = restore_segments(&mut PausePunctuator::default(), &mut segments).unwrap();
+        assert_eq!(inserted, 3);
+        assert_eq!(segments[0].text, "so we, left. then rain.");
+        let words = segments[0].words.as_ref().unwrap();
+        assert_eq!(words[1].text, "we,");
+        assert_eq!(words[2].text, "left.");
+    }
+
+    #[test]
+    fn test_mixed_script_and_existing_punctuation() {
+        let mut segments = vec![
+            seg(0.0, 2.0, "我們用Rust開發"),
+            seg(2.4, 4.0, "效果很好"),
+            // Already punctuated segments are left alone
+            seg(5.0, 6.0, "Done, thanks."),
+        ];
+        restore_segments(&mut PausePunctuator::default(), &mut segments).unwrap();
+        assert_eq!(segments[0].text, "我們用Rust開發，");
+        assert_eq!(segments[1].text, "效果很好。");
+        assert_eq!(segments[2].text, "Done, thanks.");
+    }
+
+    #[test]
+    fn test_word_pieces() {
+        let vocab =
+            WordPieceVocab::parse("[PAD]\n[UNK]\n[CLS]\n[SEP]\nplay\n##ing\n你\n好\n").unwrap();
+        assert!(vocab.lowercase);
+        assert_eq!(vocab.word_pieces("Playing,"), vec![4, 5]);
+        assert_eq!(vocab.word_pieces("你"), vec![6]);
+        assert_eq!(vocab.word_pieces("xyz"), vec![vocab.unk]);
+        assert!(WordPieceVocab::parse("play\n").is_err());
+        assert_eq!(
+            Punctuation::from_label("QUESTION").unwrap(),
+            Some(Punctuation::Question)
+        );
+        assert!(Punctuation::from_label("SEMICOLON").is_err());
+    }
+}
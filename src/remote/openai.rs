@@ -188,6 +188,7 @@ where
                 return Ok(TranscriptionResult {
                     text: response.text,
                     segments: None,
+                    language: None,
                 });
             }
             OpenAIModel::Whisper1 => {
@@ -213,6 +214,10 @@ where
                                 start: word.start,
                                 end: word.end,
                                 text: word.word,
+                                words: None,
+                                confidence: None,
+                                no_speech_prob: None,
+                                language: None,
                             })
                             .collect(),
                     ),
@@ -225,6 +230,10 @@ where
                                 start: segment.start,
                                 end: segment.end,
                                 text: segment.text,
+                                words: None,
+                                confidence: None,
+                                no_speech_prob: None,
+                                language: None,
                             })
                             .collect(),
                     ),
@@ -234,6 +243,7 @@ where
                 return Ok(TranscriptionResult {
                     text: response.text,
                     segments,
+                    language: Some(response.language),
                 });
             }
         }
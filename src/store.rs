@@ -0,0 +1,632 @@
+//! Task persistence for the transcription server.
+//!
+//! The server records every upload as a [`Task`] and keeps the finished
+//! transcript until it is downloaded. [`TaskStore`] abstracts where that
+//! state lives:
+//!
+//! - [`FileTaskStore`]: one JSON file per task plus one per transcript in a
+//!   directory. Survives restarts and keeps results out of memory.
+//! - [`MemoryTaskStore`]: a map in memory, lost on restart. Useful for tests
+//!   and short-lived processes.
+//!
+//! After a restart, [`TaskStore::unfinished`] returns the tasks that were
+//! still queued or processing so they can be queued again. Finished tasks
+//! are kept until deleted, or until a [`RetentionPolicy`] removes them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use transcribe_rs::store::{FileTaskStore, TaskStore};
+//! use transcribe_rs::worker::{Task, TaskStatus};
+//!
+//! let dir = tempfile::tempdir()?;
+//! let store = FileTaskStore::open(dir.path())?;
+//! store.insert(Task::new("abc".to_string(), "meeting.mp3".to_string(), "uploads/abc".into()))?;
+//!
+//! // A new store over the same directory sees the task
+//! let reopened = FileTaskStore::open(dir.path())?;
+//! assert_eq!(reopened.get("abc")?.unwrap().status, TaskStatus::Queued);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::transcript::{Transcript, TranscriptError};
+use crate::webhook::DeliveryAttempt;
+use crate::worker::{Task, TaskProgress, TaskStatus};
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid task record: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid stored transcript: {0}")]
+    Transcript(#[from] TranscriptError),
+    #[error("Invalid task id: {0}")]
+    InvalidId(String),
+}
+
+/// Storage for tasks and their results, shared by the server and its workers.
+pub trait TaskStore: Send + Sync {
+    /// Add a new task, replacing any task with the same id.
+    fn insert(&self, task: Task) -> Result<(), StoreError>;
+
+    /// Look up a task by id.
+    fn get(&self, id: &str) -> Result<Option<Task>, StoreError>;
+
+    /// Update the status of a task. Returns `false` if the task does not
+    /// exist or was cancelled; cancellation is final.
+    fn set_status(&self, id: &str, status: TaskStatus) -> Result<bool, StoreError>;
+
+    /// Record the progress of a task that is processing. Returns `false` if
+    /// the task does not exist or is not processing.
+    fn set_progress(&self, id: &str, progress: &TaskProgress) -> Result<bool, StoreError>;
+
+    /// Store the transcript of a task and mark it completed. Returns `false`
+    /// if the task does not exist or was cancelled.
+    fn complete(&self, id: &str, transcript: &Transcript) -> Result<bool, StoreError>;
+
+    /// Append a webhook delivery attempt to a task. Returns `false` if the
+    /// task does not exist.
+    fn record_delivery(&self, id: &str, attempt: &DeliveryAttempt) -> Result<bool, StoreError>;
+
+    /// Transcript of a completed task.
+    fn result(&self, id: &str) -> Result<Option<Transcript>, StoreError>;
+
+    /// Remove a task together with its result.
+    fn remove(&self, id: &str) -> Result<Option<Task>, StoreError>;
+
+    /// Every task, oldest first.
+    fn list(&self) -> Result<Vec<Task>, StoreError>;
+
+    /// Tasks that are queued or processing, oldest first.
+    fn unfinished(&self) -> Result<Vec<Task>, StoreError> {
+        let mut tasks = self.list()?;
+        tasks.retain(|task| is_unfinished(&task.status));
+        Ok(tasks)
+    }
+}
+
+fn is_unfinished(status: &TaskStatus) -> bool {
+    matches!(status, TaskStatus::Queued | TaskStatus::Processing)
+}
+
+/// Workers still running a cancelled task must not overwrite its status.
+fn is_cancelled(task: &Task) -> bool {
+    task.status == TaskStatus::Cancelled
+}
+
+/// Tasks kept in memory; everything is lost when the process exits.
+#[derive(Default)]
+pub struct MemoryTaskStore {
+    tasks: Mutex<HashMap<String, (Task, Option<Transcript>)>>,
+}
+
+impl MemoryTaskStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TaskStore for MemoryTaskStore {
+    fn insert(&self, task: Task) -> Result<(), StoreError> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .insert(task.id.clone(), (task, None));
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Task>, StoreError> {
+        Ok(self
+            .tasks
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|(task, _)| task.clone()))
+    }
+
+    fn set_status(&self, id: &str, status: TaskStatus) -> Result<bool, StoreError> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let Some((task, _)) = tasks.get_mut(id).filter(|(task, _)| !is_cancelled(task)) else {
+            return Ok(false);
+        };
+        task.status = status;
+        Ok(true)
+    }
+
+    fn set_progress(&self, id: &str, progress: &TaskProgress) -> Result<bool, StoreError> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let Some((task, _)) = tasks.get_mut(id).filter(|(task, _)| task.status == TaskStatus::Processing) else {
+            return Ok(false);
+        };
+        task.progress = Some(progress.clone());
+        Ok(true)
+    }
+
+    fn complete(&self, id: &str, transcript: &Transcript) -> Result<bool, StoreError> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let Some((task, result)) = tasks.get_mut(id).filter(|(task, _)| !is_cancelled(task)) else {
+            return Ok(false);
+        };
+        task.status = TaskStatus::Completed;
+        *result = Some(transcript.clone());
+        Ok(true)
+    }
+
+    fn record_delivery(&self, id: &str, attempt: &DeliveryAttempt) -> Result<bool, StoreError> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let Some((task, _)) = tasks.get_mut(id) else {
+            return Ok(false);
+        };
+        task.deliveries.push(attempt.clone());
+        Ok(true)
+    }
+
+    fn result(&self, id: &str) -> Result<Option<Transcript>, StoreError> {
+        Ok(self
+            .tasks
+            .lock()
+            .unwrap()
+            .get(id)
+            .and_then(|(_, result)| result.clone()))
+    }
+
+    fn remove(&self, id: &str) -> Result<Option<Task>, StoreError> {
+        Ok(self.tasks.lock().unwrap().remove(id).map(|(task, _)| task))
+    }
+
+    fn list(&self) -> Result<Vec<Task>, StoreError> {
+        let mut tasks: Vec<Task> = self
+            .tasks
+            .lock()
+            .unwrap()
+            .values()
+            .map(|(task, _)| task.clone())
+            .collect();
+        tasks.sort_by_key(|task| task.created_at);
+        Ok(tasks)
+    }
+}
+
+/// On-disk form of a [`Task`], including the fields the status API hides.
+#[derive(Serialize, Deserialize)]
+struct TaskRecord {
+    id: String,
+    status: TaskStatus,
+    original_filename: String,
+    file_path: PathBuf,
+    created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    progress: Option<TaskProgress>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    callback_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    deliveries: Vec<DeliveryAttempt>,
+}
+
+impl From<&Task> for TaskRecord {
+    fn from(task: &Task) -> Self {
+        Self {
+            id: task.id.clone(),
+            status: task.status.clone(),
+            original_filename: task.original_filename.clone(),
+            file_path: task.file_path.clone(),
+            created_at: task.created_at,
+            progress: task.progress.clone(),
+            callback_url: task.callback_url.clone(),
+            deliveries: task.deliveries.clone(),
+        }
+    }
+}
+
+impl From<TaskRecord> for Task {
+    fn from(record: TaskRecord) -> Self {
+        Self {
+            id: record.id,
+            status: record.status,
+            original_filename: record.original_filename,
+            file_path: record.file_path,
+            created_at: record.created_at,
+            progress: record.progress,
+            callback_url: record.callback_url,
+            deliveries: record.deliveries,
+        }
+    }
+}
+
+/// Suffix of task record files
+const TASK_SUFFIX: &str = ".task.json";
+/// Suffix of transcript files
+const RESULT_SUFFIX: &str = ".transcript.json";
+
+/// Tasks stored as files in a directory: `<id>.task.json` holds the task and
+/// `<id>.transcript.json` the finished transcript.
+///
+/// Files are replaced atomically, so a crash never leaves a half-written
+/// record behind.
+pub struct FileTaskStore {
+    dir: PathBuf,
+    // Serializes read-modify-write updates of a record
+    lock: Mutex<()>,
+}
+
+impl FileTaskStore {
+    /// Open the store in `dir`, creating the directory if needed.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, StoreError> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Path of a file belonging to task `id`. Ids come from URLs, so
+    /// anything that could escape the directory is rejected.
+    fn path(&self, id: &str, suffix: &str) -> Result<PathBuf, StoreError> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(StoreError::InvalidId(id.to_string()));
+        }
+        Ok(self.dir.join(format!("{}{}", id, suffix)))
+    }
+
+    fn read_task(&self, id: &str) -> Result<Option<Task>, StoreError> {
+        let path = match self.path(id, TASK_SUFFIX) {
+            Ok(path) => path,
+            Err(StoreError::InvalidId(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(Some(serde_json::from_str::<TaskRecord>(&json)?.into())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_task(&self, task: &Task) -> Result<(), StoreError> {
+        let json = serde_json::to_string_pretty(&TaskRecord::from(task))?;
+        write_atomic(&self.path(&task.id, TASK_SUFFIX)?, &json)
+    }
+}
+
+/// Write through a temporary file and rename it over `path`.
+fn write_atomic(path: &Path, contents: &str) -> Result<(), StoreError> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Remove a file, treating a missing file as success.
+fn remove_file(path: &Path) -> Result<(), StoreError> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+impl TaskStore for FileTaskStore {
+    fn insert(&self, task: Task) -> Result<(), StoreError> {
+        let _guard = self.lock.lock().unwrap();
+        remove_file(&self.path(&task.id, RESULT_SUFFIX)?)?;
+        self.write_task(&task)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Task>, StoreError> {
+        self.read_task(id)
+    }
+
+    fn set_status(&self, id: &str, status: TaskStatus) -> Result<bool, StoreError> {
+        let _guard = self.lock.lock().unwrap();
+        let Some(mut task) = self.read_task(id)?.filter(|task| !is_cancelled(task)) else {
+            return Ok(false);
+        };
+        task.status = status;
+        self.write_task(&task)?;
+        Ok(true)
+    }
+
+    fn set_progress(&self, id: &str, progress: &TaskProgress) -> Result<bool, StoreError> {
+        let _guard = self.lock.lock().unwrap();
+        let Some(mut task) = self
+            .read_task(id)?
+            .filter(|task| task.status == TaskStatus::Processing)
+        else {
+            return Ok(false);
+        };
+        task.progress = Some(progress.clone());
+        self.write_task(&task)?;
+        Ok(true)
+    }
+
+    fn complete(&self, id: &str, transcript: &Transcript) -> Result<bool, StoreError> {
+        let _guard = self.lock.lock().unwrap();
+        let Some(mut task) = self.read_task(id)?.filter(|task| !is_cancelled(task)) else {
+            return Ok(false);
+        };
+        // Result first: a completed record always has its transcript
+        write_atomic(&self.path(id, RESULT_SUFFIX)?, &transcript.to_json()?)?;
+        task.status = TaskStatus::Completed;
+        self.write_task(&task)?;
+        Ok(true)
+    }
+
+    fn record_delivery(&self, id: &str, attempt: &DeliveryAttempt) -> Result<bool, StoreError> {
+        let _guard = self.lock.lock().unwrap();
+        let Some(mut task) = self.read_task(id)? else {
+            return Ok(false);
+        };
+        task.deliveries.push(attempt.clone());
+        self.write_task(&task)?;
+        Ok(true)
+    }
+
+    fn result(&self, id: &str) -> Result<Option<Transcript>, StoreError> {
+        let Ok(path) = self.path(id, RESULT_SUFFIX) else {
+            return Ok(None);
+        };
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(Some(Transcript::from_json(&json)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn remove(&self, id: &str) -> Result<Option<Task>, StoreError> {
+        let _guard = self.lock.lock().unwrap();
+        let Some(task) = self.read_task(id)? else {
+            return Ok(None);
+        };
+        remove_file(&self.path(id, RESULT_SUFFIX)?)?;
+        remove_file(&self.path(id, TASK_SUFFIX)?)?;
+        Ok(Some(task))
+    }
+
+    fn list(&self) -> Result<Vec<Task>, StoreError> {
+        let mut tasks = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some(id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(TASK_SUFFIX))
+            else {
+                continue;
+            };
+            // One corrupt record must not keep the server from starting
+            match self.read_task(id) {
+                Ok(Some(task)) => tasks.push(task),
+                Ok(None) => {}
+                Err(e) => log::warn!("Skipping task record {}: {}", path.display(), e),
+            }
+        }
+        tasks.sort_by_key(|task| task.created_at);
+        Ok(tasks)
+    }
+}
+
+/// How long finished (completed, failed or cancelled) tasks are kept.
+/// Queued and processing tasks are never removed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionPolicy {
+    /// Remove finished tasks created longer ago than this
+    pub max_age: Option<Duration>,
+    /// Keep at most this many finished tasks, removing the oldest first
+    pub max_count: Option<usize>,
+}
+
+impl RetentionPolicy {
+    /// Whether the policy ever removes a task.
+    pub fn is_enabled(&self) -> bool {
+        self.max_age.is_some() || self.max_count.is_some()
+    }
+
+    /// Ids of the finished `tasks` the policy removes at `now`, in Unix
+    /// milliseconds.
+    pub fn expired(&self, tasks: &[Task], now: u64) -> Vec<String> {
+        let mut finished: Vec<&Task> = tasks.iter().filter(|task| !is_unfinished(&task.status)).collect();
+        // Newest first, so the tasks beyond `max_count` are the oldest
+        finished.sort_by_key(|task| std::cmp::Reverse(task.created_at));
+        finished
+            .into_iter()
+            .enumerate()
+            .filter(|(kept, task)| {
+                self.max_count.is_some_and(|max| *kept >= max)
+                    || self
+                        .max_age
+                        .is_some_and(|age| now.saturating_sub(task.created_at) > age.as_millis() as u64)
+            })
+            .map(|(_, task)| task.id.clone())
+            .collect()
+    }
+
+    /// Remove the finished tasks the policy no longer keeps, together with
+    /// their results and uploaded audio. Returns the removed tasks.
+    pub fn apply(&self, store: &dyn TaskStore) -> Result<Vec<Task>, StoreError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        let mut removed = Vec::new();
+        for id in self.expired(&store.list()?, now) {
+            let Some(task) = store.remove(&id)? else {
+                continue;
+            };
+            match fs::remove_file(&task.file_path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    log::warn!("Failed to delete upload of task {}: {}", id, e)
+                }
+                _ => {}
+            }
+            removed.push(task);
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::TranscriptMetadata;
+    use crate::TranscriptionSegment;
+
+    fn task(id: &str, created_at: u64) -> Task {
+        Task {
+            created_at,
+            ..Task::new(
+                id.to_string(),
+                format!("{}.mp3", id),
+                PathBuf::from("uploads").join(id),
+            )
+        }
+    }
+
+    fn transcript() -> Transcript {
+        let segments = vec![TranscriptionSegment {
+            start: 0.0,
+            end: 1.0,
+            text: "大家好".to_string(),
+            ..Default::default()
+        }];
+        Transcript::from_segments(&segments, TranscriptMetadata::default())
+    }
+
+    fn progress() -> TaskProgress {
+        TaskProgress {
+            chunks_done: 1,
+            chunks_total: 4,
+            audio_processed: 300.0,
+            audio_duration: 1200.0,
+            eta_seconds: Some(90.0),
+        }
+    }
+
+    fn exercise(store: &dyn TaskStore) {
+        store.insert(task("b", 2)).unwrap();
+        store.insert(task("a", 1)).unwrap();
+        store.insert(task("c", 3)).unwrap();
+        store.insert(task("d", 4)).unwrap();
+        assert!(store.set_status("b", TaskStatus::Processing).unwrap());
+        assert!(store.set_progress("b", &progress()).unwrap());
+        assert!(!store.set_progress("a", &progress()).unwrap());
+        assert!(store.complete("c", &transcript()).unwrap());
+        assert!(!store.set_status("missing", TaskStatus::Processing).unwrap());
+
+        // Cancellation is final
+        assert!(store.set_status("d", TaskStatus::Cancelled).unwrap());
+        assert!(!store.set_status("d", TaskStatus::Processing).unwrap());
+        assert!(!store.complete("d", &transcript()).unwrap());
+        assert_eq!(store.get("d").unwrap().unwrap().status, TaskStatus::Cancelled);
+
+        let attempt = DeliveryAttempt {
+            attempt: 1,
+            at: 5,
+            status: Some(200),
+            error: None,
+        };
+        assert!(store.record_delivery("c", &attempt).unwrap());
+        assert_eq!(store.get("c").unwrap().unwrap().deliveries, vec![attempt]);
+        assert_eq!(store.result("d").unwrap(), None);
+
+        let unfinished: Vec<String> = store
+            .unfinished()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(unfinished, vec!["a", "b"]);
+        assert_eq!(
+            store.get("c").unwrap().unwrap().status,
+            TaskStatus::Completed
+        );
+        assert_eq!(store.result("c").unwrap(), Some(transcript()));
+        assert_eq!(store.result("a").unwrap(), None);
+
+        assert_eq!(store.remove("c").unwrap().unwrap().id, "c");
+        assert!(store.get("c").unwrap().is_none());
+        assert!(store.result("c").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        exercise(&MemoryTaskStore::new());
+    }
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        exercise(&FileTaskStore::open(dir.path()).unwrap());
+
+        let reopened = FileTaskStore::open(dir.path()).unwrap();
+        let task = reopened.get("b").unwrap().unwrap();
+        assert_eq!(task.status, TaskStatus::Processing);
+        assert_eq!(task.progress, Some(progress()));
+        assert_eq!(task.file_path, PathBuf::from("uploads/b"));
+        assert_eq!(reopened.unfinished().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_retention_removes_old_and_excess_finished_tasks() {
+        let hour = 3_600_000;
+        let store = MemoryTaskStore::new();
+        for (id, hours_ago, status) in [
+            ("old", 30, TaskStatus::Completed),
+            ("old-queued", 30, TaskStatus::Queued),
+            ("failed", 3, TaskStatus::Failed("Decoding failed".to_string())),
+            ("cancelled", 2, TaskStatus::Cancelled),
+            ("new", 1, TaskStatus::Completed),
+        ] {
+            let mut task = task(id, 100 * hour - hours_ago * hour);
+            task.status = status;
+            store.insert(task).unwrap();
+        }
+        let tasks = store.list().unwrap();
+        let now = 100 * hour;
+
+        assert!(!RetentionPolicy::default().is_enabled());
+        assert!(RetentionPolicy::default().expired(&tasks, now).is_empty());
+
+        let by_age = RetentionPolicy {
+            max_age: Some(Duration::from_secs(24 * 3600)),
+            ..Default::default()
+        };
+        assert_eq!(by_age.expired(&tasks, now), vec!["old"]);
+
+        let by_count = RetentionPolicy {
+            max_count: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(by_count.expired(&tasks, now), vec!["failed", "old"]);
+
+        // Uploads that are already gone do not fail the cleanup
+        let removed = by_count.apply(&store).unwrap();
+        assert_eq!(removed.len(), 2);
+        let mut left: Vec<String> = store.list().unwrap().into_iter().map(|t| t.id).collect();
+        left.sort();
+        assert_eq!(left, vec!["cancelled", "new", "old-queued"]);
+    }
+
+    #[test]
+    fn test_file_store_rejects_path_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileTaskStore::open(dir.path()).unwrap();
+        assert!(matches!(
+            store.insert(task("../x", 0)),
+            Err(StoreError::InvalidId(_))
+        ));
+        assert!(store.get("../x").unwrap().is_none());
+        assert!(store.result("../x").unwrap().is_none());
+    }
+}
@@ -0,0 +1,281 @@
+//! Advanced SubStation Alpha (ASS) output.
+//!
+//! Produces a complete script with `[Script Info]`, `[V4+ Styles]` and
+//! `[Events]` sections, suitable for burning subtitles into video with
+//! ffmpeg/libass. The default style uses a CJK font so Chinese text renders
+//! without tofu boxes.
+
+use crate::TranscriptionSegment;
+use std::fmt::Write;
+
+/// An ASS colour with 8-bit channels.
+///
+/// `alpha` follows the ASS convention: `0` is opaque, `255` fully transparent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AssColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub alpha: u8,
+}
+
+impl AssColor {
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+
+    /// Opaque colour from red, green and blue channels.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, alpha: 0 }
+    }
+
+    /// The same colour with the given transparency.
+    pub const fn with_alpha(self, alpha: u8) -> Self {
+        Self { alpha, ..self }
+    }
+
+    /// Format as `&HAABBGGRR`, the byte order ASS uses.
+    pub fn to_ass(&self) -> String {
+        format!(
+            "&H{:02X}{:02X}{:02X}{:02X}",
+            self.alpha, self.b, self.g, self.r
+        )
+    }
+}
+
+/// Visual style for all dialogue lines.
+#[derive(Debug, Clone)]
+pub struct AssStyle {
+    /// Style name referenced by each dialogue line. Commas are written as
+    /// spaces, since the `Style:` line is comma-separated
+    pub name: String,
+    /// Font family. Must be installed where the subtitles are rendered;
+    /// common CJK choices are "Noto Sans CJK TC", "PingFang TC" (macOS) and
+    /// "Microsoft JhengHei" (Windows). Commas are written as spaces
+    pub font_name: String,
+    /// Font size in script pixels (relative to `play_res_y`)
+    pub font_size: u32,
+    pub primary_color: AssColor,
+    pub outline_color: AssColor,
+    /// Shadow colour, or box colour when `opaque_box` is set
+    pub back_color: AssColor,
+    pub bold: bool,
+    pub italic: bool,
+    /// Outline width in pixels
+    pub outline: f32,
+    /// Shadow depth in pixels
+    pub shadow: f32,
+    /// Draw an opaque box behind the text instead of an outline
+    pub opaque_box: bool,
+    /// Numpad-style alignment: 1-3 bottom, 4-6 middle, 7-9 top (2 = bottom centre)
+    pub alignment: u8,
+    pub margin_left: u32,
+    pub margin_right: u32,
+    /// Distance from the bottom (or top) edge in pixels
+    pub margin_vertical: u32,
+}
+
+impl Default for AssStyle {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_string(),
+            font_name: "Noto Sans CJK TC".to_string(),
+            font_size: 56,
+            primary_color: AssColor::WHITE,
+            outline_color: AssColor::BLACK,
+            back_color: AssColor::BLACK.with_alpha(0x80),
+            bold: false,
+            italic: false,
+            outline: 2.5,
+            shadow: 1.0,
+            opaque_box: false,
+            alignment: 2,
+            margin_left: 40,
+            margin_right: 40,
+            margin_vertical: 48,
+        }
+    }
+}
+
+/// Options for ASS output.
+#[derive(Debug, Clone)]
+pub struct AssOptions {
+    /// Script title shown by subtitle editors
+    pub title: String,
+    /// Script resolution; sizes and margins are relative to it
+    pub play_res_x: u32,
+    pub play_res_y: u32,
+    /// Style applied to every dialogue line
+    pub style: AssStyle,
+}
+
+impl Default for AssOptions {
+    fn default() -> Self {
+        Self {
+            title: "transcribe-rs".to_string(),
+            play_res_x: 1920,
+            play_res_y: 1080,
+            style: AssStyle::default(),
+        }
+    }
+}
+
+/// Convert seconds to ASS timestamp format (H:MM:SS.cc)
+fn format_ass_timestamp(seconds: f32) -> String {
+    let total_centis = (seconds.max(0.0) as f64 * 100.0).round() as u64;
+    let hours = total_centis / 360_000;
+    let minutes = (total_centis % 360_000) / 6000;
+    let secs = (total_centis % 6000) / 100;
+    let centis = total_centis % 100;
+
+    format!("{}:{:02}:{:02}.{:02}", hours, minutes, secs, centis)
+}
+
+/// Escape dialogue text: line breaks become `\N` and braces, which would
+/// start an override block, are replaced.
+fn escape_ass_text(text: &str) -> String {
+    text.replace('{', "(")
+        .replace('}', ")")
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\\N")
+}
+
+/// Make a name safe for a comma-separated `Style:` or `Dialogue:` field:
+/// commas would shift every later field and line breaks would end the line,
+/// so both become spaces.
+fn ass_field(name: &str) -> String {
+    name.replace([',', '\r', '\n'], " ").trim().to_string()
+}
+
+fn ass_bool(value: bool) -> i32 {
+    if value {
+        -1
+    } else {
+        0
+    }
+}
+
+/// Generate a complete ASS script from transcription segments
+pub fn generate_ass(segments: &[TranscriptionSegment], options: &AssOptions) -> String {
+    let style = &options.style;
+    let style_name = ass_field(&style.name);
+    let mut output = String::new();
+
+    writeln!(&mut output, "[Script Info]").unwrap();
+    writeln!(&mut output, "; Script generated by transcribe-rs").unwrap();
+    writeln!(&mut output, "Title: {}", options.title).unwrap();
+    writeln!(&mut output, "ScriptType: v4.00+").unwrap();
+    writeln!(&mut output, "WrapStyle: 0").unwrap();
+    writeln!(&mut output, "ScaledBorderAndShadow: yes").unwrap();
+    writeln!(&mut output, "PlayResX: {}", options.play_res_x).unwrap();
+    writeln!(&mut output, "PlayResY: {}", options.play_res_y).unwrap();
+    writeln!(&mut output).unwrap();
+
+    writeln!(&mut output, "[V4+ Styles]").unwrap();
+    writeln!(
+        &mut output,
+        "Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, \
+         BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, \
+         BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding"
+    )
+    .unwrap();
+    writeln!(
+        &mut output,
+        "Style: {},{},{},{},{},{},{},{},{},0,0,100,100,0,0,{},{},{},{},{},{},{},1",
+        style_name,
+        ass_field(&style.font_name),
+        style.font_size,
+        style.primary_color.to_ass(),
+        style.primary_color.to_ass(),
+        style.outline_color.to_ass(),
+        style.back_color.to_ass(),
+        ass_bool(style.bold),
+        ass_bool(style.italic),
+        if style.opaque_box { 3 } else { 1 },
+        style.outline,
+        style.shadow,
+        style.alignment,
+        style.margin_left,
+        style.margin_right,
+        style.margin_vertical,
+    )
+    .unwrap();
+    writeln!(&mut output).unwrap();
+
+    writeln!(&mut output, "[Events]").unwrap();
+    writeln!(
+        &mut output,
+        "Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text"
+    )
+    .unwrap();
+    for segment in segments {
+        writeln!(
+            &mut output,
+            "Dialogue: 0,{},{},{},,0,0,0,,{}",
+            format_ass_timestamp(segment.start),
+            format_ass_timestamp(segment.end),
+            style_name,
+            escape_ass_text(segment.text.trim()),
+        )
+        .unwrap();
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_ass_timestamp() {
+        assert_eq!(format_ass_timestamp(0.0), "0:00:00.00");
+        assert_eq!(format_ass_timestamp(3661.256), "1:01:01.26");
+    }
+
+    #[test]
+    fn test_color_byte_order() {
+        assert_eq!(
+            AssColor::rgb(0x12, 0x34, 0x56).with_alpha(0x80).to_ass(),
+            "&H80563412"
+        );
+    }
+
+    #[test]
+    fn test_generate_ass() {
+        let segments = vec![TranscriptionSegment {
+            start: 1.5,
+            end: 4.0,
+            text: "第一行\n{第二行}".to_string(),
+            ..Default::default()
+        }];
+
+        let script = generate_ass(&segments, &AssOptions::default());
+        assert!(script.starts_with("[Script Info]\n"));
+        assert!(script.contains("\n[V4+ Styles]\n"));
+        assert!(script.contains(
+            "Style: Default,Noto Sans CJK TC,56,&H00FFFFFF,&H00FFFFFF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2.5,1,2,40,40,48,1\n"
+        ));
+        assert!(script
+            .ends_with("Dialogue: 0,0:00:01.50,0:00:04.00,Default,,0,0,0,,第一行\\N(第二行)\n"));
+    }
+
+    #[test]
+    fn test_commas_in_style_names_keep_fields_aligned() {
+        let options = AssOptions {
+            style: AssStyle {
+                name: "Main, large".to_string(),
+                font_name: "Foo, Bar\nSans".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let script = generate_ass(&[TranscriptionSegment::default()], &options);
+        let style = script.lines().find(|l| l.starts_with("Style: ")).unwrap();
+        assert!(style.starts_with("Style: Main  large,Foo  Bar Sans,56,"));
+        assert_eq!(style.split(',').count(), 23);
+        assert!(script.contains("Dialogue: 0,0:00:00.00,0:00:00.00,Main  large,,"));
+    }
+}
@@ -0,0 +1,235 @@
+//! Subtitle output for transcription segments, and parsing of existing files.
+//!
+//! Supported output formats:
+//! - **SRT** via [`generate_srt`]
+//! - **WebVTT** via [`generate_vtt`], with optional cue settings and `NOTE` blocks
+//! - **ASS** via [`generate_ass`], with configurable styles for burning into video
+//!
+//! [`SubtitleFormat`] lets callers pick a format at runtime (e.g. from a CLI
+//! flag or query parameter).
+//!
+//! SRT and WebVTT files can be read back with [`parse_srt`], [`parse_vtt`] or
+//! the auto-detecting [`parse_subtitles`], e.g. to convert between formats.
+//!
+//! Engine segments are often too long to read comfortably; run them through
+//! [`resegment`] before rendering to get cues with bounded line length,
+//! duration and reading speed.
+
+pub mod ass;
+pub mod parse;
+pub mod resegment;
+
+pub use ass::{generate_ass, AssColor, AssOptions, AssStyle};
+pub use parse::{parse_srt, parse_subtitles, parse_vtt, SubtitleParseError};
+pub use resegment::{resegment, ResegmentParams};
+
+use crate::TranscriptionSegment;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Convert seconds to a clock timestamp (HH:MM:SS<sep>mmm)
+fn format_clock(seconds: f32, millis_separator: char) -> String {
+    // Round to whole milliseconds first so f32 error can't turn ,001 into ,000
+    let total_millis = (seconds.max(0.0) as f64 * 1000.0).round() as u64;
+    let hours = total_millis / 3_600_000;
+    let minutes = (total_millis % 3_600_000) / 60_000;
+    let secs = (total_millis % 60_000) / 1000;
+    let millis = total_millis % 1000;
+
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        hours, minutes, secs, millis_separator, millis
+    )
+}
+
+/// Convert seconds to SRT timestamp format (HH:MM:SS,mmm)
+fn format_timestamp(seconds: f32) -> String {
+    format_clock(seconds, ',')
+}
+
+/// Convert seconds to WebVTT timestamp format (HH:MM:SS.mmm)
+fn format_vtt_timestamp(seconds: f32) -> String {
+    format_clock(seconds, '.')
+}
+
+/// Generate SRT content from transcription segments
+pub fn generate_srt(segments: &[TranscriptionSegment]) -> String {
+    let mut output = String::new();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let start = format_timestamp(segment.start);
+        let end = format_timestamp(segment.end);
+        
+        // SRT index starts at 1
+        writeln!(&mut output, "{}", i + 1).unwrap();
+        writeln!(&mut output, "{} --> {}", start, end).unwrap();
+        writeln!(&mut output, "{}", segment.text.trim()).unwrap();
+        writeln!(&mut output).unwrap(); // Empty line after each block
+    }
+
+    output
+}
+
+/// Options for WebVTT output.
+#[derive(Debug, Clone, Default)]
+pub struct VttOptions {
+    /// Cue settings appended to every timing line, e.g. `"line:90% align:center"`
+    pub cue_settings: Option<String>,
+    /// Comments written as `NOTE` blocks right after the `WEBVTT` header
+    pub notes: Vec<String>,
+    /// Whether to number cues with an identifier line (1, 2, 3, ...)
+    pub cue_identifiers: bool,
+}
+
+/// Generate WebVTT content from transcription segments
+pub fn generate_vtt(segments: &[TranscriptionSegment], options: &VttOptions) -> String {
+    let mut output = String::new();
+
+    writeln!(&mut output, "WEBVTT").unwrap();
+    writeln!(&mut output).unwrap();
+
+    for note in &options.notes {
+        // "-->" is not allowed inside a NOTE block, and a blank line would end it early
+        let note = note.replace("-->", "->");
+        let lines: Vec<&str> = note.lines().filter(|l| !l.trim().is_empty()).collect();
+        if lines.is_empty() {
+            continue;
+        }
+        writeln!(&mut output, "NOTE {}", lines.join("\n")).unwrap();
+        writeln!(&mut output).unwrap();
+    }
+
+    let settings = options
+        .cue_settings
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+
+    for (i, segment) in segments.iter().enumerate() {
+        if options.cue_identifiers {
+            writeln!(&mut output, "{}", i + 1).unwrap();
+        }
+
+        let start = format_vtt_timestamp(segment.start);
+        let end = format_vtt_timestamp(segment.end);
+        match settings {
+            Some(settings) => writeln!(&mut output, "{} --> {} {}", start, end, settings).unwrap(),
+            None => writeln!(&mut output, "{} --> {}", start, end).unwrap(),
+        }
+
+        writeln!(&mut output, "{}", escape_vtt_text(segment.text.trim())).unwrap();
+        writeln!(&mut output).unwrap(); // Empty line after each cue
+    }
+
+    output
+}
+
+/// Escape cue text so it can't be parsed as markup or end the cue early.
+fn escape_vtt_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Subtitle formats that can be selected at runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SubtitleFormat {
+    /// SubRip (`.srt`, default)
+    #[default]
+    Srt,
+    /// WebVTT (`.vtt`)
+    Vtt,
+    /// Advanced SubStation Alpha (`.ass`)
+    Ass,
+}
+
+impl SubtitleFormat {
+    /// File extension without the leading dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Ass => "ass",
+        }
+    }
+
+    /// MIME type used when serving the file.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Srt => "application/x-subrip; charset=utf-8",
+            Self::Vtt => "text/vtt; charset=utf-8",
+            Self::Ass => "text/x-ssa; charset=utf-8",
+        }
+    }
+
+    /// Render segments with default options for this format.
+    pub fn render(&self, segments: &[TranscriptionSegment]) -> String {
+        match self {
+            Self::Srt => generate_srt(segments),
+            Self::Vtt => generate_vtt(segments, &VttOptions::default()),
+            Self::Ass => generate_ass(segments, &AssOptions::default()),
+        }
+    }
+}
+
+impl FromStr for SubtitleFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "srt" => Ok(Self::Srt),
+            "vtt" | "webvtt" => Ok(Self::Vtt),
+            "ass" | "ssa" => Ok(Self::Ass),
+            other => Err(format!("Unknown subtitle format: {}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f32, end: f32, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            start,
+            end,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0.0), "00:00:00,000");
+        assert_eq!(format_timestamp(61.5), "00:01:01,500");
+        assert_eq!(format_timestamp(3661.001), "01:01:01,001");
+    }
+
+    #[test]
+    fn test_generate_vtt() {
+        let segments = vec![seg(0.0, 1.5, " Hello <world> "), seg(61.5, 63.0, "A & B")];
+        let options = VttOptions {
+            cue_settings: Some("line:90% align:center".to_string()),
+            notes: vec!["Generated by transcribe-rs --> test".to_string()],
+            cue_identifiers: false,
+        };
+
+        let expected = "WEBVTT\n\n\
+            NOTE Generated by transcribe-rs -> test\n\n\
+            00:00:00.000 --> 00:00:01.500 line:90% align:center\nHello &lt;world&gt;\n\n\
+            00:01:01.500 --> 00:01:03.000 line:90% align:center\nA &amp; B\n\n";
+        assert_eq!(generate_vtt(&segments, &options), expected);
+    }
+
+    #[test]
+    fn test_subtitle_format_from_str() {
+        assert_eq!("SRT".parse::<SubtitleFormat>(), Ok(SubtitleFormat::Srt));
+        assert_eq!("webvtt".parse::<SubtitleFormat>(), Ok(SubtitleFormat::Vtt));
+        assert_eq!("ass".parse::<SubtitleFormat>(), Ok(SubtitleFormat::Ass));
+        assert!("docx".parse::<SubtitleFormat>().is_err());
+    }
+}
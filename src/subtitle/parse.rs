@@ -0,0 +1,292 @@
+//! Parsing of SRT and WebVTT files back into transcription segments.
+//!
+//! The parsers are lenient about the details real-world files get wrong
+//! (byte order marks, CRLF line endings, missing cue numbers, hours omitted
+//! from timestamps) but reject timing lines they cannot read, so a broken
+//! file never silently loses text.
+
+use crate::TranscriptionSegment;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SubtitleParseError {
+    #[error("Missing WEBVTT header")]
+    MissingHeader,
+    #[error("Line {line}: invalid timing line '{text}'")]
+    InvalidTiming { line: usize, text: String },
+    #[error("Line {line}: cue text without a timing line")]
+    MissingTiming { line: usize },
+}
+
+/// A block of consecutive non-empty lines with the 1-based number of its first line.
+struct Block<'a> {
+    line: usize,
+    lines: Vec<&'a str>,
+}
+
+fn blocks(content: &str) -> Vec<Block<'_>> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            blocks.extend(current.take());
+        } else {
+            current
+                .get_or_insert(Block {
+                    line: i + 1,
+                    lines: Vec::new(),
+                })
+                .lines
+                .push(line);
+        }
+    }
+    blocks.extend(current);
+    blocks
+}
+
+/// Parse `HH:MM:SS,mmm`, `HH:MM:SS.mmm` or `MM:SS.mmm` into seconds.
+fn parse_timestamp(text: &str) -> Option<f32> {
+    let (clock, fraction) = text
+        .trim()
+        .split_once([',', '.'])
+        .unwrap_or((text.trim(), "0"));
+    if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let parts: Vec<u64> = clock
+        .split(':')
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    let (hours, minutes, seconds) = match parts[..] {
+        [h, m, s] => (h, m, s),
+        [m, s] => (0, m, s),
+        _ => return None,
+    };
+    if minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+
+    let fraction: f64 = format!("0.{}", fraction).parse().ok()?;
+    Some(((hours * 3600 + minutes * 60 + seconds) as f64 + fraction) as f32)
+}
+
+/// Parse a `start --> end [settings]` line; settings are ignored.
+fn parse_timing(line: &str, line_number: usize) -> Result<(f32, f32), SubtitleParseError> {
+    let invalid = || SubtitleParseError::InvalidTiming {
+        line: line_number,
+        text: line.to_string(),
+    };
+
+    let (start, rest) = line.split_once("-->").ok_or_else(invalid)?;
+    let end = rest.split_whitespace().next().ok_or_else(invalid)?;
+    match (parse_timestamp(start), parse_timestamp(end)) {
+        (Some(start), Some(end)) => Ok((start, end)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse an SRT file.
+///
+/// Cue numbers are optional; multi-line cue text is joined with `\n`.
+pub fn parse_srt(content: &str) -> Result<Vec<TranscriptionSegment>, SubtitleParseError> {
+    let mut segments = Vec::new();
+
+    for block in blocks(content) {
+        let timing = block
+            .lines
+            .iter()
+            .take(2)
+            .position(|l| l.contains("-->"))
+            .ok_or(SubtitleParseError::MissingTiming { line: block.line })?;
+        let (start, end) = parse_timing(block.lines[timing], block.line + timing)?;
+
+        segments.push(TranscriptionSegment {
+            start,
+            end,
+            text: block.lines[timing + 1..].join("\n").trim().to_string(),
+            words: None,
+            confidence: None,
+            no_speech_prob: None,
+            language: None,
+        });
+    }
+
+    Ok(segments)
+}
+
+/// Parse a WebVTT file.
+///
+/// `NOTE`, `STYLE` and `REGION` blocks are skipped. Markup such as voice
+/// spans (`<v Speaker>`) and inline timestamps is removed and character
+/// references are decoded, so the text matches what a viewer would read.
+pub fn parse_vtt(content: &str) -> Result<Vec<TranscriptionSegment>, SubtitleParseError> {
+    let mut blocks = blocks(content).into_iter();
+
+    let header = blocks.next().ok_or(SubtitleParseError::MissingHeader)?;
+    let signature = header.lines[0];
+    if !(signature == "WEBVTT"
+        || signature.starts_with("WEBVTT ")
+        || signature.starts_with("WEBVTT\t"))
+    {
+        return Err(SubtitleParseError::MissingHeader);
+    }
+
+    let mut segments = Vec::new();
+    for block in blocks {
+        let first = block.lines[0];
+        if ["NOTE", "STYLE", "REGION"]
+            .iter()
+            .any(|kw| first == *kw || first.starts_with(&format!("{} ", kw)))
+        {
+            continue;
+        }
+
+        // An optional cue identifier may precede the timing line
+        let timing = block
+            .lines
+            .iter()
+            .take(2)
+            .position(|l| l.contains("-->"))
+            .ok_or(SubtitleParseError::MissingTiming { line: block.line })?;
+        let (start, end) = parse_timing(block.lines[timing], block.line + timing)?;
+
+        let text: Vec<String> = block.lines[timing + 1..]
+            .iter()
+            .map(|l| unescape_vtt_text(&strip_vtt_tags(l)))
+            .collect();
+        segments.push(TranscriptionSegment {
+            start,
+            end,
+            text: text.join("\n").trim().to_string(),
+            words: None,
+            confidence: None,
+            no_speech_prob: None,
+            language: None,
+        });
+    }
+
+    Ok(segments)
+}
+
+/// Parse SRT or WebVTT, detecting the format from the `WEBVTT` header.
+pub fn parse_subtitles(content: &str) -> Result<Vec<TranscriptionSegment>, SubtitleParseError> {
+    let body = content.strip_prefix('\u{feff}').unwrap_or(content);
+    if body.starts_with("WEBVTT") {
+        parse_vtt(content)
+    } else {
+        parse_srt(content)
+    }
+}
+
+fn strip_vtt_tags(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => output.push(c),
+            _ => {}
+        }
+    }
+    output
+}
+
+fn unescape_vtt_text(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&lrm;", "\u{200e}")
+        .replace("&rlm;", "\u{200f}")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::{generate_srt, generate_vtt, VttOptions};
+
+    fn seg(start: f32, end: f32, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            start,
+            end,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("01:01:01,250"), Some(3661.25));
+        assert_eq!(parse_timestamp("02:03.5"), Some(123.5));
+        assert_eq!(parse_timestamp("00:61:00,000"), None);
+        assert_eq!(parse_timestamp("00:00:aa,000"), None);
+    }
+
+    #[test]
+    fn test_srt_roundtrip() {
+        let segments = vec![seg(0.0, 1.5, "大家好"), seg(1.5, 3.25, "第一行\n第二行")];
+        assert_eq!(parse_srt(&generate_srt(&segments)).unwrap(), segments);
+    }
+
+    #[test]
+    fn test_parse_srt_lenient() {
+        let content = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,000\r\nHello\r\n\r\n\r\n00:00:03,000 --> 00:00:04,000\r\nWorld\r\n";
+        assert_eq!(
+            parse_srt(content).unwrap(),
+            vec![seg(1.0, 2.0, "Hello"), seg(3.0, 4.0, "World")]
+        );
+    }
+
+    #[test]
+    fn test_parse_srt_errors() {
+        assert_eq!(
+            parse_srt("1\n00:00:01 -> 00:00:02\nHello\n"),
+            Err(SubtitleParseError::MissingTiming { line: 1 })
+        );
+        assert!(matches!(
+            parse_srt("1\n00:00:xx,000 --> 00:00:02,000\nHello\n"),
+            Err(SubtitleParseError::InvalidTiming { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_vtt_roundtrip() {
+        let segments = vec![seg(0.0, 1.5, "a < b & c"), seg(2.0, 3.0, "second")];
+        let options = VttOptions {
+            cue_settings: Some("line:90%".to_string()),
+            notes: vec!["generated".to_string()],
+            cue_identifiers: true,
+        };
+        assert_eq!(
+            parse_vtt(&generate_vtt(&segments, &options)).unwrap(),
+            segments
+        );
+    }
+
+    #[test]
+    fn test_parse_vtt_markup() {
+        let content = "WEBVTT - title\n\nSTYLE\n::cue { color: red }\n\nintro\n00:01.000 --> 00:02.000\n<v Alice>Hi <b>there</b></v>\n";
+        assert_eq!(parse_vtt(content).unwrap(), vec![seg(1.0, 2.0, "Hi there")]);
+        assert_eq!(
+            parse_vtt("1\n00:01.000 --> 00:02.000\nHi\n"),
+            Err(SubtitleParseError::MissingHeader)
+        );
+    }
+
+    #[test]
+    fn test_parse_subtitles_detects_format() {
+        assert_eq!(
+            parse_subtitles("WEBVTT\n\n00:01.000 --> 00:02.000\n&amp;\n").unwrap()[0].text,
+            "&"
+        );
+        assert_eq!(
+            parse_subtitles("1\n00:00:01,000 --> 00:00:02,000\n&amp;\n").unwrap()[0].text,
+            "&amp;"
+        );
+    }
+}
@@ -0,0 +1,507 @@
+//! Re-segmentation of transcription segments into readable subtitle cues.
+//!
+//! Engines emit segments that suit transcription, not reading: Whisper
+//! regularly produces 20-second segments with several sentences of text.
+//! [`resegment`] splits such segments into cues that respect a maximum line
+//! width, number of lines, cue duration and reading speed. Splits prefer
+//! sentence ends, then clause punctuation, then word boundaries, and stay
+//! close to the middle of the text so cues come out balanced. Timestamps are
+//! redistributed in proportion to the amount of text in each cue.
+//!
+//! Cues too fast to read are extended into the gap before the next cue.
+//! When that gap is too small, the cue is split at a word boundary and the
+//! rest is shown in time taken from the next cue, as far as that cue can
+//! spare it.
+//!
+//! Widths are measured in half-width columns: a CJK character counts as two,
+//! so the same limits work for Chinese, Japanese, Korean and Latin text.
+
+use crate::text::{display_width, is_cjk};
+use crate::TranscriptionSegment;
+use std::ops::Range;
+
+/// Limits applied by [`resegment`].
+#[derive(Debug, Clone)]
+pub struct ResegmentParams {
+    /// Maximum line width in half-width columns (16 CJK characters at 32)
+    pub max_chars_per_line: usize,
+    /// Maximum number of lines per cue
+    pub max_lines: usize,
+    /// Maximum cue duration in seconds
+    pub max_duration: f32,
+    /// Maximum reading speed in half-width columns per second. Cues that
+    /// would be read faster are extended into the following gap, or split
+    /// when the gap is too small.
+    pub max_chars_per_second: f32,
+}
+
+impl Default for ResegmentParams {
+    fn default() -> Self {
+        Self {
+            max_chars_per_line: 32,
+            max_lines: 2,
+            max_duration: 7.0,
+            max_chars_per_second: 18.0,
+        }
+    }
+}
+
+/// An unbreakable piece of text: a Latin word or a single CJK character,
+/// with any punctuation attached.
+#[derive(Debug)]
+struct Token {
+    text: String,
+    width: usize,
+    space_before: bool,
+}
+
+fn is_closing_punct(c: char) -> bool {
+    "，。、；：！？」』）》〉】,.;:!?)]}…%\u{201D}\u{2019}".contains(c)
+}
+
+fn is_opening_punct(c: char) -> bool {
+    "「『（《〈【([{\u{201C}\u{2018}".contains(c)
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut word = String::new();
+    let mut word_space = false;
+    let mut pending_space = false;
+
+    let push = |tokens: &mut Vec<Token>, text: String, space_before: bool| {
+        // Never start a token with closing punctuation or end one with an
+        // opening bracket, so neither can be separated from its text
+        let attach = !space_before
+            && tokens.last().is_some_and(|last| {
+                text.starts_with(is_closing_punct) || last.text.ends_with(is_opening_punct)
+            });
+        match tokens.last_mut() {
+            Some(last) if attach => {
+                last.width += display_width(&text);
+                last.text.push_str(&text);
+            }
+            _ => tokens.push(Token {
+                width: display_width(&text),
+                text,
+                space_before,
+            }),
+        }
+    };
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !word.is_empty() {
+                push(&mut tokens, std::mem::take(&mut word), word_space);
+            }
+            pending_space = !tokens.is_empty();
+        } else if is_cjk(c) {
+            if !word.is_empty() {
+                push(&mut tokens, std::mem::take(&mut word), word_space);
+            }
+            push(&mut tokens, c.to_string(), pending_space);
+            pending_space = false;
+        } else {
+            if word.is_empty() {
+                word_space = pending_space;
+                pending_space = false;
+            }
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        push(&mut tokens, word, word_space);
+    }
+
+    tokens
+}
+
+/// How good a place the boundary after `token` is to split: sentence ends
+/// beat clause punctuation, which beats a plain word boundary.
+fn break_priority(token: &Token) -> u32 {
+    match token.text.chars().last() {
+        Some('。' | '！' | '？' | '.' | '!' | '?' | '…') => 3,
+        Some('，' | '、' | '；' | '：' | ',' | ';' | ':') => 2,
+        _ => 1,
+    }
+}
+
+fn span_width(tokens: &[Token]) -> usize {
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(i, t)| t.width + usize::from(i > 0 && t.space_before))
+        .sum()
+}
+
+fn join(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && token.space_before {
+            text.push(' ');
+        }
+        text.push_str(&token.text);
+    }
+    text
+}
+
+/// Lay `tokens` out in lines, or `None` if they need more than `max_lines`.
+fn wrap(tokens: &[Token], max_width: usize, max_lines: usize) -> Option<Vec<String>> {
+    if span_width(tokens) <= max_width {
+        return Some(vec![join(tokens)]);
+    }
+    if max_lines < 2 {
+        return None;
+    }
+
+    // Two balanced lines read better than a full line and a short one
+    let best_pair = (1..tokens.len())
+        .filter_map(|k| {
+            let longest = span_width(&tokens[..k]).max(span_width(&tokens[k..]));
+            let cost = longest as i64 - 3 * (break_priority(&tokens[k - 1]) as i64 - 1);
+            (longest <= max_width).then_some((k, cost))
+        })
+        .min_by_key(|&(_, cost)| cost);
+    if let Some((k, _)) = best_pair {
+        return Some(vec![join(&tokens[..k]), join(&tokens[k..])]);
+    }
+
+    // Greedy fill; a single over-long token gets a line of its own
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for end in 1..=tokens.len() {
+        if end - start > 1 && span_width(&tokens[start..end]) > max_width {
+            lines.push(join(&tokens[start..end - 1]));
+            start = end - 1;
+        }
+    }
+    lines.push(join(&tokens[start..]));
+    (lines.len() <= max_lines).then_some(lines)
+}
+
+/// Recursively split `range` until every piece fits on screen and within
+/// the maximum duration.
+fn split_cues(
+    tokens: &[Token],
+    range: Range<usize>,
+    seconds_per_column: f32,
+    params: &ResegmentParams,
+    cues: &mut Vec<Range<usize>>,
+) {
+    let width = span_width(&tokens[range.clone()]);
+    let fits = wrap(
+        &tokens[range.clone()],
+        params.max_chars_per_line,
+        params.max_lines,
+    )
+    .is_some()
+        && width as f32 * seconds_per_column <= params.max_duration;
+    if fits || range.len() < 2 {
+        cues.push(range);
+        return;
+    }
+
+    let split = (range.start + 1..range.end)
+        .max_by(|&a, &b| {
+            let score = |k: usize| {
+                let left = span_width(&tokens[range.start..k]) as f32;
+                let imbalance = (left / width as f32 - 0.5).abs();
+                break_priority(&tokens[k - 1]) as f32 - 4.0 * imbalance
+            };
+            score(a).total_cmp(&score(b)).then(b.cmp(&a))
+        })
+        .expect("range has at least two tokens");
+
+    split_cues(tokens, range.start..split, seconds_per_column, params, cues);
+    split_cues(tokens, split..range.end, seconds_per_column, params, cues);
+}
+
+fn resegment_one(
+    segment: &TranscriptionSegment,
+    params: &ResegmentParams,
+) -> Vec<TranscriptionSegment> {
+    let tokens = tokenize(&segment.text);
+    let total_width: usize = tokens.iter().map(|t| t.width).sum();
+    if total_width == 0 {
+        return Vec::new();
+    }
+
+    let duration = (segment.end - segment.start).max(0.0);
+    let seconds_per_column = duration / total_width as f32;
+
+    let mut cues = Vec::new();
+    split_cues(
+        &tokens,
+        0..tokens.len(),
+        seconds_per_column,
+        params,
+        &mut cues,
+    );
+
+    let mut offset = 0;
+    let cue_count = cues.len();
+    cues.into_iter()
+        .enumerate()
+        .map(|(i, range)| {
+            let cue_tokens = &tokens[range];
+            let cue_width: usize = cue_tokens.iter().map(|t| t.width).sum();
+            let start = segment.start + offset as f32 * seconds_per_column;
+            offset += cue_width;
+            let end = segment.start + offset as f32 * seconds_per_column;
+
+            // Each word goes to the cue containing its midpoint; the first
+            // and last cue take words outside the segment's time span
+            let words = segment.words.as_ref().map(|words| {
+                words
+                    .iter()
+                    .filter(|w| {
+                        let mid = (w.start + w.end) / 2.0;
+                        (i == 0 || mid >= start) && (i + 1 == cue_count || mid < end)
+                    })
+                    .cloned()
+                    .collect()
+            });
+
+            // Wrapped once reading speed is enforced
+            TranscriptionSegment {
+                start,
+                end,
+                text: join(cue_tokens),
+                words,
+                confidence: segment.confidence,
+                no_speech_prob: segment.no_speech_prob,
+                language: segment.language.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Split segments into subtitle cues that respect `params`.
+///
+/// Segments that already fit are kept as they are (apart from line
+/// wrapping); empty segments are dropped.
+///
+/// ```rust
+/// use transcribe_rs::subtitle::{resegment, ResegmentParams};
+/// use transcribe_rs::TranscriptionSegment;
+///
+/// let long = TranscriptionSegment {
+///     start: 0.0,
+///     end: 20.0,
+///     text: "今天我們要討論下一季的預算，還有人力配置。請各部門主管先報告目前的進度，然後我們再決定優先順序。".to_string(),
+///     ..Default::default()
+/// };
+///
+/// let cues = resegment(&[long], &ResegmentParams::default());
+/// assert!(cues.len() > 1);
+/// assert!(cues.iter().all(|c| c.end - c.start <= 7.0));
+/// ```
+pub fn resegment(
+    segments: &[TranscriptionSegment],
+    params: &ResegmentParams,
+) -> Vec<TranscriptionSegment> {
+    let mut cues: Vec<TranscriptionSegment> = segments
+        .iter()
+        .flat_map(|segment| resegment_one(segment, params))
+        .collect();
+
+    // Splitting inserts the rest of a cue right after it, to be checked next
+    let mut i = 0;
+    while i < cues.len() {
+        slow_down(&mut cues, i, params);
+        i += 1;
+    }
+
+    for cue in &mut cues {
+        let tokens = tokenize(&cue.text);
+        if let Some(lines) = wrap(&tokens, params.max_chars_per_line, params.max_lines) {
+            cue.text = lines.join("\n");
+        }
+    }
+    cues
+}
+
+/// Seconds needed to read `text` at the maximum reading speed.
+fn reading_time(text: &str, params: &ResegmentParams) -> f32 {
+    display_width(text) as f32 / params.max_chars_per_second
+}
+
+/// Give cue `i` the time its text needs to be read, up to the next cue.
+/// When the gap is too small, split the cue at a word boundary so the first
+/// part can be read in time, and move the next cue's start back to show the
+/// rest, without making the next cue too fast itself.
+fn slow_down(cues: &mut Vec<TranscriptionSegment>, i: usize, params: &ResegmentParams) {
+    // Tolerance for rounding in the time arithmetic
+    const EPSILON: f32 = 1e-3;
+
+    let needed = reading_time(&cues[i].text, params);
+    let (start, end) = (cues[i].start, cues[i].end);
+    if end - start >= needed - EPSILON {
+        return;
+    }
+    let next_start = cues.get(i + 1).map_or(f32::INFINITY, |next| next.start);
+    let available = next_start.min(start + params.max_duration);
+    cues[i].end = (start + needed).min(available).max(end);
+    if cues[i].end - start >= needed - EPSILON {
+        return;
+    }
+
+    let Some(next) = cues.get(i + 1) else {
+        return;
+    };
+    let spare = next.end - next.start - reading_time(&next.text, params);
+    let tokens = tokenize(&cues[i].text);
+    if spare <= EPSILON || tokens.len() < 2 {
+        return;
+    }
+
+    // The best break among the parts that can be read in the time there is
+    let time = cues[i].end - start;
+    let split = (1..tokens.len())
+        .filter(|&k| {
+            span_width(&tokens[..k]) as f32 / params.max_chars_per_second <= time + EPSILON
+        })
+        .max_by_key(|&k| (break_priority(&tokens[k - 1]), k))
+        .unwrap_or(1);
+    let head = join(&tokens[..split]);
+    let tail = join(&tokens[split..]);
+    let head_end = (start + reading_time(&head, params)).min(cues[i].end);
+    let tail_end = (head_end + reading_time(&tail, params))
+        .min(next.start + spare)
+        .min(head_end + params.max_duration)
+        .max(cues[i].end);
+
+    let cue = &mut cues[i];
+    let tail_words = cue.words.as_mut().map(|words| {
+        let at = words.partition_point(|w| (w.start + w.end) / 2.0 < head_end);
+        words.split_off(at)
+    });
+    let tail_cue = TranscriptionSegment {
+        start: head_end,
+        end: tail_end,
+        text: tail,
+        words: tail_words,
+        ..cue.clone()
+    };
+    cue.end = head_end;
+    cue.text = head;
+    cues[i + 1].start = tail_end;
+    cues.insert(i + 1, tail_cue);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f32, end: f32, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            start,
+            end,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tokenize_keeps_punctuation_attached() {
+        let tokens: Vec<String> = tokenize("「你好」， world! (ok)")
+            .into_iter()
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(tokens, vec!["「你", "好」，", "world!", "(ok)"]);
+    }
+
+    #[test]
+    fn test_short_segment_is_unchanged() {
+        let segments = vec![seg(1.0, 3.0, " Hello there. ")];
+        assert_eq!(
+            resegment(&segments, &ResegmentParams::default()),
+            vec![seg(1.0, 3.0, "Hello there.")]
+        );
+    }
+
+    #[test]
+    fn test_splits_at_sentence_end_and_redistributes_time() {
+        let params = ResegmentParams {
+            max_chars_per_line: 24,
+            max_lines: 1,
+            ..Default::default()
+        };
+        let segments = vec![seg(10.0, 14.0, "大家好，今天天氣很好。我們開始開會吧")];
+
+        let cues = resegment(&segments, &params);
+        let texts: Vec<&str> = cues.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["大家好，今天天氣很好。", "我們開始開會吧"]);
+        assert_eq!(cues[0].start, 10.0);
+        assert!((cues[0].end - 12.44).abs() < 0.01);
+        assert_eq!(cues[1].end, 14.0);
+    }
+
+    #[test]
+    fn test_wraps_into_balanced_lines() {
+        let params = ResegmentParams {
+            max_chars_per_line: 24,
+            ..Default::default()
+        };
+        let segments = vec![seg(0.0, 5.0, "the quick brown fox jumps over the lazy dog")];
+
+        let cues = resegment(&segments, &params);
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].text, "the quick brown fox\njumps over the lazy dog");
+    }
+
+    #[test]
+    fn test_enforces_max_duration() {
+        let words = ["word"; 12].join(" ");
+        let cues = resegment(&[seg(0.0, 30.0, &words)], &ResegmentParams::default());
+        assert!(cues.len() >= 5);
+        assert!(cues.iter().all(|c| c.end - c.start <= 7.0));
+        assert_eq!(cues.last().unwrap().end, 30.0);
+    }
+
+    #[test]
+    fn test_fast_cue_without_gap_is_split() {
+        let params = ResegmentParams::default();
+        let segments = vec![
+            seg(0.0, 1.0, "alpha beta gamma delta epsilon zeta"),
+            seg(1.0, 5.0, "next"),
+        ];
+        let cues = resegment(&segments, &params);
+
+        let texts: Vec<&str> = cues.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["alpha beta gamma", "delta epsilon zeta", "next"]
+        );
+        for (cue, next) in cues.iter().zip(&cues[1..]) {
+            assert!(
+                cue.end <= next.start + 1e-6,
+                "{:?} overlaps {:?}",
+                cue,
+                next
+            );
+        }
+        for cue in &cues {
+            let speed = display_width(&cue.text) as f32 / (cue.end - cue.start);
+            assert!(
+                speed <= params.max_chars_per_second + 0.01,
+                "{:?} is too fast",
+                cue
+            );
+        }
+        assert_eq!(cues[0].start, 0.0);
+        assert_eq!(cues[2].end, 5.0);
+    }
+
+    #[test]
+    fn test_fast_cue_is_extended_into_gap() {
+        let segments = vec![
+            seg(0.0, 0.5, "這是一句很長很長的話"),
+            seg(3.0, 4.0, "下一句"),
+        ];
+        let cues = resegment(&segments, &ResegmentParams::default());
+        // 20 columns at 18 columns per second
+        assert!((cues[0].end - 20.0 / 18.0).abs() < 0.001);
+        assert_eq!(cues[1].start, 3.0);
+    }
+}
@@ -0,0 +1,38 @@
+//! Text helpers shared by the subtitle and transcript modules.
+
+/// Whether `c` is a CJK character (ideographs, kana, hangul, CJK punctuation
+/// or full-width forms), i.e. written without spaces between words.
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{2E80}'..='\u{9FFF}'   // radicals, punctuation, kana, ideographs
+        | '\u{AC00}'..='\u{D7AF}' // hangul syllables
+        | '\u{F900}'..='\u{FAFF}' // compatibility ideographs
+        | '\u{FE30}'..='\u{FE4F}' // compatibility forms
+        | '\u{FF00}'..='\u{FF60}' // full-width forms
+        | '\u{FFE0}'..='\u{FFE6}' // full-width signs
+        | '\u{20000}'..='\u{3FFFD}' // supplementary ideographs
+    )
+}
+
+/// Display width of `c` in half-width columns: CJK characters take two.
+pub fn char_width(c: char) -> usize {
+    if is_cjk(c) {
+        2
+    } else if c.is_control() || ('\u{0300}'..='\u{036F}').contains(&c) || c == '\u{200B}' {
+        0
+    } else {
+        1
+    }
+}
+
+/// Display width of `text` in half-width columns.
+///
+/// ```rust
+/// use transcribe_rs::text::display_width;
+///
+/// assert_eq!(display_width("Hi"), 2);
+/// assert_eq!(display_width("你好"), 4);
+/// ```
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
@@ -0,0 +1,340 @@
+//! Versioned JSON transcript format.
+//!
+//! Subtitle formats only carry timing and text. [`Transcript`] additionally
+//! records which engine and model produced the result, the spoken language,
+//! the audio duration and, when the engine provides them, word timings and
+//! confidences. The layout is versioned through `schema_version` so consumers
+//! can detect documents written by a newer release.
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "engine": "whisper",
+//!   "model": "models/whisper-medium-q4_1.bin",
+//!   "language": "zh",
+//!   "duration": 12.5,
+//!   "text": "大家好",
+//!   "segments": [
+//!     { "id": 0, "start": 0.0, "end": 1.8, "text": "大家好", "confidence": 0.93 }
+//!   ]
+//! }
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! use transcribe_rs::transcript::{Transcript, TranscriptMetadata};
+//! use transcribe_rs::TranscriptionSegment;
+//!
+//! let segments = vec![TranscriptionSegment {
+//!     start: 0.0,
+//!     end: 1.8,
+//!     text: "Hello there".to_string(),
+//!     ..Default::default()
+//! }];
+//! let metadata = TranscriptMetadata {
+//!     engine: Some("whisper".to_string()),
+//!     duration: Some(2.0),
+//!     ..Default::default()
+//! };
+//!
+//! let json = Transcript::from_segments(&segments, metadata).to_json()?;
+//! let parsed = Transcript::from_json(&json)?;
+//! assert_eq!(parsed.to_segments(), segments);
+//! # Ok::<(), transcribe_rs::transcript::TranscriptError>(())
+//! ```
+
+use crate::text::is_cjk;
+use crate::{TranscriptionResult, TranscriptionSegment, TranscriptionWord};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Version written by this release; documents with a higher version are rejected.
+pub const TRANSCRIPT_SCHEMA_VERSION: u32 = 1;
+
+/// File extension for transcript documents.
+pub const TRANSCRIPT_EXTENSION: &str = "json";
+
+/// HTTP content type for transcript documents.
+pub const TRANSCRIPT_CONTENT_TYPE: &str = "application/json; charset=utf-8";
+
+#[derive(Error, Debug)]
+pub enum TranscriptError {
+    #[error("Invalid transcript JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unsupported transcript schema version {found} (newest supported is {supported})")]
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+/// Information about how a transcript was produced.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptMetadata {
+    /// Engine name, e.g. `"whisper"` or `"parakeet"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// Path or identifier of the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Spoken language as an ISO 639-1 code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Duration of the source audio in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f32>,
+}
+
+/// A complete transcript document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub metadata: TranscriptMetadata,
+    /// Full transcribed text
+    pub text: String,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+/// One timed segment of a [`Transcript`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    /// Position of the segment in the transcript, starting at 0
+    pub id: usize,
+    /// Start time in seconds
+    pub start: f32,
+    /// End time in seconds
+    pub end: f32,
+    pub text: String,
+    /// Average confidence of the segment in `[0.0, 1.0]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// Probability that the segment contains no speech
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_speech_prob: Option<f32>,
+    /// Spoken language of the segment as an ISO 639-1 code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Word-level timings, when the engine produced them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<TranscriptWord>,
+}
+
+/// A single timed word of a [`TranscriptSegment`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptWord {
+    pub start: f32,
+    pub end: f32,
+    pub word: String,
+    /// Confidence of the word in `[0.0, 1.0]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+}
+
+impl From<&TranscriptionSegment> for TranscriptSegment {
+    fn from(segment: &TranscriptionSegment) -> Self {
+        Self {
+            id: 0,
+            start: segment.start,
+            end: segment.end,
+            text: segment.text.clone(),
+            confidence: segment.confidence,
+            no_speech_prob: segment.no_speech_prob,
+            language: segment.language.clone(),
+            words: segment
+                .words
+                .iter()
+                .flatten()
+                .map(|word| TranscriptWord {
+                    start: word.start,
+                    end: word.end,
+                    word: word.text.clone(),
+                    confidence: word.confidence,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&TranscriptSegment> for TranscriptionSegment {
+    fn from(segment: &TranscriptSegment) -> Self {
+        Self {
+            start: segment.start,
+            end: segment.end,
+            text: segment.text.clone(),
+            words: (!segment.words.is_empty()).then(|| {
+                segment
+                    .words
+                    .iter()
+                    .map(|word| TranscriptionWord {
+                        start: word.start,
+                        end: word.end,
+                        text: word.word.clone(),
+                        confidence: word.confidence,
+                    })
+                    .collect()
+            }),
+            confidence: segment.confidence,
+            no_speech_prob: segment.no_speech_prob,
+            language: segment.language.clone(),
+        }
+    }
+}
+
+impl Transcript {
+    /// Build a transcript from an engine result. The result's language is
+    /// used when `metadata` has none.
+    pub fn from_result(result: &TranscriptionResult, metadata: TranscriptMetadata) -> Self {
+        let segments = result.segments.as_deref().unwrap_or_default();
+        let metadata = TranscriptMetadata {
+            language: metadata.language.or_else(|| result.language.clone()),
+            ..metadata
+        };
+        Self {
+            text: result.text.clone(),
+            ..Self::from_segments(segments, metadata)
+        }
+    }
+
+    /// Build a transcript from segments, joining their text for the full text.
+    pub fn from_segments(segments: &[TranscriptionSegment], metadata: TranscriptMetadata) -> Self {
+        let segments: Vec<TranscriptSegment> = segments
+            .iter()
+            .enumerate()
+            .map(|(id, segment)| TranscriptSegment {
+                id,
+                ..segment.into()
+            })
+            .collect();
+
+        Self {
+            schema_version: TRANSCRIPT_SCHEMA_VERSION,
+            metadata,
+            text: join_segment_text(segments.iter().map(|s| s.text.as_str())),
+            segments,
+        }
+    }
+
+    /// Plain segments, e.g. for rendering subtitles.
+    pub fn to_segments(&self) -> Vec<TranscriptionSegment> {
+        self.segments.iter().map(Into::into).collect()
+    }
+
+    /// Convert back into an engine result.
+    pub fn into_result(self) -> TranscriptionResult {
+        TranscriptionResult {
+            segments: Some(self.to_segments()),
+            text: self.text,
+            language: self.metadata.language,
+        }
+    }
+
+    /// Serialize as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, TranscriptError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a transcript, rejecting documents from a newer schema version.
+    pub fn from_json(json: &str) -> Result<Self, TranscriptError> {
+        let transcript: Self = serde_json::from_str(json)?;
+        if transcript.schema_version > TRANSCRIPT_SCHEMA_VERSION {
+            return Err(TranscriptError::UnsupportedVersion {
+                found: transcript.schema_version,
+                supported: TRANSCRIPT_SCHEMA_VERSION,
+            });
+        }
+        Ok(transcript)
+    }
+}
+
+/// Join segment texts, separating them with a space except next to CJK
+/// characters, which are written without spaces.
+fn join_segment_text<'a>(texts: impl Iterator<Item = &'a str>) -> String {
+    let mut joined = String::new();
+    for text in texts.map(str::trim).filter(|t| !t.is_empty()) {
+        let needs_space = match (joined.chars().last(), text.chars().next()) {
+            (Some(prev), Some(next)) => !is_cjk(prev) && !is_cjk(next),
+            _ => false,
+        };
+        if needs_space {
+            joined.push(' ');
+        }
+        joined.push_str(text);
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f32, end: f32, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            start,
+            end,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let metadata = TranscriptMetadata {
+            engine: Some("whisper".to_string()),
+            model: Some("models/whisper-medium-q4_1.bin".to_string()),
+            language: Some("zh".to_string()),
+            duration: Some(6.0),
+        };
+        let transcript = Transcript::from_segments(
+            &[seg(0.0, 2.0, " 大家好"), seg(2.0, 4.0, "我是主持人"), seg(4.0, 6.0, "OK then")],
+            metadata,
+        );
+        assert_eq!(transcript.text, "大家好我是主持人OK then");
+        assert_eq!(transcript.segments[2].id, 2);
+
+        let json = transcript.to_json().unwrap();
+        assert!(json.contains("\"schema_version\": 1"));
+        assert!(!json.contains("confidence"));
+        assert_eq!(Transcript::from_json(&json).unwrap(), transcript);
+    }
+
+    #[test]
+    fn test_parse_optional_fields() {
+        let json = r#"{
+            "schema_version": 1,
+            "text": "hi",
+            "segments": [{
+                "id": 0, "start": 0.0, "end": 1.0, "text": "hi", "confidence": 0.9,
+                "words": [{ "start": 0.1, "end": 0.5, "word": "hi", "confidence": 0.95 }]
+            }]
+        }"#;
+
+        let transcript = Transcript::from_json(json).unwrap();
+        assert_eq!(transcript.metadata, TranscriptMetadata::default());
+        assert_eq!(transcript.segments[0].confidence, Some(0.9));
+        assert_eq!(transcript.segments[0].words[0].word, "hi");
+
+        let result = transcript.into_result();
+        let words = vec![TranscriptionWord {
+            start: 0.1,
+            end: 0.5,
+            text: "hi".to_string(),
+            confidence: Some(0.95),
+        }];
+        assert_eq!(
+            result.segments.unwrap(),
+            vec![TranscriptionSegment {
+                words: Some(words),
+                confidence: Some(0.9),
+                ..seg(0.0, 1.0, "hi")
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rejects_newer_schema() {
+        let json = r#"{ "schema_version": 99, "text": "", "segments": [] }"#;
+        assert!(matches!(
+            Transcript::from_json(json),
+            Err(TranscriptError::UnsupportedVersion { found: 99, .. })
+        ));
+    }
+}
@@ -0,0 +1,270 @@
+//! Chunk planning over detected speech regions.
+//!
+//! Chunks cover the whole recording and are cut in the middle of silence
+//! gaps, so speech the detector missed is still transcribed. Only when a
+//! single speech region is longer than the maximum chunk length is it cut
+//! inside speech, and those forced cuts overlap so [`crate::merge`] can
+//! stitch the text back together.
+
+use super::SpeechRegion;
+
+/// Bounds for planned chunks.
+#[derive(Debug, Clone)]
+pub struct ChunkPlanParams {
+    /// Chunks are not cut before reaching this length (seconds) unless the
+    /// audio runs out
+    pub min_chunk_duration: f32,
+    /// Hard upper bound on chunk length (seconds)
+    pub max_chunk_duration: f32,
+    /// Overlap (seconds) between pieces of a speech region that had to be
+    /// cut because it exceeds `max_chunk_duration`
+    pub forced_overlap: f32,
+}
+
+impl Default for ChunkPlanParams {
+    fn default() -> Self {
+        Self {
+            min_chunk_duration: 60.0,
+            max_chunk_duration: 300.0,
+            forced_overlap: 10.0,
+        }
+    }
+}
+
+/// A planned chunk of audio to transcribe, in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedChunk {
+    /// Index of the chunk in playback order
+    pub index: usize,
+    /// Start of the audio to transcribe
+    pub start: f32,
+    /// End of the audio to transcribe
+    pub end: f32,
+}
+
+/// Plan contiguous chunks covering `0..total_duration` that cut only in
+/// silence gaps.
+///
+/// Within the window `[min_chunk_duration, max_chunk_duration]` after a
+/// chunk's start, the widest silence gap is chosen and the cut is placed at
+/// its midpoint. Gaps too long to share out without exceeding
+/// `max_chunk_duration` get chunks of their own; text decoded over true
+/// silence is left to the hallucination filter.
+pub fn plan_chunks(
+    regions: &[SpeechRegion],
+    total_duration: f32,
+    params: &ChunkPlanParams,
+) -> Vec<PlannedChunk> {
+    let max_len = params.max_chunk_duration.max(1.0);
+    let overlap = params.forced_overlap.clamp(0.0, max_len / 2.0);
+
+    // Split regions that cannot fit in one chunk into overlapping pieces.
+    // The bool marks pieces that continue into the next one.
+    let mut pieces: Vec<(SpeechRegion, bool)> = Vec::new();
+    for region in regions {
+        let region = SpeechRegion {
+            start: region.start.max(0.0),
+            end: region.end.min(total_duration),
+        };
+        if region.duration() <= 0.0 {
+            continue;
+        }
+
+        let mut start = region.start;
+        while region.end - start > max_len {
+            pieces.push((
+                SpeechRegion {
+                    start,
+                    end: start + max_len,
+                },
+                true,
+            ));
+            start += max_len - overlap;
+        }
+        pieces.push((
+            SpeechRegion {
+                start,
+                end: region.end,
+            },
+            false,
+        ));
+    }
+
+    let mut chunks: Vec<PlannedChunk> = Vec::new();
+    let mut i = 0;
+    while i < pieces.len() {
+        let chunk_start = pieces[i].0.start;
+
+        // Furthest piece that still fits in the chunk
+        let mut last = i;
+        while last + 1 < pieces.len() && pieces[last + 1].0.end - chunk_start <= max_len {
+            last += 1;
+        }
+
+        let cut = if last + 1 == pieces.len() || pieces[last].1 {
+            last
+        } else {
+            // Widest silence gap that keeps the chunk above the minimum length;
+            // ties go to the later gap so chunks stay as long as possible
+            (i..=last)
+                .filter(|&k| !pieces[k].1)
+                .filter(|&k| pieces[k].0.end - chunk_start >= params.min_chunk_duration)
+                .max_by(|&a, &b| {
+                    let gap_a = pieces[a + 1].0.start - pieces[a].0.end;
+                    let gap_b = pieces[b + 1].0.start - pieces[b].0.end;
+                    gap_a
+                        .partial_cmp(&gap_b)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(last)
+        };
+
+        chunks.push(PlannedChunk {
+            index: chunks.len(),
+            start: chunk_start,
+            end: pieces[cut].0.end,
+        });
+        i = cut + 1;
+    }
+
+    cover(&chunks, total_duration, max_len)
+}
+
+/// Stretch speech chunks to the middle of the gaps around them and fill
+/// whatever is still uncovered, so every second of audio is in a chunk
+fn cover(speech: &[PlannedChunk], total_duration: f32, max_len: f32) -> Vec<PlannedChunk> {
+    let mut bounds: Vec<(f32, f32)> = Vec::new();
+    let mut edge = 0.0f32;
+    for (k, chunk) in speech.iter().enumerate() {
+        // Forced cuts already overlap their neighbour and stay as they are
+        let want_start = match k.checked_sub(1).map(|prev| &speech[prev]) {
+            None => 0.0,
+            Some(prev) if prev.end > chunk.start => chunk.start,
+            Some(prev) => (prev.end + chunk.start) / 2.0,
+        };
+        let want_end = match speech.get(k + 1) {
+            None => total_duration,
+            Some(next) if chunk.end > next.start => chunk.end,
+            Some(next) => (chunk.end + next.start) / 2.0,
+        };
+
+        // Share the room left under the maximum between both sides; room
+        // one side does not need goes to the other
+        let spare = (max_len - (chunk.end - chunk.start)).max(0.0);
+        let want_left = chunk.start - want_start;
+        let want_right = want_end - chunk.end;
+        let left = want_left.min(spare - want_right.min(spare / 2.0));
+        let right = want_right.min(spare - left);
+        fill(&mut bounds, edge, chunk.start - left, max_len);
+        bounds.push((chunk.start - left, chunk.end + right));
+        edge = chunk.end + right;
+    }
+    fill(&mut bounds, edge, total_duration, max_len);
+
+    bounds
+        .into_iter()
+        .enumerate()
+        .map(|(index, (start, end))| PlannedChunk { index, start, end })
+        .collect()
+}
+
+/// Push evenly sized chunks of at most `max_len` covering `start..end`
+fn fill(bounds: &mut Vec<(f32, f32)>, start: f32, end: f32, max_len: f32) {
+    let len = end - start;
+    if len <= 0.0 {
+        return;
+    }
+    let count = (len / max_len).ceil().max(1.0) as usize;
+    let step = len / count as f32;
+    for n in 0..count {
+        let piece_end = if n + 1 == count {
+            end
+        } else {
+            start + step * (n + 1) as f32
+        };
+        bounds.push((start + step * n as f32, piece_end));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(start: f32, end: f32) -> SpeechRegion {
+        SpeechRegion { start, end }
+    }
+
+    #[test]
+    fn test_short_audio_is_one_chunk() {
+        let regions = vec![region(1.0, 5.0), region(6.0, 20.0)];
+        let chunks = plan_chunks(&regions, 30.0, &ChunkPlanParams::default());
+        assert_eq!(chunks.len(), 1);
+        assert_eq!((chunks[0].start, chunks[0].end), (0.0, 30.0));
+    }
+
+    #[test]
+    fn test_cuts_at_widest_gap() {
+        let params = ChunkPlanParams {
+            min_chunk_duration: 10.0,
+            max_chunk_duration: 30.0,
+            forced_overlap: 2.0,
+        };
+        // Gaps after 12s (1s wide) and after 20s (4s wide); 28s end fits too
+        let regions = vec![
+            region(0.0, 12.0),
+            region(13.0, 20.0),
+            region(24.0, 28.0),
+            region(28.5, 40.0),
+        ];
+        let chunks = plan_chunks(&regions, 40.0, &params);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!((chunks[0].start, chunks[0].end), (0.0, 22.0));
+        assert_eq!((chunks[1].start, chunks[1].end), (22.0, 40.0));
+    }
+
+    #[test]
+    fn test_long_region_is_force_cut_with_overlap() {
+        let params = ChunkPlanParams {
+            min_chunk_duration: 10.0,
+            max_chunk_duration: 30.0,
+            forced_overlap: 4.0,
+        };
+        let chunks = plan_chunks(&[region(0.0, 50.0)], 50.0, &params);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!((chunks[0].start, chunks[0].end), (0.0, 30.0));
+        assert_eq!((chunks[1].start, chunks[1].end), (26.0, 50.0));
+    }
+
+    #[test]
+    fn test_long_silence_gets_its_own_chunks() {
+        let params = ChunkPlanParams {
+            min_chunk_duration: 10.0,
+            max_chunk_duration: 30.0,
+            forced_overlap: 2.0,
+        };
+        // 100s of silence between two regions: each region takes what fits,
+        // the rest is covered in pieces of at most 30s
+        let chunks = plan_chunks(&[region(0.0, 20.0), region(120.0, 130.0)], 130.0, &params);
+        let bounds: Vec<(f32, f32)> = chunks.iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(bounds[0], (0.0, 30.0));
+        assert_eq!(*bounds.last().unwrap(), (100.0, 130.0));
+        for pair in bounds.windows(2) {
+            assert_eq!(pair[0].1, pair[1].0, "chunks must be contiguous");
+        }
+        assert!(bounds.iter().all(|(start, end)| end - start <= 30.0));
+        assert!(chunks.iter().enumerate().all(|(i, c)| c.index == i));
+    }
+
+    #[test]
+    fn test_no_regions_still_covers_audio() {
+        let params = ChunkPlanParams {
+            min_chunk_duration: 10.0,
+            max_chunk_duration: 30.0,
+            forced_overlap: 2.0,
+        };
+        let chunks = plan_chunks(&[], 45.0, &params);
+        let bounds: Vec<(f32, f32)> = chunks.iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(bounds, vec![(0.0, 22.5), (22.5, 45.0)]);
+        assert!(plan_chunks(&[], 0.0, &params).is_empty());
+    }
+}
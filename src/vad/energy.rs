@@ -0,0 +1,120 @@
+//! Energy-based voice activity detection.
+//!
+//! Each 30 ms frame is compared against an adaptive threshold derived from
+//! the recording's own noise floor, so the detector works on both quiet
+//! lecture recordings and noisy meeting rooms without tuning.
+
+use super::{frames_to_regions, RegionParams, SpeechRegion, VadError, VoiceActivityDetector};
+use crate::audio::TARGET_SAMPLE_RATE;
+
+/// Parameters for the energy detector.
+#[derive(Debug, Clone)]
+pub struct EnergyVadParams {
+    /// Analysis frame length in milliseconds
+    pub frame_ms: u32,
+    /// Percentile (0.0-1.0) of frame energies taken as the noise floor
+    pub noise_percentile: f32,
+    /// How far above the noise floor (dB) a frame must be to count as speech
+    pub threshold_above_floor_db: f32,
+    /// Frames below this absolute level (dBFS) are never speech
+    pub min_threshold_db: f32,
+    /// Region smoothing shared with the other detectors
+    pub regions: RegionParams,
+}
+
+impl Default for EnergyVadParams {
+    fn default() -> Self {
+        Self {
+            frame_ms: 30,
+            noise_percentile: 0.1,
+            threshold_above_floor_db: 12.0,
+            min_threshold_db: -50.0,
+            regions: RegionParams::default(),
+        }
+    }
+}
+
+/// Voice activity detector based on frame energy.
+#[derive(Debug, Clone, Default)]
+pub struct EnergyVad {
+    params: EnergyVadParams,
+}
+
+impl EnergyVad {
+    pub fn new(params: EnergyVadParams) -> Self {
+        Self { params }
+    }
+}
+
+impl VoiceActivityDetector for EnergyVad {
+    fn detect(&mut self, samples: &[f32]) -> Result<Vec<SpeechRegion>, VadError> {
+        let frame_len = (TARGET_SAMPLE_RATE * self.params.frame_ms / 1000).max(1) as usize;
+        let total_duration = samples.len() as f32 / TARGET_SAMPLE_RATE as f32;
+
+        let energies: Vec<f32> = samples
+            .chunks(frame_len)
+            .map(|frame| {
+                let mean_square =
+                    frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+                10.0 * (mean_square + 1e-10).log10()
+            })
+            .collect();
+
+        if energies.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut sorted = energies.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let floor_idx = ((sorted.len() - 1) as f32 * self.params.noise_percentile) as usize;
+        let noise_floor = sorted[floor_idx];
+        let threshold =
+            (noise_floor + self.params.threshold_above_floor_db).max(self.params.min_threshold_db);
+
+        let is_speech: Vec<bool> = energies.iter().map(|&e| e > threshold).collect();
+        let frame_duration = frame_len as f32 / TARGET_SAMPLE_RATE as f32;
+
+        Ok(frames_to_regions(
+            &is_speech,
+            frame_duration,
+            total_duration,
+            &self.params.regions,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(seconds: f32) -> Vec<f32> {
+        (0..(seconds * TARGET_SAMPLE_RATE as f32) as usize)
+            .map(|i| (i as f32 * 0.05).sin() * 0.3)
+            .collect()
+    }
+
+    fn silence(seconds: f32) -> Vec<f32> {
+        vec![0.0; (seconds * TARGET_SAMPLE_RATE as f32) as usize]
+    }
+
+    #[test]
+    fn test_detects_tone_bursts() {
+        let mut samples = silence(1.0);
+        samples.extend(tone(2.0));
+        samples.extend(silence(2.0));
+        samples.extend(tone(1.0));
+        samples.extend(silence(1.0));
+
+        let regions = EnergyVad::default().detect(&samples).unwrap();
+        assert_eq!(regions.len(), 2);
+        assert!((regions[0].start - 0.8).abs() < 0.05);
+        assert!((regions[0].end - 3.2).abs() < 0.05);
+        assert!((regions[1].start - 4.8).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_silence_has_no_regions() {
+        let regions = EnergyVad::default().detect(&silence(3.0)).unwrap();
+        assert!(regions.is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+//! Language spans for code-switched recordings.
+//!
+//! Meetings that switch between languages transcribe poorly with a single
+//! Whisper `language` setting. [`plan_language_spans`] detects the language of
+//! each speech region and groups consecutive regions of the same language
+//! into spans, which are then transcribed with their own language setting.
+
+use super::SpeechRegion;
+
+/// Options for code-switching transcription.
+#[derive(Debug, Clone)]
+pub struct CodeSwitchingParams {
+    /// Languages (ISO 639-1) to choose between, e.g. `["zh", "en"]`.
+    /// Empty allows every language the model knows.
+    pub languages: Vec<String>,
+    /// Regions shorter than this (seconds) are too short to identify and
+    /// take the language of the preceding region
+    pub min_region_duration: f32,
+}
+
+impl Default for CodeSwitchingParams {
+    fn default() -> Self {
+        Self {
+            languages: vec!["zh".to_string(), "en".to_string()],
+            min_region_duration: 1.5,
+        }
+    }
+}
+
+/// A stretch of audio spoken in one language, in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageSpan {
+    pub start: f32,
+    pub end: f32,
+    /// Detected language, or `None` when no region could be identified
+    pub language: Option<String>,
+}
+
+/// Split `start..end` into spans of one language each.
+///
+/// `detect` is called for every region of at least
+/// `params.min_region_duration` and returns its language. Shorter or
+/// unidentified regions inherit the language of the preceding region (or
+/// the following one at the start). Spans cover `start..end` without gaps,
+/// switching halfway through the silence between regions.
+///
+/// ```rust
+/// use transcribe_rs::vad::{plan_language_spans, CodeSwitchingParams, SpeechRegion};
+///
+/// let regions = [
+///     SpeechRegion { start: 0.5, end: 4.0 },
+///     SpeechRegion { start: 5.0, end: 9.0 },
+/// ];
+/// let spans = plan_language_spans(&regions, 0.0, 10.0, &CodeSwitchingParams::default(), |r| {
+///     Some(if r.start < 1.0 { "zh" } else { "en" }.to_string())
+/// });
+/// assert_eq!(spans.len(), 2);
+/// assert_eq!(spans[1].start, 4.5);
+/// ```
+pub fn plan_language_spans(
+    regions: &[SpeechRegion],
+    start: f32,
+    end: f32,
+    params: &CodeSwitchingParams,
+    mut detect: impl FnMut(&SpeechRegion) -> Option<String>,
+) -> Vec<LanguageSpan> {
+    let mut languages: Vec<Option<String>> = regions
+        .iter()
+        .map(|region| {
+            if region.duration() >= params.min_region_duration {
+                detect(region)
+            } else {
+                None
+            }
+        })
+        .collect();
+    for i in 1..languages.len() {
+        if languages[i].is_none() {
+            languages[i] = languages[i - 1].clone();
+        }
+    }
+    for i in (0..languages.len().saturating_sub(1)).rev() {
+        if languages[i].is_none() {
+            languages[i] = languages[i + 1].clone();
+        }
+    }
+
+    let mut spans: Vec<LanguageSpan> = Vec::new();
+    for (region, language) in regions.iter().zip(languages) {
+        match spans.last_mut() {
+            Some(span) if span.language == language => span.end = region.end,
+            Some(span) => {
+                let cut = (span.end + region.start) / 2.0;
+                span.end = cut;
+                spans.push(LanguageSpan {
+                    start: cut,
+                    end: region.end,
+                    language,
+                });
+            }
+            None => spans.push(LanguageSpan {
+                start,
+                end: region.end,
+                language,
+            }),
+        }
+    }
+
+    match spans.last_mut() {
+        Some(last) => last.end = end,
+        None => spans.push(LanguageSpan {
+            start,
+            end,
+            language: None,
+        }),
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(start: f32, end: f32) -> SpeechRegion {
+        SpeechRegion { start, end }
+    }
+
+    #[test]
+    fn test_merges_runs_and_inherits_short_regions() {
+        let regions = vec![
+            region(0.0, 3.0),   // zh
+            region(3.5, 4.0),   // too short, inherits zh
+            region(5.0, 8.0),   // zh
+            region(10.0, 14.0), // en
+            region(14.2, 18.0), // undetected, inherits en
+        ];
+        let mut calls = 0;
+        let spans =
+            plan_language_spans(&regions, 0.0, 20.0, &CodeSwitchingParams::default(), |r| {
+                calls += 1;
+                match r.start {
+                    s if s < 9.0 => Some("zh".to_string()),
+                    s if s < 12.0 => Some("en".to_string()),
+                    _ => None,
+                }
+            });
+
+        assert_eq!(calls, 4);
+        assert_eq!(
+            spans,
+            vec![
+                LanguageSpan {
+                    start: 0.0,
+                    end: 9.0,
+                    language: Some("zh".to_string()),
+                },
+                LanguageSpan {
+                    start: 9.0,
+                    end: 20.0,
+                    language: Some("en".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_regions_is_one_span() {
+        let spans = plan_language_spans(&[], 5.0, 10.0, &CodeSwitchingParams::default(), |_| {
+            Some("en".to_string())
+        });
+        assert_eq!(
+            spans,
+            vec![LanguageSpan {
+                start: 5.0,
+                end: 10.0,
+                language: None,
+            }]
+        );
+    }
+}
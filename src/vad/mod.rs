@@ -0,0 +1,165 @@
+//! Voice activity detection and silence-aware chunk planning.
+//!
+//! Long recordings are transcribed in parallel chunks. Cutting those chunks
+//! at fixed offsets slices words in half, so this module first finds the
+//! regions that contain speech and then plans chunk boundaries that fall in
+//! the middle of the silence between them. The chunks still cover the whole
+//! recording, so speech the detector misses is not lost.
+//!
+//! # Detectors
+//!
+//! - [`EnergyVad`]: frame energy against an adaptive noise floor. No model
+//!   required, used by default.
+//! - [`SileroVad`]: the Silero VAD ONNX model, loaded through `ort`. More
+//!   robust against music and background noise.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use transcribe_rs::audio::load_audio_file;
+//! use transcribe_rs::vad::{plan_chunks, ChunkPlanParams, EnergyVad, VoiceActivityDetector};
+//! use std::path::Path;
+//!
+//! let samples = load_audio_file(Path::new("meeting.mp3"))?;
+//! let regions = EnergyVad::default().detect(&samples)?;
+//!
+//! let duration = samples.len() as f32 / 16000.0;
+//! for chunk in plan_chunks(&regions, duration, &ChunkPlanParams::default()) {
+//!     println!("[{:.2}s - {:.2}s]", chunk.start, chunk.end);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod chunking;
+pub mod energy;
+pub mod language;
+pub mod silero;
+
+pub use chunking::{plan_chunks, ChunkPlanParams, PlannedChunk};
+pub use energy::{EnergyVad, EnergyVadParams};
+pub use language::{plan_language_spans, CodeSwitchingParams, LanguageSpan};
+pub use silero::{SileroVad, SileroVadParams};
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A span of audio that contains speech, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeechRegion {
+    /// Start time of the region in seconds
+    pub start: f32,
+    /// End time of the region in seconds
+    pub end: f32,
+}
+
+impl SpeechRegion {
+    /// Length of the region in seconds.
+    pub fn duration(&self) -> f32 {
+        self.end - self.start
+    }
+}
+
+/// Post-processing applied to raw per-frame speech decisions.
+///
+/// Shared by all detectors so they produce comparable regions.
+#[derive(Debug, Clone)]
+pub struct RegionParams {
+    /// Speech runs shorter than this (seconds) are discarded as clicks/noise
+    pub min_speech_duration: f32,
+    /// Silences shorter than this (seconds) do not split a region
+    pub min_silence_duration: f32,
+    /// Padding (seconds) added around each region, never crossing a neighbour
+    pub speech_pad: f32,
+}
+
+impl Default for RegionParams {
+    fn default() -> Self {
+        Self {
+            min_speech_duration: 0.25,
+            min_silence_duration: 0.5,
+            speech_pad: 0.2,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum VadError {
+    #[error("ORT error")]
+    Ort(#[from] ort::Error),
+    #[error("ndarray shape error")]
+    Shape(#[from] ndarray::ShapeError),
+    #[error("Model output not found: {0}")]
+    OutputNotFound(String),
+}
+
+/// Common interface for voice activity detectors.
+pub trait VoiceActivityDetector {
+    /// Find the speech regions in 16 kHz mono samples.
+    fn detect(&mut self, samples: &[f32]) -> Result<Vec<SpeechRegion>, VadError>;
+}
+
+/// Detect speech with the Silero model if a path is given, otherwise with
+/// the default energy detector.
+pub fn detect_speech_regions(
+    samples: &[f32],
+    vad_model: Option<&Path>,
+) -> Result<Vec<SpeechRegion>, VadError> {
+    match vad_model {
+        Some(model_path) => SileroVad::new(model_path, SileroVadParams::default())?.detect(samples),
+        None => EnergyVad::default().detect(samples),
+    }
+}
+
+/// Turn per-frame speech flags into padded, merged speech regions.
+pub(crate) fn frames_to_regions(
+    is_speech: &[bool],
+    frame_duration: f32,
+    total_duration: f32,
+    params: &RegionParams,
+) -> Vec<SpeechRegion> {
+    // Step 1: Collect raw runs of speech frames
+    let mut raw = Vec::new();
+    let mut run_start: Option<usize> = None;
+    for (i, &speech) in is_speech.iter().enumerate() {
+        match (speech, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(start)) => {
+                raw.push((start, i));
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = run_start {
+        raw.push((start, is_speech.len()));
+    }
+
+    // Step 2: Merge runs separated by short silences
+    let mut merged: Vec<SpeechRegion> = Vec::new();
+    for (start, end) in raw {
+        let region = SpeechRegion {
+            start: start as f32 * frame_duration,
+            end: (end as f32 * frame_duration).min(total_duration),
+        };
+        match merged.last_mut() {
+            Some(last) if region.start - last.end < params.min_silence_duration => {
+                last.end = region.end;
+            }
+            _ => merged.push(region),
+        }
+    }
+
+    // Step 3: Drop blips and pad what remains without overlapping neighbours
+    merged.retain(|r| r.duration() >= params.min_speech_duration);
+    let bounds: Vec<(f32, f32)> = merged.iter().map(|r| (r.start, r.end)).collect();
+    for (i, region) in merged.iter_mut().enumerate() {
+        let prev_end = if i > 0 { bounds[i - 1].1 } else { 0.0 };
+        let next_start = bounds.get(i + 1).map_or(total_duration, |b| b.0);
+        let pad_before = params.speech_pad.min((region.start - prev_end) / 2.0);
+        let pad_after = params.speech_pad.min((next_start - region.end) / 2.0);
+        region.start = (region.start - pad_before.max(0.0)).max(0.0);
+        region.end = (region.end + pad_after.max(0.0)).min(total_duration);
+    }
+
+    merged
+}
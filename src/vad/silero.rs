@@ -0,0 +1,134 @@
+//! Silero VAD running on ONNX Runtime.
+//!
+//! Expects the Silero VAD v5 model (`silero_vad.onnx`), which consumes
+//! 512-sample windows at 16 kHz together with a recurrent state tensor.
+
+use std::path::Path;
+
+use ndarray::{arr0, Array2, Array3, ArrayD};
+use ort::execution_providers::CPUExecutionProvider;
+use ort::inputs;
+use ort::session::builder::GraphOptimizationLevel;
+use ort::session::Session;
+use ort::value::TensorRef;
+
+use super::{frames_to_regions, RegionParams, SpeechRegion, VadError, VoiceActivityDetector};
+use crate::audio::TARGET_SAMPLE_RATE;
+
+const WINDOW_SIZE: usize = 512;
+const CONTEXT_SIZE: usize = 64;
+const STATE_SIZE: usize = 128;
+
+/// Parameters for the Silero detector.
+#[derive(Debug, Clone)]
+pub struct SileroVadParams {
+    /// Speech probability above which a window starts counting as speech
+    pub threshold: f32,
+    /// Speech probability below which an ongoing speech run ends
+    pub neg_threshold: f32,
+    /// Region smoothing shared with the other detectors
+    pub regions: RegionParams,
+}
+
+impl Default for SileroVadParams {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            neg_threshold: 0.35,
+            regions: RegionParams::default(),
+        }
+    }
+}
+
+/// Voice activity detector backed by the Silero VAD ONNX model.
+pub struct SileroVad {
+    session: Session,
+    params: SileroVadParams,
+}
+
+impl SileroVad {
+    /// Load the Silero model from an `.onnx` file.
+    pub fn new<P: AsRef<Path>>(model_path: P, params: SileroVadParams) -> Result<Self, VadError> {
+        let session = Session::builder()?
+            .with_optimization_level(GraphOptimizationLevel::Level3)?
+            .with_execution_providers(vec![CPUExecutionProvider::default().build()])?
+            .with_intra_threads(1)?
+            .commit_from_file(model_path.as_ref())?;
+
+        Ok(Self { session, params })
+    }
+
+    /// Run the model over every window and return per-window speech probabilities.
+    pub fn speech_probabilities(&mut self, samples: &[f32]) -> Result<Vec<f32>, VadError> {
+        let mut state = ArrayD::<f32>::zeros(vec![2, 1, STATE_SIZE]);
+        let mut context = vec![0.0f32; CONTEXT_SIZE];
+        let sample_rate = arr0(TARGET_SAMPLE_RATE as i64);
+        let mut probabilities = Vec::with_capacity(samples.len() / WINDOW_SIZE + 1);
+
+        for window in samples.chunks(WINDOW_SIZE) {
+            // v5 models expect the tail of the previous window prepended to the input
+            let mut input = Vec::with_capacity(CONTEXT_SIZE + WINDOW_SIZE);
+            input.extend_from_slice(&context);
+            input.extend_from_slice(window);
+            input.resize(CONTEXT_SIZE + WINDOW_SIZE, 0.0);
+            context.copy_from_slice(&input[input.len() - CONTEXT_SIZE..]);
+
+            let input = Array2::from_shape_vec((1, CONTEXT_SIZE + WINDOW_SIZE), input)?;
+            let state_in: Array3<f32> = state.clone().into_dimensionality()?;
+
+            let outputs = self.session.run(inputs![
+                "input" => TensorRef::from_array_view(input.view())?,
+                "state" => TensorRef::from_array_view(state_in.view())?,
+                "sr" => TensorRef::from_array_view(sample_rate.view())?,
+            ])?;
+
+            let probability = outputs
+                .get("output")
+                .ok_or_else(|| VadError::OutputNotFound("output".to_string()))?
+                .try_extract_array::<f32>()?
+                .iter()
+                .copied()
+                .next()
+                .unwrap_or(0.0);
+            state = outputs
+                .get("stateN")
+                .ok_or_else(|| VadError::OutputNotFound("stateN".to_string()))?
+                .try_extract_array::<f32>()?
+                .to_owned();
+
+            probabilities.push(probability);
+        }
+
+        Ok(probabilities)
+    }
+}
+
+impl VoiceActivityDetector for SileroVad {
+    fn detect(&mut self, samples: &[f32]) -> Result<Vec<SpeechRegion>, VadError> {
+        let probabilities = self.speech_probabilities(samples)?;
+
+        // Hysteresis: enter speech above `threshold`, leave below `neg_threshold`
+        let mut in_speech = false;
+        let is_speech: Vec<bool> = probabilities
+            .iter()
+            .map(|&p| {
+                if p >= self.params.threshold {
+                    in_speech = true;
+                } else if p < self.params.neg_threshold {
+                    in_speech = false;
+                }
+                in_speech
+            })
+            .collect();
+
+        let frame_duration = WINDOW_SIZE as f32 / TARGET_SAMPLE_RATE as f32;
+        let total_duration = samples.len() as f32 / TARGET_SAMPLE_RATE as f32;
+
+        Ok(frames_to_regions(
+            &is_speech,
+            frame_duration,
+            total_duration,
+            &self.params.regions,
+        ))
+    }
+}
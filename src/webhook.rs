@@ -0,0 +1,671 @@
+//! Webhook callbacks for finished server tasks.
+//!
+//! An upload can carry a `callback_url`. When its task completes or fails,
+//! the server POSTs a JSON [`WebhookPayload`] there instead of making the
+//! client poll `/status/{id}`. Each request is signed with HMAC-SHA256 over
+//! `<timestamp>.<body>` using a shared secret, where the timestamp is the
+//! Unix time of the attempt in seconds. Both are sent in the
+//! [`SIGNATURE_HEADER`] as `t=<timestamp>,sha256=<hex>`, and receivers check
+//! it with [`verify`], which also rejects old requests so a captured one
+//! cannot be replayed.
+//!
+//! Failed deliveries (connection errors and non-2xx responses) are retried
+//! with exponential backoff, and every attempt is reported so it can be
+//! recorded on the task. Attempts are stored with the task, so deliveries
+//! interrupted by a restart can resume with the attempts that are left.
+//!
+//! Callbacks only go to public addresses: hosts that resolve to loopback,
+//! private or link-local addresses are refused unless listed in
+//! [`WebhookParams::allowed_hosts`]. Addresses are checked again whenever a
+//! request connects, and redirects are not followed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::{Duration, SystemTime, UNIX_EPOCH};
+//! use transcribe_rs::webhook::{sign, verify};
+//!
+//! let body = br#"{"event":"task.completed"}"#;
+//! let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+//! let signature = sign(b"secret", now, body);
+//! assert!(signature.starts_with(&format!("t={},sha256=", now)));
+//! let tolerance = Duration::from_secs(300);
+//! assert!(verify(b"secret", body, &signature, tolerance));
+//! assert!(!verify(b"other", body, &signature, tolerance));
+//! assert!(!verify(b"secret", body, &sign(b"secret", now - 3600, body), tolerance));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::transcript::Transcript;
+use crate::worker::{Task, TaskStatus};
+
+/// Header carrying the `t=<timestamp>,sha256=<hex>` signature of the body
+pub const SIGNATURE_HEADER: &str = "X-Transcribe-Signature";
+/// Header carrying the event name, e.g. `task.completed`
+pub const EVENT_HEADER: &str = "X-Transcribe-Event";
+
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("Invalid callback URL: {0}")]
+    InvalidUrl(String),
+    #[error("Failed to resolve {0}: {1}")]
+    Resolve(String, std::io::Error),
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Failed to serialize payload: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Delivery failed after {attempts} attempts: {last_error}")]
+    Exhausted { attempts: u32, last_error: String },
+}
+
+/// Parse `url` as an absolute http(s) URL with a host.
+fn parse_url(url: &str) -> Result<reqwest::Url, WebhookError> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| WebhookError::InvalidUrl(format!("{}: {}", url, e)))?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host_str().is_some() => Ok(parsed),
+        "http" | "https" => Err(WebhookError::InvalidUrl(format!("{}: missing host", url))),
+        scheme => Err(WebhookError::InvalidUrl(format!(
+            "{}: unsupported scheme {}",
+            url, scheme
+        ))),
+    }
+}
+
+/// Whether `ip` is reachable from the internet, as opposed to loopback,
+/// private, link-local and other special-purpose addresses.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // Shared address space of carrier-grade NAT (RFC 6598)
+                || (a == 100 && b & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Unique local (fc00::/7) and link-local (fe80::/10)
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Hosts exempt from the public address check.
+#[derive(Debug, Clone, Default)]
+struct AllowedHosts(Arc<Vec<String>>);
+
+impl AllowedHosts {
+    fn contains(&self, host: &str) -> bool {
+        self.0
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    /// Resolve `host`, keeping only the addresses callbacks may reach.
+    async fn resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, WebhookError> {
+        let target = (host.to_string(), port);
+        let addrs =
+            tokio::task::spawn_blocking(move || target.to_socket_addrs().map(Vec::from_iter))
+                .await
+                .map_err(std::io::Error::other)
+                .and_then(|addrs| addrs)
+                .map_err(|e| WebhookError::Resolve(host.to_string(), e))?;
+
+        let allowed = self.contains(host);
+        let addrs: Vec<SocketAddr> = addrs
+            .into_iter()
+            .filter(|addr| allowed || is_public(addr.ip()))
+            .collect();
+        if addrs.is_empty() {
+            return Err(WebhookError::InvalidUrl(format!(
+                "{} does not resolve to a public address",
+                host
+            )));
+        }
+        Ok(addrs)
+    }
+}
+
+/// Resolver for the HTTP client, so a host that passed
+/// [`WebhookSender::validate_url`] cannot later resolve to a private address.
+struct PublicResolver(AllowedHosts);
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allowed_hosts = self.0.clone();
+        Box::pin(async move {
+            let addrs = allowed_hosts.resolve(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// HMAC-SHA256 of `<timestamp>.<body>`.
+fn mac(secret: &[u8], timestamp: u64, body: &[u8]) -> Hmac<Sha256> {
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC key of any length");
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body);
+    mac
+}
+
+/// Signature of `body` sent at `timestamp` (Unix seconds), in the form sent
+/// in [`SIGNATURE_HEADER`].
+pub fn sign(secret: &[u8], timestamp: u64, body: &[u8]) -> String {
+    let digest = mac(secret, timestamp, body).finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("t={},sha256={}", timestamp, hex)
+}
+
+/// Check a [`SIGNATURE_HEADER`] value in constant time. Signatures made
+/// more than `tolerance` away from the current time are rejected.
+pub fn verify(secret: &[u8], body: &[u8], signature: &str, tolerance: Duration) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    verify_at(secret, body, signature, now, tolerance)
+}
+
+fn verify_at(secret: &[u8], body: &[u8], signature: &str, now: u64, tolerance: Duration) -> bool {
+    let Some((timestamp, hex)) = signature.split_once(',') else {
+        return false;
+    };
+    let Some(timestamp) = timestamp
+        .strip_prefix("t=")
+        .and_then(|t| t.parse::<u64>().ok())
+    else {
+        return false;
+    };
+    let Some(hex) = hex.strip_prefix("sha256=") else {
+        return false;
+    };
+    if now.abs_diff(timestamp) > tolerance.as_secs() || hex.len() != 64 || !hex.is_ascii() {
+        return false;
+    }
+    let bytes: Option<Vec<u8>> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect();
+    bytes.is_some_and(|bytes| mac(secret, timestamp, body).verify_slice(&bytes).is_ok())
+}
+
+/// Body of a webhook request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    /// `task.completed` or `task.failed`
+    pub event: String,
+    pub task_id: String,
+    pub status: TaskStatus,
+    pub original_filename: String,
+    /// Unix time in milliseconds when the task was created
+    pub created_at: u64,
+    /// The transcript of a completed task
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<Transcript>,
+}
+
+impl WebhookPayload {
+    /// Payload for a finished task, or `None` if the task is still running
+    /// or was cancelled.
+    pub fn for_task(task: &Task, transcript: Option<Transcript>) -> Option<Self> {
+        let event = match task.status {
+            TaskStatus::Completed => "task.completed",
+            TaskStatus::Failed(_) => "task.failed",
+            _ => return None,
+        };
+        Some(Self {
+            event: event.to_string(),
+            task_id: task.id.clone(),
+            status: task.status.clone(),
+            original_filename: task.original_filename.clone(),
+            created_at: task.created_at,
+            transcript,
+        })
+    }
+}
+
+/// One attempt to deliver a webhook, as recorded on the task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    /// 1 for the first attempt
+    pub attempt: u32,
+    /// Unix time in milliseconds when the attempt was made
+    pub at: u64,
+    /// HTTP status of the response, if one was received
+    pub status: Option<u16>,
+    /// Why the attempt failed; `None` on success
+    pub error: Option<String>,
+}
+
+impl DeliveryAttempt {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Options for [`WebhookSender`].
+#[derive(Debug, Clone)]
+pub struct WebhookParams {
+    /// Attempts before giving up, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled after every failure
+    pub initial_backoff: Duration,
+    /// Upper bound on the wait between attempts
+    pub max_backoff: Duration,
+    /// Timeout of a single request
+    pub timeout: Duration,
+    /// Hosts that may receive webhooks even though they are or resolve to
+    /// loopback, private or link-local addresses, e.g. `hooks.internal` or
+    /// `10.0.0.5`
+    pub allowed_hosts: Vec<String>,
+}
+
+impl Default for WebhookParams {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(60),
+            timeout: Duration::from_secs(10),
+            allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+/// Delivers signed webhook requests.
+#[derive(Clone)]
+pub struct WebhookSender {
+    client: reqwest::Client,
+    secret: Vec<u8>,
+    params: WebhookParams,
+    allowed_hosts: AllowedHosts,
+}
+
+impl WebhookSender {
+    pub fn new(secret: impl Into<Vec<u8>>, params: WebhookParams) -> Result<Self, WebhookError> {
+        let allowed_hosts = AllowedHosts(Arc::new(params.allowed_hosts.clone()));
+        // A redirect could lead to a private address, so it counts as a failure
+        let client = reqwest::Client::builder()
+            .timeout(params.timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicResolver(allowed_hosts.clone())))
+            .build()?;
+        Ok(Self {
+            client,
+            secret: secret.into(),
+            params,
+            allowed_hosts,
+        })
+    }
+
+    /// Check that `url` can be used as a callback: an absolute http(s) URL
+    /// whose host is a public address or resolves to one, unless it is in
+    /// [`WebhookParams::allowed_hosts`].
+    pub async fn validate_url(&self, url: &str) -> Result<(), WebhookError> {
+        let parsed = parse_url(url)?;
+        let host = parsed.host_str().unwrap_or_default();
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if self.allowed_hosts.contains(host) {
+            return Ok(());
+        }
+        match host.parse::<IpAddr>() {
+            Ok(ip) if is_public(ip) => Ok(()),
+            Ok(_) => Err(WebhookError::InvalidUrl(format!(
+                "{}: {} is not a public address",
+                url, host
+            ))),
+            Err(_) => {
+                let port = parsed.port_or_known_default().unwrap_or(80);
+                self.allowed_hosts.resolve(host, port).await.map(|_| ())
+            }
+        }
+    }
+
+    /// Whether the webhook of `task` still has to be sent: the task finished
+    /// with a callback URL, no attempt succeeded and attempts are left.
+    pub fn is_pending(&self, task: &Task) -> bool {
+        task.callback_url.is_some()
+            && matches!(task.status, TaskStatus::Completed | TaskStatus::Failed(_))
+            && !task.deliveries.iter().any(DeliveryAttempt::succeeded)
+            && (task.deliveries.len() as u32) < self.params.max_attempts
+    }
+
+    /// POST `payload` to `url`, retrying until a 2xx response or
+    /// `max_attempts` failures. `previous` are the attempts recorded before,
+    /// e.g. by a server that restarted since; they count toward
+    /// `max_attempts`. `on_attempt` is called after every new attempt.
+    pub async fn deliver(
+        &self,
+        url: &str,
+        payload: &WebhookPayload,
+        previous: &[DeliveryAttempt],
+        mut on_attempt: impl FnMut(&DeliveryAttempt),
+    ) -> Result<(), WebhookError> {
+        if previous.iter().any(DeliveryAttempt::succeeded) {
+            return Ok(());
+        }
+        self.validate_url(url).await?;
+        let body = serde_json::to_vec(payload)?;
+
+        let mut backoff = self.params.initial_backoff;
+        let mut last_error = previous
+            .last()
+            .and_then(|attempt| attempt.error.clone())
+            .unwrap_or_default();
+        for attempt in previous.len() as u32 + 1..=self.params.max_attempts {
+            let at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64);
+            // Signed per attempt, so retries carry a fresh timestamp
+            let signature = sign(&self.secret, at / 1000, &body);
+            let response = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_HEADER, &payload.event)
+                .body(body.clone())
+                .send()
+                .await;
+
+            let (status, error) = match response {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16()), None)
+                }
+                Ok(response) => (
+                    Some(response.status().as_u16()),
+                    Some(format!("Unexpected response status {}", response.status())),
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            let record = DeliveryAttempt {
+                attempt,
+                at,
+                status,
+                error,
+            };
+            on_attempt(&record);
+            match record.error {
+                None => return Ok(()),
+                Some(error) => last_error = error,
+            }
+
+            if attempt < self.params.max_attempts {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(self.params.max_backoff);
+            }
+        }
+
+        Err(WebhookError::Exhausted {
+            attempts: self.params.max_attempts,
+            last_error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Signature header and body of a request received by the stub
+    type Received = (String, Vec<u8>);
+
+    /// Serve one request per status in `statuses`, returning every request
+    /// received.
+    fn stub_server(statuses: Vec<u16>) -> (String, std::thread::JoinHandle<Vec<Received>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut signature = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(": ") {
+                        if name.eq_ignore_ascii_case(SIGNATURE_HEADER) {
+                            signature = value.to_string();
+                        } else if name.eq_ignore_ascii_case("content-length") {
+                            length = value.parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                requests.push((signature, body));
+
+                let response = format!(
+                    "HTTP/1.1 {} Stub\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn payload() -> WebhookPayload {
+        let mut task = Task::new(
+            "abc".to_string(),
+            "meeting.mp3".to_string(),
+            "uploads/abc".into(),
+        );
+        task.status = TaskStatus::Failed("Decoding failed".to_string());
+        WebhookPayload::for_task(&task, None).unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        // HMAC-SHA256 of "1700000000.body" with key "secret"
+        let signature = sign(b"secret", 1_700_000_000, b"body");
+        let mut expected = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        expected.update(b"1700000000.body");
+        let hex: String = expected
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(signature, format!("t=1700000000,sha256={}", hex));
+
+        let tolerance = Duration::from_secs(300);
+        let now = 1_700_000_100;
+        assert!(verify_at(b"secret", b"body", &signature, now, tolerance));
+        assert!(!verify_at(
+            b"secret",
+            b"tampered",
+            &signature,
+            now,
+            tolerance
+        ));
+        assert!(!verify_at(b"other", b"body", &signature, now, tolerance));
+        // Replayed later, or with the timestamp moved forward
+        assert!(!verify_at(
+            b"secret",
+            b"body",
+            &signature,
+            now + 3600,
+            tolerance
+        ));
+        let moved = signature.replace("t=1700000000", "t=1700003600");
+        assert!(!verify_at(
+            b"secret",
+            b"body",
+            &moved,
+            now + 3600,
+            tolerance
+        ));
+        assert!(!verify_at(b"secret", b"body", "sha256=zz", now, tolerance));
+    }
+
+    #[test]
+    fn test_payload() {
+        let task = Task::new("abc".to_string(), "a.mp3".to_string(), "uploads/abc".into());
+        assert!(WebhookPayload::for_task(&task, None).is_none());
+        assert_eq!(payload().event, "task.failed");
+    }
+
+    #[tokio::test]
+    async fn test_validate_url_rejects_private_hosts() {
+        let sender = WebhookSender::new("secret", WebhookParams::default()).unwrap();
+        assert!(sender
+            .validate_url("https://93.184.216.34/hook")
+            .await
+            .is_ok());
+        assert!(sender.validate_url("ftp://example.com").await.is_err());
+        assert!(sender.validate_url("/hook").await.is_err());
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://localhost/hook",
+            "http://10.1.2.3/hook",
+            "http://192.168.0.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(
+                matches!(
+                    sender.validate_url(url).await,
+                    Err(WebhookError::InvalidUrl(_))
+                ),
+                "{} should be rejected",
+                url
+            );
+        }
+
+        let params = WebhookParams {
+            allowed_hosts: vec!["localhost".to_string(), "10.1.2.3".to_string()],
+            ..Default::default()
+        };
+        let sender = WebhookSender::new("secret", params).unwrap();
+        assert!(sender.validate_url("http://localhost/hook").await.is_ok());
+        assert!(sender.validate_url("http://10.1.2.3/hook").await.is_ok());
+        assert!(sender.validate_url("http://10.1.2.4/hook").await.is_err());
+    }
+
+    /// Parameters that let the sender reach [`stub_server`].
+    fn stub_params() -> WebhookParams {
+        WebhookParams {
+            initial_backoff: Duration::from_millis(10),
+            allowed_hosts: vec!["127.0.0.1".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deliver_retries_until_success() {
+        let (url, server) = stub_server(vec![503, 200]);
+        let sender = WebhookSender::new("secret", stub_params()).unwrap();
+
+        let mut attempts = Vec::new();
+        sender
+            .deliver(&url, &payload(), &[], |attempt| {
+                attempts.push(attempt.clone())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].status, Some(503));
+        assert!(!attempts[0].succeeded());
+        assert!(attempts[1].succeeded());
+
+        let requests = server.join().unwrap();
+        let (signature, body) = &requests[1];
+        assert!(verify(b"secret", body, signature, Duration::from_secs(60)));
+        let received: WebhookPayload = serde_json::from_slice(body).unwrap();
+        assert_eq!(received.task_id, "abc");
+    }
+
+    #[tokio::test]
+    async fn test_deliver_gives_up() {
+        let (url, server) = stub_server(vec![500, 500]);
+        let params = WebhookParams {
+            max_attempts: 2,
+            ..stub_params()
+        };
+        let sender = WebhookSender::new("secret", params).unwrap();
+
+        let result = sender.deliver(&url, &payload(), &[], |_| {}).await;
+        assert!(matches!(
+            result,
+            Err(WebhookError::Exhausted { attempts: 2, .. })
+        ));
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_deliver_resumes_after_restart() {
+        let params = WebhookParams {
+            max_attempts: 3,
+            ..stub_params()
+        };
+        let sender = WebhookSender::new("secret", params).unwrap();
+        let failed = |attempt| DeliveryAttempt {
+            attempt,
+            at: 0,
+            status: Some(500),
+            error: Some("Unexpected response status 500".to_string()),
+        };
+
+        let mut task = Task::new("abc".to_string(), "a.mp3".to_string(), "uploads/abc".into());
+        task.status = TaskStatus::Completed;
+        assert!(!sender.is_pending(&task));
+        task.callback_url = Some("http://127.0.0.1/hook".to_string());
+        task.deliveries = vec![failed(1), failed(2)];
+        assert!(sender.is_pending(&task));
+
+        // Only the attempt that is left is made
+        let (url, server) = stub_server(vec![200]);
+        let mut attempts = Vec::new();
+        sender
+            .deliver(&url, &payload(), &task.deliveries, |attempt| {
+                attempts.push(attempt.clone())
+            })
+            .await
+            .unwrap();
+        assert_eq!(server.join().unwrap().len(), 1);
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].attempt, 3);
+
+        task.deliveries.extend(attempts);
+        assert!(!sender.is_pending(&task));
+        task.deliveries = vec![failed(1), failed(2), failed(3)];
+        assert!(!sender.is_pending(&task));
+    }
+}
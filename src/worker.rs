@@ -1,15 +1,26 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
-use async_channel;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use crate::engines::whisper::{WhisperEngine, WhisperInferenceParams};
 use crate::{TranscriptionEngine, TranscriptionSegment};
 use crate::subtitle::generate_srt;
-use crate::audio::get_audio_duration;
-use rayon::prelude::*;
-use std::cmp::Ordering;
+use crate::transcript::{Transcript, TranscriptMetadata};
+use crate::audio::{load_audio_file_with_mix, MixStrategy, TARGET_SAMPLE_RATE};
+use crate::chinese::ChineseVariant;
+use crate::hallucination::HallucinationFilter;
+use crate::merge::{merge_chunks, ChunkTranscript, MergeParams};
+use crate::normalize::{normalize_segments, NormalizeParams};
+use crate::punctuation::{load_restorer, restore_segments, PunctuationParams};
+use crate::store::{StoreError, TaskStore};
+use crate::webhook::{DeliveryAttempt, WebhookPayload, WebhookSender};
+use crate::vad::{
+    detect_speech_regions, plan_chunks, plan_language_spans, ChunkPlanParams, CodeSwitchingParams, LanguageSpan,
+    PlannedChunk, SpeechRegion,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TaskStatus {
@@ -17,6 +28,44 @@ pub enum TaskStatus {
     Processing,
     Completed,
     Failed(String),
+    /// Cancelled by the client before it finished
+    Cancelled,
+}
+
+/// How far a running transcription has got.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskProgress {
+    pub chunks_done: usize,
+    pub chunks_total: usize,
+    /// Seconds of audio in the finished chunks
+    pub audio_processed: f32,
+    /// Seconds of audio in all chunks; long silences between chunks are skipped
+    pub audio_duration: f32,
+    /// Estimated seconds until all chunks are done; known once one has finished
+    pub eta_seconds: Option<f32>,
+}
+
+impl TaskProgress {
+    /// Progress before any of `chunks` has finished.
+    pub fn new(chunks: &[PlannedChunk]) -> Self {
+        Self {
+            chunks_done: 0,
+            chunks_total: chunks.len(),
+            audio_processed: 0.0,
+            audio_duration: chunks.iter().map(|chunk| chunk.end - chunk.start).sum(),
+            eta_seconds: None,
+        }
+    }
+
+    /// Count a finished chunk of `chunk_duration` seconds of audio, `elapsed`
+    /// after transcription started. The ETA extrapolates the rate so far to
+    /// the audio that is left.
+    pub fn chunk_done(&mut self, chunk_duration: f32, elapsed: Duration) {
+        self.chunks_done += 1;
+        self.audio_processed += chunk_duration;
+        let rate = elapsed.as_secs_f32() / self.audio_processed.max(f32::EPSILON);
+        self.eta_seconds = Some((self.audio_duration - self.audio_processed).max(0.0) * rate);
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -26,24 +75,108 @@ pub struct Task {
     pub original_filename: String,
     #[serde(skip)]
     pub file_path: PathBuf,
-    #[serde(skip)]
-    pub result_srt: Option<String>,
+    /// Unix time in milliseconds when the task was created
+    pub created_at: u64,
+    /// Progress of the last run, reported while processing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<TaskProgress>,
+    /// Receives a webhook once the task completes or fails
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    /// Attempts to deliver the webhook, oldest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deliveries: Vec<DeliveryAttempt>,
+}
+
+impl Task {
+    /// A new queued task for an uploaded file.
+    pub fn new(id: String, original_filename: String, file_path: PathBuf) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        Self {
+            id,
+            status: TaskStatus::Queued,
+            original_filename,
+            file_path,
+            created_at,
+            progress: None,
+            callback_url: None,
+            deliveries: Vec::new(),
+        }
+    }
+}
+
+/// Flag asking a running transcription to stop. Checked between chunks and
+/// by whisper.cpp while a chunk is decoded, so decoding stops mid-chunk.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Cancellation tokens of the tasks workers are running, shared with the
+/// server so a request can stop a task in progress.
+#[derive(Clone, Default)]
+pub struct TaskCancellations {
+    tokens: Arc<Mutex<HashMap<String, CancellationToken>>>,
 }
 
-pub type TaskMap = Arc<Mutex<HashMap<String, Task>>>;
+impl TaskCancellations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token for a task that is about to run.
+    pub fn register(&self, id: &str) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.tokens.lock().unwrap().insert(id.to_string(), token.clone());
+        token
+    }
+
+    /// Cancel a running task. Returns `false` if no worker is running it.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.tokens.lock().unwrap().get(id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget a task once it has finished.
+    pub fn unregister(&self, id: &str) {
+        self.tokens.lock().unwrap().remove(id);
+    }
+}
 
 pub struct Worker {
     worker_id: usize,
     task_receiver: async_channel::Receiver<String>,
-    tasks: TaskMap,
+    tasks: Arc<dyn TaskStore>,
     model_path: PathBuf,
+    params: ParallelTranscriptionParams,
+    cancellations: TaskCancellations,
+    webhooks: Option<WebhookSender>,
 }
 
 impl Worker {
     pub fn new(
         worker_id: usize,
         task_receiver: async_channel::Receiver<String>,
-        tasks: TaskMap,
+        tasks: Arc<dyn TaskStore>,
         model_path: PathBuf,
     ) -> Self {
         Self {
@@ -51,9 +184,30 @@ impl Worker {
             task_receiver,
             tasks,
             model_path,
+            params: ParallelTranscriptionParams::default(),
+            cancellations: TaskCancellations::default(),
+            webhooks: None,
         }
     }
 
+    /// Use `params` instead of the defaults for every task.
+    pub fn with_params(mut self, params: ParallelTranscriptionParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Register running tasks in `cancellations` so they can be cancelled.
+    pub fn with_cancellations(mut self, cancellations: TaskCancellations) -> Self {
+        self.cancellations = cancellations;
+        self
+    }
+
+    /// Send webhooks through `sender` for tasks with a callback URL.
+    pub fn with_webhooks(mut self, sender: WebhookSender) -> Self {
+        self.webhooks = Some(sender);
+        self
+    }
+
     pub async fn run(self) {
         println!("[Worker {}] Started. Waiting for tasks...", self.worker_id);
         
@@ -66,147 +220,389 @@ impl Worker {
         while let Ok(task_id) = self.task_receiver.recv().await {
             let tasks = self.tasks.clone();
             let model_path = self.model_path.clone();
+            let params = self.params.clone();
             let worker_id = self.worker_id;
 
-            // Update status to Processing
-            {
-                let mut map = tasks.lock().unwrap();
-                if let Some(task) = map.get_mut(&task_id) {
-                    task.status = TaskStatus::Processing;
-                    println!("[Worker {}] Processing task: {}", worker_id, task_id);
+            // Register before leaving Queued, so a cancel that sees
+            // Processing always finds the token
+            let cancel = self.cancellations.register(&task_id);
+
+            // Update status to Processing; deleted and cancelled tasks are skipped
+            match tasks.set_status(&task_id, TaskStatus::Processing) {
+                Ok(true) => println!("[Worker {}] Processing task: {}", worker_id, task_id),
+                Ok(false) => {
+                    // Cancelled while queued: no one else holds the record now
+                    self.cancellations.unregister(&task_id);
+                    if let Err(e) = tasks.remove(&task_id) {
+                        eprintln!("[Worker {}] Failed to remove task {}: {}", worker_id, task_id, e);
+                    }
+                    continue;
                 }
+                Err(e) => eprintln!("[Worker {}] Failed to update task {}: {}", worker_id, task_id, e),
             }
 
             // Execute transcription in a blocking thread
             let task_id_clone = task_id.clone();
-            let result = tokio::task::spawn_blocking(move || -> Result<String> {
-                let task_data = {
-                    let map = tasks.lock().unwrap();
-                    map.get(&task_id_clone).cloned().context("Task not found")?
+            let task_cancel = cancel.clone();
+            let result = tokio::task::spawn_blocking(move || -> Result<Transcript> {
+                let task_data = tasks.get(&task_id_clone)?.context("Task not found")?;
+
+                // Decoding happens in-process inside transcribe_parallel
+                let on_progress = |progress: &TaskProgress| {
+                    if let Err(e) = tasks.set_progress(&task_id_clone, progress) {
+                        eprintln!("[Worker {}] Failed to store progress of task {}: {}", worker_id, task_id_clone, e);
+                    }
                 };
+                transcribe_parallel_cancellable(&task_data.file_path, &model_path, &params, &task_cancel, on_progress)
+            }).await;
+            self.cancellations.unregister(&task_id);
+
+            // Update status based on result; the store ignores updates to cancelled tasks
+            let stored = match result {
+                _ if cancel.is_cancelled() => {
+                    // The DELETE that cancelled the task left its record to us
+                    println!("[Worker {}] Task {} cancelled.", self.worker_id, task_id);
+                    self.tasks.remove(&task_id).map(|_| false)
+                }
+                Ok(Ok(transcript)) => {
+                    println!("[Worker {}] Task {} completed successfully.", self.worker_id, task_id);
+                    self.tasks.complete(&task_id, &transcript)
+                }
+                Ok(Err(e)) => {
+                    eprintln!("[Worker {}] Task {} failed: {}", self.worker_id, task_id, e);
+                    self.tasks.set_status(&task_id, TaskStatus::Failed(e.to_string()))
+                }
+                Err(e) => {
+                    eprintln!("[Worker {}] Task {} panicked: {}", self.worker_id, task_id, e);
+                    self.tasks.set_status(&task_id, TaskStatus::Failed(format!("Worker panic: {}", e)))
+                }
+            };
+            match stored {
+                Ok(true) => {
+                    self.remove_upload(&task_id).await;
+                    self.notify(&task_id);
+                }
+                Ok(false) => {}
+                Err(e) => eprintln!("[Worker {}] Failed to store task {}: {}", self.worker_id, task_id, e),
+            }
+        }
+    }
 
-                // 1. Convert to WAV (reuse logic or call ffmpeg directly)
-                let temp_wav = task_data.file_path.with_extension("wav");
-                convert_to_wav(&task_data.file_path, &temp_wav)?;
+    /// Delete the uploaded audio of a finished task, which is not needed
+    /// once the result is stored.
+    async fn remove_upload(&self, task_id: &str) {
+        let Ok(Some(task)) = self.tasks.get(task_id) else {
+            return;
+        };
+        match tokio::fs::remove_file(&task.file_path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                eprintln!("[Worker {}] Failed to delete upload of task {}: {}", self.worker_id, task_id, e);
+            }
+            _ => {}
+        }
+    }
 
-                // 2. Parallel Transcription
-                let srt = transcribe_parallel(&temp_wav, &model_path)?;
+    /// Deliver the webhook of a finished task, if it has a callback URL.
+    fn notify(&self, task_id: &str) {
+        if let Some(sender) = &self.webhooks {
+            spawn_webhook(self.tasks.clone(), sender.clone(), task_id);
+        }
+    }
+}
 
-                // Cleanup
-                std::fs::remove_file(temp_wav).ok();
+/// Deliver the webhook of a finished task in the background, recording
+/// every attempt on the task. Attempts recorded before, e.g. by a server
+/// that restarted since, count toward the limit.
+pub fn spawn_webhook(tasks: Arc<dyn TaskStore>, sender: WebhookSender, task_id: &str) {
+    let task = match tasks.get(task_id) {
+        Ok(Some(task)) => task,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Failed to load task {}: {}", task_id, e);
+            return;
+        }
+    };
+    let Some(url) = task.callback_url.clone() else {
+        return;
+    };
+    let transcript = match tasks.result(task_id) {
+        Ok(transcript) => transcript,
+        Err(e) => {
+            eprintln!("Failed to load result of task {}: {}", task_id, e);
+            return;
+        }
+    };
+    let Some(payload) = WebhookPayload::for_task(&task, transcript) else {
+        return;
+    };
+
+    let task_id = task_id.to_string();
+    tokio::spawn(async move {
+        let result = sender
+            .deliver(&url, &payload, &task.deliveries, |attempt| {
+                if let Err(e) = tasks.record_delivery(&task_id, attempt) {
+                    eprintln!("Failed to record delivery for task {}: {}", task_id, e);
+                }
+            })
+            .await;
+        match result {
+            Ok(()) => println!("Delivered webhook for task {}", task_id),
+            Err(e) => eprintln!("Webhook for task {} failed: {}", task_id, e),
+        }
+    });
+}
 
-                Ok(srt)
-            }).await;
+/// Resume the webhooks of finished tasks that were not delivered yet, e.g.
+/// because the server stopped during the retries. Returns how many were
+/// resumed.
+pub fn resume_webhooks(tasks: &Arc<dyn TaskStore>, sender: &WebhookSender) -> Result<usize, StoreError> {
+    let pending: Vec<Task> = tasks.list()?.into_iter().filter(|task| sender.is_pending(task)).collect();
+    for task in &pending {
+        spawn_webhook(tasks.clone(), sender.clone(), &task.id);
+    }
+    Ok(pending.len())
+}
 
-            // Update status based on result
-            let mut map = self.tasks.lock().unwrap();
-            if let Some(task) = map.get_mut(&task_id) {
-                match result {
-                    Ok(Ok(srt_content)) => {
-                        task.status = TaskStatus::Completed;
-                        task.result_srt = Some(srt_content);
-                        println!("[Worker {}] Task {} completed successfully.", self.worker_id, task_id);
-                    }
-                    Ok(Err(e)) => {
-                        task.status = TaskStatus::Failed(e.to_string());
-                        eprintln!("[Worker {}] Task {} failed: {}", self.worker_id, task_id, e);
-                    }
+/// Transcribe one chunk. With code-switching, the chunk is split into spans
+/// of one language and each span is transcribed with that language set.
+/// `thread` only labels log lines, which go to stderr so the transcription
+/// worker process can keep stdout for its responses.
+///
+/// Returns the chunk transcript and the duration transcribed per language.
+pub fn transcribe_chunk(
+    engine: &mut WhisperEngine,
+    thread: usize,
+    samples: &[f32],
+    regions: &[SpeechRegion],
+    chunk: &PlannedChunk,
+    params: &WhisperInferenceParams,
+    code_switching: Option<&CodeSwitchingParams>,
+) -> Result<(ChunkTranscript, Vec<(String, f32)>)> {
+    let slice = |start: f32, end: f32| {
+        let first = (start * TARGET_SAMPLE_RATE as f32) as usize;
+        let last = ((end * TARGET_SAMPLE_RATE as f32) as usize).min(samples.len());
+        &samples[first.min(last)..last]
+    };
+
+    let spans = match code_switching {
+        Some(code_switching) => {
+            let chunk_regions: Vec<SpeechRegion> = regions
+                .iter()
+                .filter(|r| r.end > chunk.start && r.start < chunk.end)
+                .map(|r| SpeechRegion {
+                    start: r.start.max(chunk.start),
+                    end: r.end.min(chunk.end),
+                })
+                .collect();
+            let spans = plan_language_spans(&chunk_regions, chunk.start, chunk.end, code_switching, |region| {
+                match engine.detect_language(slice(region.start, region.end)) {
+                    Ok(ranked) => ranked
+                        .into_iter()
+                        .find(|l| code_switching.languages.is_empty() || code_switching.languages.contains(&l.language))
+                        .map(|l| l.language),
                     Err(e) => {
-                        task.status = TaskStatus::Failed(format!("Worker panic: {}", e));
-                        eprintln!("[Worker {}] Task {} panicked: {}", self.worker_id, task_id, e);
+                        eprintln!("[Thread {}] Language detection failed at {:.2}s: {}", thread, region.start, e);
+                        None
                     }
                 }
-            }
+            });
+            let languages: Vec<&str> = spans.iter().map(|s| s.language.as_deref().unwrap_or("auto")).collect();
+            eprintln!("[Thread {}] Chunk {} language spans: {}", thread, chunk.index, languages.join(", "));
+            spans
+        }
+        None => vec![LanguageSpan {
+            start: chunk.start,
+            end: chunk.end,
+            language: None,
+        }],
+    };
+
+    let mut segments = Vec::new();
+    let mut languages = Vec::new();
+    for span in spans {
+        let mut span_params = params.clone();
+        if span.language.is_some() {
+            span_params.language = span.language.clone();
+        }
+        let transcription = engine
+            .transcribe_samples(slice(span.start, span.end).to_vec(), Some(span_params))
+            .map_err(|e| anyhow::anyhow!("Transcription of chunk {} failed: {}", chunk.index, e))?;
+
+        for retry in engine.last_retries() {
+            eprintln!(
+                "[Thread {}] Re-decoded repetition loop at {:.2}s-{:.2}s in chunk {} ({} attempt(s), {})",
+                thread,
+                span.start + retry.start,
+                span.start + retry.end,
+                chunk.index,
+                retry.attempts,
+                if retry.resolved { "resolved" } else { "kept least repetitive" }
+            );
+        }
+        if let Some(language) = transcription.language {
+            languages.push((language, span.end - span.start));
+        }
+        // Relative to the chunk; overlaps between chunks are resolved when merging
+        for mut segment in transcription.segments.unwrap_or_default() {
+            segment.shift(span.start - chunk.start);
+            segments.push(segment);
         }
     }
-}
 
-// Helper: FFmpeg conversion (Synchronous for blocking thread)
-fn convert_to_wav(input: &PathBuf, output: &PathBuf) -> Result<()> {
-    let status = std::process::Command::new("ffmpeg")
-        .arg("-i")
-        .arg(input)
-        .arg("-ar")
-        .arg("16000")
-        .arg("-ac")
-        .arg("1")
-        .arg("-c:a")
-        .arg("pcm_s16le")
-        .arg(output)
-        .arg("-y")
-        .status()
-        .context("Failed to run ffmpeg")?;
-
-    if !status.success() {
-        anyhow::bail!("FFmpeg conversion failed");
-    }
-    Ok(())
+    Ok((ChunkTranscript::from_relative(chunk.start, chunk.end, segments), languages))
 }
 
-struct Chunk {
-    index: usize,
-    start_time: f64,
-    end_time: f64,
-    temp_file: PathBuf,
+/// Language spoken for the longest total time, from `(language, seconds)` pairs.
+pub fn dominant_language(languages: &[(String, f32)]) -> Option<String> {
+    let mut totals: HashMap<&str, f32> = HashMap::new();
+    for (language, seconds) in languages {
+        *totals.entry(language.as_str()).or_default() += seconds;
+    }
+    totals
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(a.0)))
+        .map(|(language, _)| language.to_string())
 }
 
-pub fn transcribe_parallel(wav_path: &PathBuf, model_path: &PathBuf) -> Result<String> {
-    let duration = get_audio_duration(wav_path).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+/// Options for [`transcribe_parallel_with_params`].
+#[derive(Debug, Clone)]
+pub struct ParallelTranscriptionParams {
+    /// Number of engine instances transcribing chunks concurrently
+    pub num_threads: usize,
+    /// Bounds used when planning chunk boundaries
+    pub chunking: ChunkPlanParams,
+    /// Silero VAD model; the energy detector is used when `None`
+    pub vad_model: Option<PathBuf>,
+    /// How multi-channel recordings are reduced to mono
+    pub mix: MixStrategy,
+    /// Convert Chinese text to this variant (e.g. Simplified to Taiwan Traditional)
+    pub chinese_variant: Option<ChineseVariant>,
+    /// Removes hallucinated text after merging; `None` disables filtering
+    pub hallucination_filter: Option<HallucinationFilter>,
+    /// Decoding parameters used for every chunk
+    pub whisper: WhisperInferenceParams,
+    /// Detect the language of every speech region and transcribe each
+    /// language separately, for recordings that mix languages
+    pub code_switching: Option<CodeSwitchingParams>,
+    /// Restores punctuation in segments that have none
+    pub punctuation: Option<PunctuationParams>,
+    /// Normalizes spacing, punctuation width and numbers after conversion
+    pub normalize: Option<NormalizeParams>,
+}
 
-    // Configuration
-    let chunk_duration = 300.0; // 5 minutes
-    let overlap = 10.0; // 10 seconds overlap
-    let num_threads = 3;
+impl Default for ParallelTranscriptionParams {
+    fn default() -> Self {
+        Self {
+            num_threads: 3,
+            chunking: ChunkPlanParams::default(),
+            vad_model: None,
+            mix: MixStrategy::default(),
+            chinese_variant: None,
+            hallucination_filter: Some(HallucinationFilter::default()),
+            whisper: WhisperInferenceParams::default(),
+            code_switching: None,
+            punctuation: None,
+            normalize: None,
+        }
+    }
+}
 
-    let mut chunks = Vec::new();
-    let mut current_time = 0.0;
-    let mut index = 0;
+/// Transcribe a recording in parallel chunks and return it as SRT.
+pub fn transcribe_parallel(audio_path: &Path, model_path: &Path) -> Result<String> {
+    let segments =
+        transcribe_parallel_with_params(audio_path, model_path, &ParallelTranscriptionParams::default())?;
+    Ok(generate_srt(&segments))
+}
 
-    // Create chunks plan
-    while current_time < duration {
-        let start = current_time;
-        let end = (start + chunk_duration).min(duration);
-        
-        let temp_file = wav_path.with_file_name(format!("{}_chunk_{}.wav", wav_path.file_stem().unwrap().to_string_lossy(), index));
+/// Transcribe a recording in parallel chunks and return the merged segments.
+pub fn transcribe_parallel_with_params(
+    audio_path: &Path,
+    model_path: &Path,
+    params: &ParallelTranscriptionParams,
+) -> Result<Vec<TranscriptionSegment>> {
+    Ok(transcribe_parallel_transcript(audio_path, model_path, params)?.to_segments())
+}
 
-        chunks.push(Chunk {
-            index,
-            start_time: start,
-            end_time: end,
-            temp_file,
-        });
+/// Transcribe a recording in parallel chunks and return a [`Transcript`]
+/// with engine, model and duration metadata.
+pub fn transcribe_parallel_transcript(
+    audio_path: &Path,
+    model_path: &Path,
+    params: &ParallelTranscriptionParams,
+) -> Result<Transcript> {
+    transcribe_parallel_cancellable(audio_path, model_path, params, &CancellationToken::new(), |_| {})
+}
 
-        current_time += chunk_duration;
-        index += 1;
+/// [`transcribe_parallel_transcript`] that stops early with an error once
+/// `cancel` is set. Chunks being decoded are aborted as well.
+///
+/// `on_progress` is called once the chunks are planned and again after each
+/// chunk finishes.
+pub fn transcribe_parallel_cancellable(
+    audio_path: &Path,
+    model_path: &Path,
+    params: &ParallelTranscriptionParams,
+    cancel: &CancellationToken,
+    mut on_progress: impl FnMut(&TaskProgress),
+) -> Result<Transcript> {
+    let PlannedAudio {
+        samples,
+        duration,
+        regions,
+        chunks,
+    } = plan_audio(audio_path, params)?;
+    if cancel.is_cancelled() {
+        anyhow::bail!("Transcription cancelled");
     }
-
-    println!("Splitting audio into {} chunks for parallel processing...", chunks.len());
+    let started = Instant::now();
+    let mut progress = TaskProgress::new(&chunks);
+    on_progress(&progress);
+    let samples = Arc::new(samples);
+    let regions = Arc::new(regions);
+
+    println!(
+        "Found {} speech regions, splitting audio into {} chunks for parallel processing...",
+        regions.len(),
+        chunks.len()
+    );
 
     // Use std::sync::mpsc for job distribution and result collection
-    let (job_tx, job_rx) = std::sync::mpsc::channel();
+    let (job_tx, job_rx) = std::sync::mpsc::channel::<PlannedChunk>();
     let job_rx = Arc::new(Mutex::new(job_rx)); // Share receiver among threads
     let (result_tx, result_rx) = std::sync::mpsc::channel();
 
-    // Spawn workers
+    // Spawn workers (never more than there are chunks)
     let mut handles = Vec::new();
-    for i in 0..num_threads {
+    for i in 0..params.num_threads.clamp(1, chunks.len().max(1)) {
         let job_rx = job_rx.clone();
         let result_tx = result_tx.clone();
-        let model_path = model_path.clone();
-        let wav_path = wav_path.clone();
+        let model_path = model_path.to_path_buf();
+        let samples = samples.clone();
+        let regions = regions.clone();
+        let mut whisper_params = params.whisper.clone();
+        whisper_params.abort = Some(cancel.0.clone());
+        let code_switching = params.code_switching.clone();
+        let cancel = cancel.clone();
         
-        handles.push(std::thread::spawn(move || {
+        handles.push(std::thread::spawn(move || -> Result<()> {
+            if cancel.is_cancelled() {
+                return Ok(());
+            }
             println!("[Thread {}] Loading model...", i);
             let mut engine = WhisperEngine::new();
             if let Err(e) = engine.load_model(&model_path) {
                 eprintln!("[Thread {}] Failed to load model: {}", i, e);
-                return;
+                anyhow::bail!("Failed to load model {}: {}", model_path.display(), e);
             }
             println!("[Thread {}] Model loaded. Ready for tasks.", i);
 
             loop {
+                if cancel.is_cancelled() {
+                    break;
+                }
+
                 // Fetch next job
-                let chunk: Chunk = {
+                let chunk = {
                     let rx = job_rx.lock().unwrap();
                     match rx.recv() {
                         Ok(job) => job,
@@ -214,67 +610,21 @@ pub fn transcribe_parallel(wav_path: &PathBuf, model_path: &PathBuf) -> Result<S
                     }
                 };
 
-                // Process chunk
-                // 1. Extract chunk using FFmpeg
-                let phys_start = (chunk.start_time - overlap).max(0.0);
-                let phys_end = (chunk.end_time + overlap).min(duration);
-                let phys_duration = phys_end - phys_start;
-
-                let status = std::process::Command::new("ffmpeg")
-                    .arg("-i")
-                    .arg(&wav_path)
-                    .arg("-ss")
-                    .arg(format!("{:.3}", phys_start))
-                    .arg("-t")
-                    .arg(format!("{:.3}", phys_duration))
-                    .arg("-c")
-                    .arg("copy") // Fast copy since it's already WAV
-                    .arg(&chunk.temp_file)
-                    .arg("-y")
-                    .output();
-
-                match status {
-                    Ok(output) if output.status.success() => {
-                        // 2. Transcribe
-                        let params = WhisperInferenceParams::default();
-                        match engine.transcribe_file(&chunk.temp_file, Some(params)) {
-                            Ok(transcription) => {
-                                // 3. Cleanup temp file
-                                std::fs::remove_file(&chunk.temp_file).ok();
-
-                                // 4. Process segments (Filter and Offset)
-                                let mut valid_segments = Vec::new();
-                                if let Some(segments) = transcription.segments {
-                                    for mut seg in segments {
-                                        // Adjust timestamp to global time (seconds)
-                                        seg.start += phys_start as f32;
-                                        seg.end += phys_start as f32;
-
-                                        // Filter based on core range (seconds)
-                                        let core_start = chunk.start_time as f32;
-                                        let core_end = chunk.end_time as f32;
-
-                                        // Simple inclusion check: if the segment's midpoint is within the core range
-                                        let midpoint = (seg.start + seg.end) / 2.0;
-                                        if midpoint >= core_start && midpoint < core_end {
-                                            valid_segments.push(seg);
-                                        }
-                                    }
-                                }
-                                result_tx.send(Ok(valid_segments)).ok();
-                            }
-                            Err(e) => {
-                                result_tx.send(Err(anyhow::anyhow!("Transcription failed: {}", e))).ok();
-                            }
-                        }
-                    }
-                    _ => {
-                        result_tx.send(Err(anyhow::anyhow!("FFmpeg extraction failed"))).ok();
-                    }
-                }
+                let result = transcribe_chunk(
+                    &mut engine,
+                    i,
+                    &samples,
+                    &regions,
+                    &chunk,
+                    &whisper_params,
+                    code_switching.as_ref(),
+                );
+                result_tx.send((chunk.end - chunk.start, result)).ok();
             }
+            Ok(())
         }));
     }
+    drop(result_tx); // Only workers hold senders, so a dead pool can't hang collection
 
     // Send jobs
     let total_chunks = chunks.len();
@@ -284,23 +634,284 @@ pub fn transcribe_parallel(wav_path: &PathBuf, model_path: &PathBuf) -> Result<S
     drop(job_tx); // Close channel so workers know when to stop
 
     // Collect results
-    let mut all_segments = Vec::new();
+    let mut chunk_transcripts = Vec::new();
+    let mut chunk_languages = Vec::new();
+    let mut last_error = None;
+    let mut done = 0;
     for _ in 0..total_chunks {
-        match result_rx.recv() {
-            Ok(Ok(segments)) => all_segments.extend(segments),
-            Ok(Err(e)) => eprintln!("Chunk processing error: {}", e),
-            Err(e) => eprintln!("Failed to receive result: {}", e),
+        let result = result_rx.recv().map(|(chunk_duration, result)| {
+            // Failed chunks count as done
+            done += 1;
+            progress.chunk_done(chunk_duration, started.elapsed());
+            on_progress(&progress);
+            result
+        });
+        match result {
+            Ok(Ok((transcript, languages))) => {
+                chunk_languages.extend(languages);
+                chunk_transcripts.push(transcript);
+            }
+            Ok(Err(e)) => {
+                eprintln!("Chunk processing error: {}", e);
+                last_error = Some(e);
+            }
+            // Every thread stopped (cancelled, or none could load the model)
+            // before all chunks were sent back
+            Err(_) => break,
         }
     }
 
     // Wait for threads to finish
+    let mut thread_error = None;
     for handle in handles {
-        handle.join().unwrap();
+        if let Err(e) = handle.join().unwrap() {
+            thread_error.get_or_insert(e);
+        }
+    }
+    if cancel.is_cancelled() {
+        anyhow::bail!("Transcription cancelled");
+    }
+    // An empty transcript would be indistinguishable from silence, so a
+    // transcription where nothing was decoded fails instead
+    if done < total_chunks {
+        return Err(thread_error.unwrap_or_else(|| {
+            anyhow::anyhow!("Transcription threads stopped after {} of {} chunks", done, total_chunks)
+        }));
+    }
+    if chunk_transcripts.is_empty() {
+        if let Some(e) = last_error {
+            return Err(e.context(format!("All {} chunks failed", total_chunks)));
+        }
+    }
+
+    finish_transcript(chunk_transcripts, &chunk_languages, &regions, duration, model_path, params)
+}
+
+/// Transcribe a recording chunk by chunk with an already loaded `engine`,
+/// like [`transcribe_parallel_transcript`] but without loading a model per
+/// thread. Suited to short recordings and servers that keep engines loaded.
+pub fn transcribe_with_engine(
+    engine: &mut WhisperEngine,
+    audio_path: &Path,
+    model_path: &Path,
+    params: &ParallelTranscriptionParams,
+) -> Result<Transcript> {
+    let PlannedAudio {
+        samples,
+        duration,
+        regions,
+        chunks,
+    } = plan_audio(audio_path, params)?;
+
+    let mut chunk_transcripts = Vec::new();
+    let mut chunk_languages = Vec::new();
+    let mut last_error = None;
+    for chunk in &chunks {
+        let result = transcribe_chunk(
+            engine,
+            0,
+            &samples,
+            &regions,
+            chunk,
+            &params.whisper,
+            params.code_switching.as_ref(),
+        );
+        match result {
+            Ok((transcript, languages)) => {
+                chunk_languages.extend(languages);
+                chunk_transcripts.push(transcript);
+            }
+            Err(e) => {
+                eprintln!("Chunk processing error: {}", e);
+                last_error = Some(e);
+            }
+        }
+    }
+    if chunk_transcripts.is_empty() {
+        if let Some(e) = last_error {
+            return Err(e.context(format!("All {} chunks failed", chunks.len())));
+        }
+    }
+
+    finish_transcript(chunk_transcripts, &chunk_languages, &regions, duration, model_path, params)
+}
+
+/// Decoded audio with its speech regions and planned chunks
+struct PlannedAudio {
+    samples: Vec<f32>,
+    duration: f32,
+    regions: Vec<SpeechRegion>,
+    chunks: Vec<PlannedChunk>,
+}
+
+/// Decode a recording once (every chunk is a slice of the same 16 kHz mono
+/// buffer) and plan chunks that are cut in silence
+fn plan_audio(audio_path: &Path, params: &ParallelTranscriptionParams) -> Result<PlannedAudio> {
+    let samples = load_audio_file_with_mix(audio_path, params.mix).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let duration = samples.len() as f32 / TARGET_SAMPLE_RATE as f32;
+    let regions = detect_speech_regions(&samples, params.vad_model.as_deref())
+        .context("Voice activity detection failed")?;
+    let chunks = plan_chunks(&regions, duration, &params.chunking);
+    Ok(PlannedAudio {
+        samples,
+        duration,
+        regions,
+        chunks,
+    })
+}
+
+/// Merge chunk transcripts and apply the filters and text passes in `params`
+fn finish_transcript(
+    chunk_transcripts: Vec<ChunkTranscript>,
+    chunk_languages: &[(String, f32)],
+    regions: &[SpeechRegion],
+    duration: f32,
+    model_path: &Path,
+    params: &ParallelTranscriptionParams,
+) -> Result<Transcript> {
+    // Stitch chunks back together in time order
+    let mut segments = merge_chunks(chunk_transcripts, &MergeParams::default());
+    let language = dominant_language(chunk_languages);
+    if let Some(filter) = &params.hallucination_filter {
+        // Per-language rules only apply to the language that was spoken
+        let (kept, report) = filter.filter_in_language(segments, Some(regions), language.as_deref());
+        if !report.is_empty() {
+            println!("Removed {} hallucinations:\n{}", report.removals.len(), report);
+        }
+        segments = kept;
+    }
+    if let Some(punctuation) = &params.punctuation {
+        let mut restorer = load_restorer(punctuation).context("Failed to load punctuation model")?;
+        let inserted = restore_segments(restorer.as_mut(), &mut segments)?;
+        println!("Restored {} punctuation marks", inserted);
+    }
+    if let Some(variant) = params.chinese_variant {
+        variant.converter().convert_segments(&mut segments);
+    }
+    if let Some(normalize) = &params.normalize {
+        normalize_segments(&mut segments, normalize);
     }
+    let metadata = TranscriptMetadata {
+        engine: Some("whisper".to_string()),
+        model: Some(model_path.display().to_string()),
+        language,
+        duration: Some(duration),
+    };
+    Ok(Transcript::from_segments(&segments, metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryTaskStore;
+
+    /// Store that cancels its task like `DELETE /tasks/{id}` once the chunks
+    /// are planned, and logs every status it accepts.
+    struct CancellingStore {
+        inner: MemoryTaskStore,
+        cancellations: TaskCancellations,
+        statuses: Mutex<Vec<TaskStatus>>,
+    }
+
+    impl TaskStore for CancellingStore {
+        fn insert(&self, task: Task) -> Result<(), StoreError> {
+            self.inner.insert(task)
+        }
 
-    // Sort by start time
-    all_segments.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(Ordering::Equal));
+        fn get(&self, id: &str) -> Result<Option<Task>, StoreError> {
+            self.inner.get(id)
+        }
+
+        fn set_status(&self, id: &str, status: TaskStatus) -> Result<bool, StoreError> {
+            let stored = self.inner.set_status(id, status.clone())?;
+            if stored {
+                self.statuses.lock().unwrap().push(status);
+            }
+            Ok(stored)
+        }
 
-    // Generate SRT
-    Ok(generate_srt(&all_segments))
+        fn set_progress(&self, id: &str, progress: &TaskProgress) -> Result<bool, StoreError> {
+            let stored = self.inner.set_progress(id, progress)?;
+            self.set_status(id, TaskStatus::Cancelled)?;
+            assert!(self.cancellations.cancel(id), "task should be running");
+            Ok(stored)
+        }
+
+        fn complete(&self, id: &str, transcript: &Transcript) -> Result<bool, StoreError> {
+            let stored = self.inner.complete(id, transcript)?;
+            if stored {
+                self.statuses.lock().unwrap().push(TaskStatus::Completed);
+            }
+            Ok(stored)
+        }
+
+        fn record_delivery(&self, id: &str, attempt: &DeliveryAttempt) -> Result<bool, StoreError> {
+            self.inner.record_delivery(id, attempt)
+        }
+
+        fn result(&self, id: &str) -> Result<Option<Transcript>, StoreError> {
+            self.inner.result(id)
+        }
+
+        fn remove(&self, id: &str) -> Result<Option<Task>, StoreError> {
+            self.inner.remove(id)
+        }
+
+        fn list(&self) -> Result<Vec<Task>, StoreError> {
+            self.inner.list()
+        }
+    }
+
+    #[test]
+    fn test_progress_counts_chunks_and_estimates_time_left() {
+        let chunks: Vec<PlannedChunk> = [(0.0, 10.0), (12.0, 32.0), (40.0, 70.0)]
+            .iter()
+            .enumerate()
+            .map(|(index, &(start, end))| PlannedChunk { index, start, end })
+            .collect();
+        let mut progress = TaskProgress::new(&chunks);
+        assert_eq!(progress.chunks_total, 3);
+        assert_eq!(progress.audio_duration, 60.0);
+        assert_eq!(progress.eta_seconds, None);
+
+        // 10 s of audio took 5 s, so the 50 s left take 25 s
+        progress.chunk_done(10.0, Duration::from_secs(5));
+        assert_eq!(progress.chunks_done, 1);
+        assert_eq!(progress.audio_processed, 10.0);
+        assert_eq!(progress.eta_seconds, Some(25.0));
+
+        progress.chunk_done(30.0, Duration::from_secs(10));
+        assert_eq!(progress.chunks_done, 2);
+        assert_eq!(progress.audio_processed, 40.0);
+        assert_eq!(progress.eta_seconds, Some(5.0));
+
+        progress.chunk_done(20.0, Duration::from_secs(12));
+        assert_eq!(progress.chunks_done, 3);
+        assert_eq!(progress.eta_seconds, Some(0.0));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_task_is_never_completed() {
+        let cancellations = TaskCancellations::new();
+        let store = Arc::new(CancellingStore {
+            inner: MemoryTaskStore::new(),
+            cancellations: cancellations.clone(),
+            statuses: Mutex::new(Vec::new()),
+        });
+        let task = Task::new("jfk".to_string(), "jfk.wav".to_string(), PathBuf::from("samples/jfk.wav"));
+        store.insert(task).unwrap();
+
+        let (sender, receiver) = async_channel::unbounded();
+        sender.send("jfk".to_string()).await.unwrap();
+        drop(sender);
+        // Cancelled before any thread loads the model, so none is needed
+        Worker::new(0, receiver, store.clone(), PathBuf::from("missing-model.bin"))
+            .with_cancellations(cancellations)
+            .run()
+            .await;
+
+        assert_eq!(*store.statuses.lock().unwrap(), vec![TaskStatus::Processing, TaskStatus::Cancelled]);
+        // The worker let go of the task, so its record is gone
+        assert!(store.get("jfk").unwrap().is_none());
+    }
 }
@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+use transcribe_rs::audio::{
+    decode_audio_file, downmix, load_audio_file, read_wav_samples, resample, write_wav_samples,
+    MixStrategy, TARGET_SAMPLE_RATE,
+};
+
+#[test]
+fn test_decode_wav_matches_hound() {
+    let audio_path = PathBuf::from("samples/jfk.wav");
+
+    let decoded = decode_audio_file(&audio_path).expect("Failed to decode");
+    assert_eq!(decoded.sample_rate, TARGET_SAMPLE_RATE);
+    assert_eq!(decoded.channels, 1);
+    assert!((decoded.duration() - 11.0).abs() < 0.01);
+
+    let loaded = load_audio_file(&audio_path).expect("Failed to load");
+    let reference = read_wav_samples(&audio_path).expect("Failed to read with hound");
+    assert_eq!(loaded.len(), reference.len());
+}
+
+#[test]
+fn test_write_and_reload_roundtrip() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let wav_path = dir.path().join("tone.wav");
+
+    let samples: Vec<f32> = (0..TARGET_SAMPLE_RATE)
+        .map(|i| (i as f32 * 440.0 * 2.0 * std::f32::consts::PI / TARGET_SAMPLE_RATE as f32).sin() * 0.5)
+        .collect();
+    write_wav_samples(&wav_path, &samples).expect("Failed to write WAV");
+
+    let reloaded = load_audio_file(&wav_path).expect("Failed to reload WAV");
+    assert_eq!(reloaded.len(), samples.len());
+    let max_err = samples
+        .iter()
+        .zip(&reloaded)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0f32, f32::max);
+    assert!(max_err < 1e-3, "max error {}", max_err);
+}
+
+#[test]
+fn test_stereo_24bit_44k_is_converted() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let wav_path = dir.path().join("stereo.wav");
+
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: 44100,
+        bits_per_sample: 24,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(&wav_path, spec).expect("Failed to create WAV");
+    // Two seconds: left channel carries a tone, right channel is silent
+    for i in 0..88200 {
+        let tone = (i as f32 * 440.0 * 2.0 * std::f32::consts::PI / 44100.0).sin() * 0.5;
+        writer.write_sample((tone * 8_388_607.0) as i32).unwrap();
+        writer.write_sample(0i32).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let samples = read_wav_samples(&wav_path).expect("Failed to read stereo WAV");
+    assert_eq!(samples.len(), 32000);
+
+    // Averaging halves the tone's amplitude
+    let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    assert!((peak - 0.25).abs() < 0.02, "peak {}", peak);
+}
+
+#[test]
+fn test_float_48k_wav_is_converted() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let wav_path = dir.path().join("float.wav");
+
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 48000,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(&wav_path, spec).expect("Failed to create WAV");
+    for _ in 0..48000 {
+        writer.write_sample(0.1f32).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let samples = load_audio_file(&wav_path).expect("Failed to load float WAV");
+    assert_eq!(samples.len(), 16000);
+    // Away from the edges the DC level must survive resampling
+    assert!((samples[8000] - 0.1).abs() < 1e-3);
+}
+
+#[test]
+fn test_downmix_strategies() {
+    let interleaved = [0.1, -0.8, 0.3, 0.6, -0.2, 0.9];
+
+    let average = downmix(&interleaved, 2, MixStrategy::Average).unwrap();
+    assert_eq!(average.len(), 3);
+    assert!((average[0] - -0.35).abs() < 1e-6);
+
+    let right = downmix(&interleaved, 2, MixStrategy::Channel(1)).unwrap();
+    assert_eq!(right, vec![-0.8, 0.6, 0.9]);
+
+    let loudest = downmix(&interleaved, 2, MixStrategy::Loudest).unwrap();
+    assert_eq!(loudest, right);
+
+    assert!(downmix(&interleaved, 2, MixStrategy::Channel(2)).is_err());
+
+    assert_eq!("loudest".parse(), Ok(MixStrategy::Loudest));
+    assert_eq!("Right".parse(), Ok(MixStrategy::Channel(1)));
+    assert_eq!("2".parse(), Ok(MixStrategy::Channel(2)));
+    assert!("both".parse::<MixStrategy>().is_err());
+}
+
+#[test]
+fn test_resample_identity_and_length() {
+    let samples = vec![0.25f32; 1000];
+    assert_eq!(resample(&samples, 16000, 16000).unwrap(), samples);
+
+    let upsampled = resample(&samples, 8000, 16000).unwrap();
+    assert_eq!(upsampled.len(), 2000);
+}